@@ -0,0 +1,115 @@
+use crate::sops::recipient_matches;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Libreta local del repositorio, buscada desde el directorio actual hacia arriba
+pub const LOCAL_ADDRESS_BOOK: &str = ".agesmith/recipients.toml";
+
+/// Persona del equipo con sus llaves públicas (age, SSH o de plugins)
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Contact {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+    #[serde(default)]
+    pub keys: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub groups: Vec<String>,
+}
+
+impl Contact {
+    /// Etiqueta legible: `alice <alice@example.com>`
+    pub fn display(&self, name: &str) -> String {
+        match &self.email {
+            Some(email) => format!("{} <{}>", name, email),
+            None => name.to_string(),
+        }
+    }
+}
+
+/// Libreta de recipients (`[people.<nombre>]`): da nombre a las llaves públicas
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AddressBook {
+    #[serde(default)]
+    pub people: BTreeMap<String, Contact>,
+}
+
+impl AddressBook {
+    pub fn parse(content: &str) -> Result<Self> {
+        Ok(toml::from_str(content)?)
+    }
+
+    /// Une la libreta global (`~/.config/agesmith/recipients.toml`) y la del repositorio;
+    /// la del repositorio tiene prioridad si un nombre aparece en ambas
+    pub fn load(dir: &Path) -> Result<Self> {
+        let mut book = Self::default();
+        let paths = [global_path(), local_path(dir)];
+        for path in paths.into_iter().flatten() {
+            let content = fs::read_to_string(&path)
+                .with_context(|| format!("No se pudo leer {}", path.display()))?;
+            let other = Self::parse(&content)
+                .with_context(|| format!("Libreta de recipients inválida: {}", path.display()))?;
+            book.people.extend(other.people);
+        }
+        Ok(book)
+    }
+
+    /// Persona dueña de la llave pública
+    pub fn find(&self, recipient: &str) -> Option<(&String, &Contact)> {
+        self.people
+            .iter()
+            .find(|(_, c)| c.keys.iter().any(|k| recipient_matches(k, recipient)))
+    }
+
+    pub fn label(&self, recipient: &str) -> Option<String> {
+        self.find(recipient)
+            .map(|(name, contact)| contact.display(name))
+    }
+
+    pub fn groups(&self) -> Vec<String> {
+        let mut groups: Vec<String> = self
+            .people
+            .values()
+            .flat_map(|c| c.groups.iter().cloned())
+            .collect();
+        groups.sort();
+        groups.dedup();
+        groups
+    }
+
+    /// Llaves de una persona (por nombre o email) o de un grupo (`@grupo`)
+    pub fn resolve(&self, query: &str) -> Vec<String> {
+        let mut keys: Vec<String> = match query.strip_prefix('@') {
+            Some(group) => self
+                .people
+                .values()
+                .filter(|c| c.groups.iter().any(|g| g == group))
+                .flat_map(|c| c.keys.iter().cloned())
+                .collect(),
+            None => self
+                .people
+                .iter()
+                .filter(|(name, c)| *name == query || c.email.as_deref() == Some(query))
+                .flat_map(|(_, c)| c.keys.iter().cloned())
+                .collect(),
+        };
+        keys.sort();
+        keys.dedup();
+        keys
+    }
+}
+
+pub fn global_path() -> Option<PathBuf> {
+    let path = dirs::home_dir()?.join(".config/agesmith/recipients.toml");
+    path.exists().then_some(path)
+}
+
+pub fn local_path(dir: &Path) -> Option<PathBuf> {
+    dir.ancestors()
+        .map(|d| d.join(LOCAL_ADDRESS_BOOK))
+        .find(|p| p.exists())
+}
@@ -0,0 +1,102 @@
+use crate::perms::append_private;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Acciones registradas en el log de auditoría. Nunca se registran valores.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditAction {
+    FileOpened,
+    ValueRevealed,
+    ValueCopied,
+    KeyEdited,
+    KeyAdded,
+    KeyDeleted,
+    KeyRotated,
+    FileSaved,
+    RecipientsChanged,
+}
+
+impl AuditAction {
+    pub const ALL: [AuditAction; 9] = [
+        AuditAction::FileOpened,
+        AuditAction::ValueRevealed,
+        AuditAction::ValueCopied,
+        AuditAction::KeyEdited,
+        AuditAction::KeyAdded,
+        AuditAction::KeyDeleted,
+        AuditAction::KeyRotated,
+        AuditAction::FileSaved,
+        AuditAction::RecipientsChanged,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuditAction::FileOpened => "file_opened",
+            AuditAction::ValueRevealed => "value_revealed",
+            AuditAction::ValueCopied => "value_copied",
+            AuditAction::KeyEdited => "key_edited",
+            AuditAction::KeyAdded => "key_added",
+            AuditAction::KeyDeleted => "key_deleted",
+            AuditAction::KeyRotated => "key_rotated",
+            AuditAction::FileSaved => "file_saved",
+            AuditAction::RecipientsChanged => "recipients_changed",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEvent {
+    pub timestamp: DateTime<Utc>,
+    pub action: AuditAction,
+    pub file: PathBuf,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+}
+
+impl AuditEvent {
+    pub fn new(action: AuditAction, file: &Path, key: Option<&str>) -> Self {
+        Self {
+            timestamp: Utc::now(),
+            action,
+            // Rutas absolutas para poder filtrar sin importar desde dónde se abrió
+            file: fs::canonicalize(file).unwrap_or_else(|_| file.to_path_buf()),
+            key: key.map(String::from),
+        }
+    }
+}
+
+pub fn audit_log_path() -> Result<PathBuf> {
+    Ok(dirs::home_dir()
+        .context("Could not get home directory")?
+        .join(".config/agesmith/audit.log"))
+}
+
+/// Agrega un evento como una línea JSON al final del log
+/// El log revela rutas y nombres de llaves: solo el usuario puede leerlo
+pub fn append_event(event: &AuditEvent) -> Result<()> {
+    let line = format!("{}\n", serde_json::to_string(event)?);
+    append_private(&audit_log_path()?, line.as_bytes())
+}
+
+/// Parsea un log JSON-lines ignorando líneas vacías o corruptas
+pub fn parse_events(content: &str) -> Vec<AuditEvent> {
+    content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect()
+}
+
+pub fn load_events() -> Result<Vec<AuditEvent>> {
+    let log_path = audit_log_path()?;
+    if !log_path.exists() {
+        return Ok(Vec::new());
+    }
+    Ok(parse_events(&fs::read_to_string(&log_path)?))
+}
@@ -1,6 +1,8 @@
+use crate::generator::PasswordPolicy;
+use crate::perms::write_private;
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
-use std::{fs, path::PathBuf};
+use std::{collections::BTreeMap, fs, path::PathBuf};
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Language {
@@ -61,12 +63,33 @@ pub struct Config {
     pub message_timeout_seconds: u64,
     #[serde(default = "default_language")]
     pub language: String,
+    #[serde(default = "default_true")]
+    pub audit_log: bool,
+    #[serde(default = "default_expiry_warning_days")]
+    pub expiry_warning_days: i64,
+    /// Políticas de contraseñas con nombre (`[policies.<nombre>]`)
+    #[serde(default)]
+    pub policies: BTreeMap<String, PasswordPolicy>,
+    /// Hash de la frase para desbloquear la sesión (`sha256$rondas$sal$hash`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lock_passphrase_hash: Option<String>,
+    /// Archivos de identidades age adicionales al de SOPS (admite `~/`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub identity_files: Vec<String>,
 }
 
 fn default_language() -> String {
     "en".to_string()
 }
 
+fn default_true() -> bool {
+    true
+}
+
+fn default_expiry_warning_days() -> i64 {
+    14
+}
+
 impl Default for Config {
     fn default() -> Self {
         Self {
@@ -74,6 +97,11 @@ impl Default for Config {
             auto_lock_minutes: 15,
             message_timeout_seconds: 3,
             language: "en".to_string(),
+            audit_log: true,
+            expiry_warning_days: 14,
+            policies: BTreeMap::new(),
+            lock_passphrase_hash: None,
+            identity_files: Vec::new(),
         }
     }
 }
@@ -122,12 +150,8 @@ pub fn save_favorites(favorites: &[PathBuf]) -> Result<()> {
         .context("Could not get home directory")?
         .join(".config/agesmith/favorites.json");
 
-    if let Some(parent) = fav_path.parent() {
-        fs::create_dir_all(parent)?;
-    }
-
     let content = serde_json::to_string_pretty(favorites)?;
-    fs::write(&fav_path, content)?;
+    write_private(&fav_path, content.as_bytes())?;
     Ok(())
 }
 
@@ -136,11 +160,8 @@ pub fn save_config(config: &Config) -> Result<()> {
         .context("Could not get home directory")?
         .join(".config/agesmith/config.toml");
 
-    if let Some(parent) = config_path.parent() {
-        fs::create_dir_all(parent)?;
-    }
-
     let content = toml::to_string_pretty(config)?;
-    fs::write(&config_path, content)?;
+    // Incluye el hash de la frase de bloqueo: solo legible por el usuario
+    write_private(&config_path, content.as_bytes())?;
     Ok(())
 }
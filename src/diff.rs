@@ -0,0 +1,121 @@
+/// Diff de líneas (LCS) con salida en formato unificado, usado por el diff-driver de git
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DiffLine {
+    Same(String),
+    Removed(String),
+    Added(String),
+}
+
+pub fn diff_lines(old: &[String], new: &[String]) -> Vec<DiffLine> {
+    let (n, m) = (old.len(), new.len());
+
+    // lcs[i][j] = longitud de la subsecuencia común más larga de old[i..] y new[j..]
+    let mut lcs = vec![vec![0u32; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut result = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < n && j < m {
+        if old[i] == new[j] {
+            result.push(DiffLine::Same(old[i].clone()));
+            i += 1;
+            j += 1;
+        } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+            result.push(DiffLine::Removed(old[i].clone()));
+            i += 1;
+        } else {
+            result.push(DiffLine::Added(new[j].clone()));
+            j += 1;
+        }
+    }
+    result.extend(old[i..].iter().cloned().map(DiffLine::Removed));
+    result.extend(new[j..].iter().cloned().map(DiffLine::Added));
+    result
+}
+
+/// Genera un diff unificado con `context` líneas de contexto alrededor de cada cambio.
+/// Devuelve una cadena vacía si no hay diferencias.
+pub fn unified_diff(
+    old_label: &str,
+    new_label: &str,
+    old: &[String],
+    new: &[String],
+    context: usize,
+) -> String {
+    let ops = diff_lines(old, new);
+    let changes: Vec<usize> = ops
+        .iter()
+        .enumerate()
+        .filter(|(_, op)| !matches!(op, DiffLine::Same(_)))
+        .map(|(i, _)| i)
+        .collect();
+
+    if changes.is_empty() {
+        return String::new();
+    }
+
+    // Agrupar cambios cercanos en hunks [inicio, fin)
+    let mut hunks: Vec<(usize, usize)> = Vec::new();
+    for &idx in &changes {
+        let start = idx.saturating_sub(context);
+        let end = (idx + context + 1).min(ops.len());
+        match hunks.last_mut() {
+            Some(last) if start <= last.1 => last.1 = end,
+            _ => hunks.push((start, end)),
+        }
+    }
+
+    let mut out = format!("--- {}\n+++ {}\n", old_label, new_label);
+    for (start, end) in hunks {
+        let old_before = ops[..start]
+            .iter()
+            .filter(|op| !matches!(op, DiffLine::Added(_)))
+            .count();
+        let new_before = ops[..start]
+            .iter()
+            .filter(|op| !matches!(op, DiffLine::Removed(_)))
+            .count();
+        let old_count = ops[start..end]
+            .iter()
+            .filter(|op| !matches!(op, DiffLine::Added(_)))
+            .count();
+        let new_count = ops[start..end]
+            .iter()
+            .filter(|op| !matches!(op, DiffLine::Removed(_)))
+            .count();
+
+        // Convención de diff: un rango vacío apunta a la línea anterior
+        let old_start = if old_count == 0 {
+            old_before
+        } else {
+            old_before + 1
+        };
+        let new_start = if new_count == 0 {
+            new_before
+        } else {
+            new_before + 1
+        };
+
+        out.push_str(&format!(
+            "@@ -{},{} +{},{} @@\n",
+            old_start, old_count, new_start, new_count
+        ));
+        for op in &ops[start..end] {
+            match op {
+                DiffLine::Same(line) => out.push_str(&format!(" {}\n", line)),
+                DiffLine::Removed(line) => out.push_str(&format!("-{}\n", line)),
+                DiffLine::Added(line) => out.push_str(&format!("+{}\n", line)),
+            }
+        }
+    }
+    out
+}
@@ -0,0 +1,323 @@
+use crate::keystore::{display_relative, save_backup_path, sops_files, tree_files};
+use crate::sops::{insecure_identity_files, parse_sops_recipients};
+use crate::sopsconfig::{find_config, Resolution, SopsConfig};
+use anyhow::{Context, Result};
+use regex::Regex;
+use serde::Serialize;
+use serde_json::Value;
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Tipo de problema que detecta `agesmith doctor`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Check {
+    /// `.sops.yaml` que no se puede leer
+    InvalidConfig,
+    /// Archivo SOPS sin `.sops.yaml` o sin regla que le corresponda
+    NoMatchingRule,
+    /// Recipients del archivo distintos de los de su regla
+    RecipientDrift,
+    /// Claves que el `encrypted_regex` de la regla cubre pero están en claro
+    UnencryptedValue,
+    /// Copia `.bak`/`.tmp` en claro de un archivo SOPS que dejó un guardado interrumpido
+    PlaintextLeftover,
+    /// Archivo de identidades legible por el grupo u otros usuarios
+    ReadableIdentity,
+}
+
+impl Check {
+    fn label(self) -> &'static str {
+        match self {
+            Self::InvalidConfig => "CONFIG",
+            Self::NoMatchingRule => "NO RULE",
+            Self::RecipientDrift => "DRIFT",
+            Self::UnencryptedValue => "PLAINTEXT",
+            Self::PlaintextLeftover => "LEFTOVER",
+            Self::ReadableIdentity => "IDENTITY",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Finding {
+    pub check: Check,
+    pub path: PathBuf,
+    pub detail: String,
+}
+
+/// Resultado de revisar un árbol de archivos SOPS
+#[derive(Debug, Serialize)]
+pub struct DoctorReport {
+    pub root: PathBuf,
+    pub sops_files: usize,
+    pub findings: Vec<Finding>,
+}
+
+impl DoctorReport {
+    /// Revisa el árbol de `root`. La raíz se resuelve a una ruta absoluta para que la
+    /// búsqueda del `.sops.yaml` pueda subir por encima del directorio de trabajo.
+    pub fn scan(root: &Path, identity_files: &[String]) -> Result<Self> {
+        let root = root
+            .canonicalize()
+            .with_context(|| format!("No se pudo abrir {}", root.display()))?;
+        let mut findings = Vec::new();
+        // Cada `.sops.yaml` se lee una sola vez; `None` si no es válido
+        let mut configs: HashMap<PathBuf, Option<SopsConfig>> = HashMap::new();
+
+        // Los restos de guardados se revisan aparte
+        let all_files = sops_files(std::slice::from_ref(&root));
+        let sops_paths: Vec<&Path> = all_files.iter().map(|(path, _)| path.as_path()).collect();
+        let files: Vec<_> = all_files
+            .iter()
+            .filter(|(path, _)| !is_leftover(path, &sops_paths))
+            .collect();
+        for (path, recipients) in &files {
+            let Some(config_path) = path.parent().and_then(find_config) else {
+                findings.push(Finding {
+                    check: Check::NoMatchingRule,
+                    path: path.clone(),
+                    detail: "no hay .sops.yaml".to_string(),
+                });
+                continue;
+            };
+            let config = configs.entry(config_path.clone()).or_insert_with(|| {
+                SopsConfig::load(&config_path)
+                    .map_err(|e| {
+                        findings.push(Finding {
+                            check: Check::InvalidConfig,
+                            path: config_path.clone(),
+                            detail: format!("{:#}", e),
+                        })
+                    })
+                    .ok()
+            });
+            let Some(config) = config.clone() else {
+                continue;
+            };
+
+            let resolution = Resolution::new(config_path, config, path);
+            let Some(rule) = resolution.rule() else {
+                findings.push(Finding {
+                    check: Check::NoMatchingRule,
+                    path: path.clone(),
+                    detail: format!(
+                        "{} no coincide con ninguna regla de {}",
+                        resolution.relative,
+                        resolution.config_path.display()
+                    ),
+                });
+                continue;
+            };
+
+            if let Some(drift) = resolution.drift(recipients) {
+                findings.push(Finding {
+                    check: Check::RecipientDrift,
+                    path: path.clone(),
+                    detail: format!(
+                        "regla {}: faltan [{}], sobran [{}]",
+                        resolution.rule.unwrap_or_default() + 1,
+                        drift.missing.join(", "),
+                        drift.extra.join(", ")
+                    ),
+                });
+            }
+
+            let pattern = rule.encrypted_regex.as_deref().map(Regex::new);
+            if let (Some(Ok(re)), Ok(content)) = (pattern, fs::read_to_string(path)) {
+                let keys = plaintext_keys(path, &content, &re);
+                if !keys.is_empty() {
+                    findings.push(Finding {
+                        check: Check::UnencryptedValue,
+                        path: path.clone(),
+                        detail: format!("sin cifrar: {}", keys.join(", ")),
+                    });
+                }
+            }
+        }
+
+        for path in tree_files(&root)
+            .into_iter()
+            .filter(|p| is_leftover(p, &sops_paths))
+        {
+            if fs::read_to_string(&path).is_ok_and(|content| is_plaintext(&content)) {
+                findings.push(Finding {
+                    check: Check::PlaintextLeftover,
+                    path,
+                    detail: "copia en claro de un guardado interrumpido".to_string(),
+                });
+            }
+        }
+
+        for (path, mode) in insecure_identity_files(identity_files)? {
+            findings.push(Finding {
+                check: Check::ReadableIdentity,
+                path,
+                detail: format!("permisos {:o}: otros usuarios pueden leerlo", mode),
+            });
+        }
+
+        Ok(Self {
+            root,
+            sops_files: files.len(),
+            findings,
+        })
+    }
+}
+
+/// Copia que deja un guardado de un archivo SOPS del mismo directorio: el `.bak` de
+/// AgeSmith (`secrets.env.bak` de `secrets.env`) o un `.tmp` con su nombre, de SOPS o de
+/// `write_private_atomic`
+fn is_leftover(path: &Path, sops_paths: &[&Path]) -> bool {
+    let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+    if !name.ends_with(".bak") && !name.contains(".tmp") {
+        return false;
+    }
+    sops_paths
+        .iter()
+        .filter(|sops| **sops != path && sops.parent() == path.parent())
+        .any(|sops| {
+            let sops_name = sops.file_name().and_then(|n| n.to_str()).unwrap_or("");
+            save_backup_path(sops) == path || (name.contains(".tmp") && name.contains(sops_name))
+        })
+}
+
+fn is_plaintext(content: &str) -> bool {
+    parse_sops_recipients(content).is_empty() && !content.contains("ENC[")
+}
+
+/// Claves cubiertas por `encrypted_regex` (ella o alguna clave que la contiene) cuyo valor
+/// no está cifrado
+pub fn plaintext_keys(path: &Path, content: &str, encrypted_regex: &Regex) -> Vec<String> {
+    let file_name = path.file_name().and_then(|s| s.to_str()).unwrap_or("");
+    let ext = path.extension().and_then(|s| s.to_str());
+    let mut found = Vec::new();
+
+    if file_name == ".env" || ext == Some("env") || file_name == ".ini" || ext == Some("ini") {
+        let mut section = String::new();
+        for line in content.lines().map(str::trim) {
+            if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                section = name.to_string();
+                continue;
+            }
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+            let key = key.trim();
+            if line.starts_with(['#', ';']) || section == "sops" || key.starts_with("sops_") {
+                continue;
+            }
+            let covered = encrypted_regex.is_match(key)
+                || (!section.is_empty() && encrypted_regex.is_match(&section));
+            if covered && !value.trim().starts_with("ENC[") {
+                found.push(if section.is_empty() {
+                    key.to_string()
+                } else {
+                    format!("{}.{}", section, key)
+                });
+            }
+        }
+        return found;
+    }
+
+    let parsed = serde_json::from_str::<Value>(content)
+        .ok()
+        .or_else(|| serde_yaml::from_str::<Value>(content).ok());
+    if let Some(Value::Object(map)) = parsed {
+        for (key, value) in map.iter().filter(|(k, _)| k.as_str() != "sops") {
+            collect_plaintext(
+                value,
+                key,
+                encrypted_regex.is_match(key),
+                encrypted_regex,
+                &mut found,
+            );
+        }
+    }
+    found
+}
+
+fn collect_plaintext(
+    value: &Value,
+    path: &str,
+    covered: bool,
+    encrypted_regex: &Regex,
+    found: &mut Vec<String>,
+) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map {
+                let covered = covered || encrypted_regex.is_match(key);
+                collect_plaintext(
+                    value,
+                    &format!("{}.{}", path, key),
+                    covered,
+                    encrypted_regex,
+                    found,
+                );
+            }
+        }
+        Value::Array(items) => {
+            for (i, item) in items.iter().enumerate() {
+                collect_plaintext(
+                    item,
+                    &format!("{}[{}]", path, i),
+                    covered,
+                    encrypted_regex,
+                    found,
+                );
+            }
+        }
+        Value::String(s) if s.starts_with("ENC[") => {}
+        Value::Null => {}
+        _ if covered => found.push(path.to_string()),
+        _ => {}
+    }
+}
+
+/// `agesmith doctor [--json] [DIR]`
+///
+/// Termina con error si encuentra algún problema, para CI.
+pub fn run_doctor(args: &[String], identity_files: &[String]) -> Result<()> {
+    let mut json = false;
+    let mut root = None;
+    for arg in args {
+        match arg.as_str() {
+            "--json" => json = true,
+            flag if flag.starts_with("--") => anyhow::bail!("Opción desconocida: {}", flag),
+            _ if root.is_some() => anyhow::bail!("Solo se puede revisar un directorio"),
+            _ => root = Some(PathBuf::from(arg)),
+        }
+    }
+    let root = root.unwrap_or_else(|| PathBuf::from("."));
+    if !root.is_dir() {
+        anyhow::bail!("{} no es un directorio", root.display());
+    }
+
+    let report = DoctorReport::scan(&root, identity_files)?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        for finding in &report.findings {
+            println!(
+                "{:<10} {}  {}",
+                finding.check.label(),
+                display_relative(&report.root, &finding.path),
+                finding.detail
+            );
+        }
+        println!(
+            "{} problemas en {} archivos SOPS",
+            report.findings.len(),
+            report.sops_files
+        );
+    }
+
+    if !report.findings.is_empty() {
+        anyhow::bail!("La revisión de SOPS encontró problemas");
+    }
+    Ok(())
+}
@@ -1,9 +1,13 @@
+use crate::audit::AuditAction;
 use crate::generator::TokenFormat;
+use crate::secmem::set_core_dumps;
 use crate::sops::decrypt_and_parse;
+use crate::sopsconfig::{SopsConfig, RULE_FIELDS};
 use crate::state::{App, InputMode};
 use anyhow::Result;
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 use regex::Regex;
+use zeroize::Zeroize;
 
 pub fn handle_key_event(app: &mut App, key: KeyEvent) -> Result<bool> {
     if app.message_timestamp.is_some() {
@@ -13,6 +17,21 @@ pub fn handle_key_event(app: &mut App, key: KeyEvent) -> Result<bool> {
     // Update activity on any key press
     app.update_activity();
 
+    // Bloqueo manual desde cualquier pantalla
+    if key.code == KeyCode::Char('l')
+        && key.modifiers.contains(KeyModifiers::CONTROL)
+        && app.input_mode != InputMode::Locked
+    {
+        // Bloquear borra los valores descifrados: los cambios sin guardar se perderían
+        if app.is_modified {
+            app.set_temp_message(app.i18n.t("save_before_lock").to_string());
+        } else {
+            app.lock();
+            app.set_temp_message(app.i18n.t("session_locked_manual").to_string());
+        }
+        return Ok(false);
+    }
+
     match app.input_mode {
         InputMode::Explorer => handle_explorer_keys(app, key),
         InputMode::Secrets => handle_secrets_keys(app, key),
@@ -37,9 +56,58 @@ pub fn handle_key_event(app: &mut App, key: KeyEvent) -> Result<bool> {
         InputMode::SelectingSopsKeys => handle_selecting_sops_keys_keys(app, key),
         InputMode::EditingSopsConfig => handle_editing_sops_config_keys(app, key),
         InputMode::SelectingSopsTemplate => handle_selecting_sops_template_keys(app, key),
+        InputMode::ViewingHistory => handle_viewing_history_keys(app, key),
+        InputMode::ViewingAudit => handle_viewing_audit_keys(app, key),
+        InputMode::EditingMetadata => handle_editing_metadata_keys(app, key),
+        InputMode::Locked => handle_locked_keys(app, key),
+        InputMode::SettingLockPassphrase => handle_setting_lock_passphrase_keys(app, key),
+        InputMode::UnlockingIdentities => handle_unlocking_identities_keys(app, key),
+        InputMode::ImportingKeys
+        | InputMode::ExportingKey
+        | InputMode::EditingKeyComment
+        | InputMode::BackingUpKeys => handle_key_io_keys(app, key),
+        InputMode::EnteringKeyPassphrase => handle_key_passphrase_keys(app, key),
+        InputMode::FixingPermissions => handle_fixing_permissions_keys(app, key),
+        InputMode::ViewingKeyUsage => handle_viewing_key_usage_keys(app, key),
     }
 }
 
+/// Texto pegado (bracketed paste) en el campo de texto activo. La importación de llaves
+/// conserva los saltos de línea; el resto de campos son de una línea.
+pub fn handle_paste(app: &mut App, text: &str) {
+    app.update_activity();
+    if app.input_mode == InputMode::ImportingKeys {
+        app.key_io_buffer.push_str(text);
+        return;
+    }
+
+    let text: String = text.chars().filter(|c| !c.is_control()).collect();
+    let buffer = match app.input_mode {
+        InputMode::Editing | InputMode::AddingSecret => {
+            for c in text.chars() {
+                handle_text_input(app, KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE));
+            }
+            return;
+        }
+        InputMode::SearchingKey => &mut app.key_search_query,
+        InputMode::SearchingSecrets => &mut app.secret_search_query,
+        InputMode::CreatingFolder => &mut app.folder_name_buffer,
+        InputMode::RenamingFile => &mut app.rename_buffer,
+        InputMode::CreatingSecretFile => &mut app.new_file_name_buffer,
+        InputMode::EditingMetadata => &mut app.meta_buffers[app.editing_field],
+        InputMode::EditingSopsConfig if app.sops_field_editing => &mut app.edit_buffer,
+        InputMode::ExportingKey | InputMode::EditingKeyComment | InputMode::BackingUpKeys => {
+            &mut app.key_io_buffer
+        }
+        InputMode::Locked
+        | InputMode::SettingLockPassphrase
+        | InputMode::UnlockingIdentities
+        | InputMode::EnteringKeyPassphrase => &mut *app.lock_buffer,
+        _ => return,
+    };
+    buffer.push_str(&text);
+}
+
 fn handle_explorer_keys(app: &mut App, key: KeyEvent) -> Result<bool> {
     match key.code {
         KeyCode::Char('q') => return Ok(true),
@@ -88,6 +156,7 @@ fn handle_explorer_keys(app: &mut App, key: KeyEvent) -> Result<bool> {
             }
         }
         KeyCode::Char('m') => app.toggle_mark_file(),
+        KeyCode::Char('A') => app.open_audit_log(),
         KeyCode::Down => app.next_file(),
         KeyCode::Up => app.previous_file(),
         KeyCode::Enter => app.open_selected()?,
@@ -96,6 +165,8 @@ fn handle_explorer_keys(app: &mut App, key: KeyEvent) -> Result<bool> {
                 app.input_mode = InputMode::SelectingKey;
                 app.key_list_state
                     .select(Some(app.selected_key_index.unwrap_or(0)));
+            } else if app.pending_identity_file().is_some() {
+                app.input_mode = InputMode::UnlockingIdentities;
             }
         }
         KeyCode::Tab => {
@@ -116,10 +187,31 @@ fn handle_secrets_keys(app: &mut App, key: KeyEvent) -> Result<bool> {
             app.input_mode = InputMode::Settings;
         }
         KeyCode::Char('f') => app.toggle_favorite(),
-        KeyCode::Char('v') => app.show_values = !app.show_values,
+        KeyCode::Char('v') => app.toggle_show_values(),
+        KeyCode::Char('A') => app.open_audit_log(),
         KeyCode::Char('c') => app.copy_selected_value(),
         KeyCode::Char('C') => app.copy_selected_key(),
         KeyCode::Char('z') => app.open_value_viewer(),
+        KeyCode::Char('h') => app.open_history(),
+        KeyCode::Char('m') => app.open_metadata_editor(),
+        KeyCode::Char('g') => app.open_generator(false),
+        KeyCode::Char('r') => app.rotate_selected(),
+        KeyCode::Char('R') => {
+            if let Err(e) = app.rotate_in_marked_files() {
+                app.set_temp_message(format!("❌ Error: {}", e));
+            }
+        }
+        KeyCode::Char('u') => app.rollback_selected_rotation(),
+        KeyCode::Char('U') => {
+            if let Err(e) = app.undo_batch_rotation() {
+                app.set_temp_message(format!("❌ Error: {}", e));
+            }
+        }
+        KeyCode::Char('y') => {
+            if let Err(e) = app.rekey_to_rule() {
+                app.set_temp_message(format!("❌ Error: {}", e));
+            }
+        }
         KeyCode::Char('e') => app.edit_secret(),
         KeyCode::Char('n') => app.add_secret(),
         KeyCode::Char('d') => app.delete_secret(),
@@ -169,7 +261,8 @@ fn handle_selecting_key_keys(app: &mut App, key: KeyEvent) -> Result<bool> {
                     if let Some(file_path) = &app.file_path {
                         match decrypt_and_parse(file_path, Some(&key)) {
                             Ok(secrets) => {
-                                app.secrets = secrets;
+                                app.secrets = secrets.into();
+                                set_core_dumps(false);
                                 app.error_message = None;
                                 if !app.secrets.is_empty() {
                                     app.table_state.select(Some(0));
@@ -263,7 +356,7 @@ fn handle_viewing_value_keys(app: &mut App, key: KeyEvent) -> Result<bool> {
         }
         KeyCode::Char('j') => {
             if let Some(value) = &app.viewing_value {
-                app.viewing_value = Some(app.format_json_value(value));
+                app.viewing_value = Some(app.format_json_value(value).into());
             }
         }
         _ => {}
@@ -276,6 +369,7 @@ fn handle_editing_keys(app: &mut App, key: KeyEvent) -> Result<bool> {
         KeyCode::Esc => {
             app.editing_key_buffer.clear();
             app.editing_value_buffer.clear();
+            app.pending_public_key = None;
             app.cursor_position = 0;
             app.editing_field = 0;
             app.input_mode = InputMode::Secrets;
@@ -293,7 +387,7 @@ fn handle_editing_keys(app: &mut App, key: KeyEvent) -> Result<bool> {
             if app.editing_field == 1 {
                 // Generar directamente sin abrir modal
                 let result = crate::generator::generate_password(16, true, true);
-                app.editing_value_buffer = result.clone();
+                app.editing_value_buffer = result.clone().into();
                 app.cursor_position = app.editing_value_buffer.len();
 
                 if let Some(clipboard) = &mut app.clipboard {
@@ -307,9 +401,20 @@ fn handle_editing_keys(app: &mut App, key: KeyEvent) -> Result<bool> {
                 let filtered = app.filtered_secrets();
                 if let Some(&real_idx) = filtered.get(idx) {
                     if !app.editing_key_buffer.is_empty() {
-                        app.secrets[real_idx].0 = app.editing_key_buffer.clone();
-                        app.secrets[real_idx].1 = app.editing_value_buffer.clone();
+                        let old_key = app.secrets[real_idx].0.clone();
+                        if old_key != app.editing_key_buffer {
+                            app.metadata.rename(&old_key, &app.editing_key_buffer);
+                        }
+                        if app.secrets[real_idx].1 != *app.editing_value_buffer {
+                            app.metadata.mark_rotated(&app.editing_key_buffer);
+                        }
+                        app.secrets
+                            .set_key(real_idx, app.editing_key_buffer.clone());
+                        app.secrets
+                            .set_value(real_idx, app.editing_value_buffer.to_string());
+                        app.store_pending_public_key(&app.editing_key_buffer.clone());
                         app.is_modified = true;
+                        app.audit(AuditAction::KeyEdited, Some(&app.editing_key_buffer));
                         app.set_temp_message(app.i18n.t("updated").to_string());
                         app.editing_key_buffer.clear();
                         app.editing_value_buffer.clear();
@@ -332,6 +437,7 @@ fn handle_adding_secret_keys(app: &mut App, key: KeyEvent) -> Result<bool> {
         KeyCode::Esc => {
             app.editing_key_buffer.clear();
             app.editing_value_buffer.clear();
+            app.pending_public_key = None;
             app.cursor_position = 0;
             app.editing_field = 0;
             app.input_mode = InputMode::Secrets;
@@ -349,7 +455,7 @@ fn handle_adding_secret_keys(app: &mut App, key: KeyEvent) -> Result<bool> {
             if app.editing_field == 1 {
                 // Generar directamente sin abrir modal
                 let result = crate::generator::generate_password(16, true, true);
-                app.editing_value_buffer = result.clone();
+                app.editing_value_buffer = result.clone().into();
                 app.cursor_position = app.editing_value_buffer.len();
 
                 if let Some(clipboard) = &mut app.clipboard {
@@ -362,10 +468,13 @@ fn handle_adding_secret_keys(app: &mut App, key: KeyEvent) -> Result<bool> {
             if !app.editing_key_buffer.is_empty() {
                 app.secrets.push((
                     app.editing_key_buffer.clone(),
-                    app.editing_value_buffer.clone(),
+                    app.editing_value_buffer.to_string(),
                 ));
                 app.is_modified = true;
+                app.metadata.mark_created(&app.editing_key_buffer);
+                app.audit(AuditAction::KeyAdded, Some(&app.editing_key_buffer));
                 app.table_state.select(Some(app.secrets.len() - 1));
+                app.store_pending_public_key(&app.editing_key_buffer.clone());
                 app.set_temp_message(app.i18n.t("added").to_string());
                 app.editing_key_buffer.clear();
                 app.editing_value_buffer.clear();
@@ -457,7 +566,10 @@ fn handle_confirming_keys(app: &mut App, key: KeyEvent) -> Result<bool> {
 
 fn handle_generating_keys(app: &mut App, key: KeyEvent) -> Result<bool> {
     match key.code {
-        KeyCode::Esc => app.input_mode = InputMode::Secrets,
+        KeyCode::Esc => {
+            app.gen_rotating = false;
+            app.input_mode = InputMode::Secrets;
+        }
         KeyCode::Enter => app.generate_and_copy(),
         KeyCode::Up => {
             if app.gen_selected_option > 0 {
@@ -465,7 +577,7 @@ fn handle_generating_keys(app: &mut App, key: KeyEvent) -> Result<bool> {
             }
         }
         KeyCode::Down => {
-            if app.gen_selected_option < 1 {
+            if app.gen_selected_option < 4 {
                 app.gen_selected_option += 1;
             }
         }
@@ -478,10 +590,15 @@ fn handle_generating_keys(app: &mut App, key: KeyEvent) -> Result<bool> {
             1 => {
                 app.gen_token_format = match app.gen_token_format {
                     TokenFormat::Base64 => TokenFormat::Hex,
-                    TokenFormat::Uuid => TokenFormat::Base64,
+                    TokenFormat::Base64Url => TokenFormat::Base64,
+                    TokenFormat::Base32 => TokenFormat::Base64Url,
+                    TokenFormat::Uuid => TokenFormat::Base32,
                     TokenFormat::Hex => TokenFormat::Uuid,
                 };
             }
+            2 => app.cycle_gen_policy(false),
+            3 if app.gen_passphrase.words > 3 => app.gen_passphrase.words -= 1,
+            4 => app.cycle_gen_key_kind(false),
             _ => {}
         },
         KeyCode::Right => match app.gen_selected_option {
@@ -493,12 +610,29 @@ fn handle_generating_keys(app: &mut App, key: KeyEvent) -> Result<bool> {
             1 => {
                 app.gen_token_format = match app.gen_token_format {
                     TokenFormat::Hex => TokenFormat::Base64,
-                    TokenFormat::Base64 => TokenFormat::Uuid,
+                    TokenFormat::Base64 => TokenFormat::Base64Url,
+                    TokenFormat::Base64Url => TokenFormat::Base32,
+                    TokenFormat::Base32 => TokenFormat::Uuid,
                     TokenFormat::Uuid => TokenFormat::Hex,
                 };
             }
+            2 => app.cycle_gen_policy(true),
+            3 if app.gen_passphrase.words < 16 => app.gen_passphrase.words += 1,
+            4 => app.cycle_gen_key_kind(true),
             _ => {}
         },
+        KeyCode::Char('+') if app.gen_length < 128 => app.gen_length += 1,
+        KeyCode::Char('-') if app.gen_length > 8 => app.gen_length -= 1,
+        KeyCode::Char('s') if app.gen_selected_option == 3 => app.cycle_gen_separator(),
+        KeyCode::Char('n') if app.gen_selected_option == 3 => {
+            app.gen_passphrase.digit = !app.gen_passphrase.digit
+        }
+        KeyCode::Char('c') if app.gen_selected_option == 3 => {
+            app.gen_passphrase.capitalize = !app.gen_passphrase.capitalize
+        }
+        KeyCode::Char('p') if app.gen_selected_option == 4 => {
+            app.gen_public_sibling = !app.gen_public_sibling
+        }
         KeyCode::Char('s') => app.gen_use_special = !app.gen_use_special,
         KeyCode::Char('n') => app.gen_use_numbers = !app.gen_use_numbers,
         _ => {}
@@ -535,10 +669,15 @@ fn handle_settings_keys(app: &mut App, key: KeyEvent) -> Result<bool> {
             }
         }
         KeyCode::Down => {
-            if app.settings_selected < 3 {
+            if app.settings_selected < 4 {
                 app.settings_selected += 1;
             }
         }
+        KeyCode::Enter if app.settings_selected == 4 => {
+            app.lock_buffer = Default::default();
+            app.lock_passphrase_first = None;
+            app.input_mode = InputMode::SettingLockPassphrase;
+        }
         KeyCode::Left | KeyCode::Right | KeyCode::Enter => match app.settings_selected {
             0 => app.toggle_theme(),
             1 => app.toggle_language(),
@@ -598,9 +737,34 @@ fn handle_managing_keys_keys(app: &mut App, key: KeyEvent) -> Result<bool> {
         }
         KeyCode::Char('d') => {
             if app.key_manager_selected < app.age_keys.len() {
-                app.input_mode = InputMode::ConfirmingKeyDeletion;
+                if let Err(e) = app.prepare_key_deletion() {
+                    app.set_temp_message(format!("❌ Error: {}", e));
+                }
             }
         }
+        KeyCode::Char('c') if app.key_manager_selected < app.age_keys.len() => {
+            app.copy_selected_public_key();
+        }
+        KeyCode::Char('e') if app.key_manager_selected < app.age_keys.len() => {
+            if let Err(e) = app.start_key_comment_edit() {
+                app.set_temp_message(format!("❌ Error: {}", e));
+            }
+        }
+        KeyCode::Char('x') if app.key_manager_selected < app.age_keys.len() => {
+            if let Err(e) = app.start_key_export() {
+                app.set_temp_message(format!("❌ Error: {}", e));
+            }
+        }
+        // En la fila "Nueva llave" solo mientras no se esté escribiendo un comentario
+        KeyCode::Char('i') if app.new_key_comment.is_empty() => {
+            app.key_io_buffer.clear();
+            app.input_mode = InputMode::ImportingKeys;
+        }
+        KeyCode::Char('u') if app.new_key_comment.is_empty() => app.open_key_usage(),
+        KeyCode::Char('b') if app.new_key_comment.is_empty() => {
+            app.key_io_buffer.clear();
+            app.input_mode = InputMode::BackingUpKeys;
+        }
         KeyCode::Char(c) if app.key_manager_selected == app.age_keys.len() => {
             app.new_key_comment.push(c);
         }
@@ -615,12 +779,14 @@ fn handle_managing_keys_keys(app: &mut App, key: KeyEvent) -> Result<bool> {
 fn handle_confirming_key_deletion_keys(app: &mut App, key: KeyEvent) -> Result<bool> {
     match key.code {
         KeyCode::Char('y') | KeyCode::Char('Y') => {
+            // Puede pasar a pedir la frase del respaldo si no quedan otras llaves
+            app.input_mode = InputMode::ManagingKeys;
             if let Err(e) = app.delete_selected_age_key() {
                 app.set_temp_message(format!("❌ Error: {}", e));
             }
-            app.input_mode = InputMode::ManagingKeys;
         }
         KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => {
+            app.key_deletion_dependents.clear();
             app.input_mode = InputMode::ManagingKeys;
         }
         _ => {}
@@ -628,6 +794,78 @@ fn handle_confirming_key_deletion_keys(app: &mut App, key: KeyEvent) -> Result<b
     Ok(false)
 }
 
+fn handle_key_io_keys(app: &mut App, key: KeyEvent) -> Result<bool> {
+    match key.code {
+        KeyCode::Esc => {
+            app.key_io_buffer.zeroize();
+            app.input_mode = InputMode::ManagingKeys;
+        }
+        KeyCode::Enter => {
+            let result = match app.input_mode {
+                InputMode::ImportingKeys => app.import_keys(),
+                InputMode::ExportingKey => {
+                    app.choose_export_path();
+                    Ok(())
+                }
+                InputMode::EditingKeyComment => app.save_key_comment(),
+                _ => app.backup_age_keys(),
+            };
+            if let Err(e) = result {
+                app.key_io_buffer.zeroize();
+                app.input_mode = InputMode::ManagingKeys;
+                app.set_temp_message(format!("❌ Error: {}", e));
+            }
+        }
+        KeyCode::Backspace => {
+            app.key_io_buffer.pop();
+        }
+        KeyCode::Char(c) => app.key_io_buffer.push(c),
+        _ => {}
+    }
+    Ok(false)
+}
+
+fn handle_viewing_key_usage_keys(app: &mut App, key: KeyEvent) -> Result<bool> {
+    match key.code {
+        KeyCode::Esc | KeyCode::Char('q') => {
+            app.usage_report = None;
+            app.input_mode = InputMode::ManagingKeys;
+        }
+        KeyCode::Up => app.usage_scroll = app.usage_scroll.saturating_sub(1),
+        KeyCode::Down => app.usage_scroll = app.usage_scroll.saturating_add(1),
+        KeyCode::PageUp => app.usage_scroll = app.usage_scroll.saturating_sub(10),
+        KeyCode::PageDown => app.usage_scroll = app.usage_scroll.saturating_add(10),
+        KeyCode::Char('r') => {
+            let scroll = app.usage_scroll;
+            app.open_key_usage();
+            app.usage_scroll = scroll;
+        }
+        _ => {}
+    }
+    Ok(false)
+}
+
+fn handle_key_passphrase_keys(app: &mut App, key: KeyEvent) -> Result<bool> {
+    match key.code {
+        KeyCode::Esc => {
+            app.lock_buffer = Default::default();
+            app.key_transfer = None;
+            app.input_mode = InputMode::ManagingKeys;
+        }
+        KeyCode::Enter => {
+            if let Err(e) = app.finish_key_transfer() {
+                app.set_temp_message(format!("❌ Error: {}", e));
+            }
+        }
+        KeyCode::Backspace => {
+            app.lock_buffer.pop();
+        }
+        KeyCode::Char(c) => app.lock_buffer.push(c),
+        _ => {}
+    }
+    Ok(false)
+}
+
 fn handle_creating_folder_keys(app: &mut App, key: KeyEvent) -> Result<bool> {
     match key.code {
         KeyCode::Esc => {
@@ -696,13 +934,10 @@ fn handle_creating_secret_file_keys(app: &mut App, key: KeyEvent) -> Result<bool
             app.input_mode = InputMode::Explorer;
         }
         KeyCode::Enter => {
-            // Ir al selector de llaves SOPS
-            app.selected_sops_keys = vec![false; app.age_keys.len()];
-            if !app.age_keys.is_empty() {
-                app.selected_sops_keys[0] = true;
+            if let Err(e) = app.choose_new_file_recipients() {
+                app.set_temp_message(format!("❌ Error: {:#}", e));
+                app.input_mode = InputMode::Explorer;
             }
-            app.key_list_state.select(Some(0));
-            app.input_mode = InputMode::SelectingSopsKeys;
         }
         KeyCode::Backspace => {
             app.new_file_name_buffer.pop();
@@ -753,20 +988,18 @@ fn handle_selecting_sops_keys_keys(app: &mut App, key: KeyEvent) -> Result<bool>
         }
         KeyCode::Down => {
             if let Some(selected) = app.key_list_state.selected() {
-                if selected < app.age_keys.len() - 1 {
+                if selected + 1 < app.selected_sops_keys.len() {
                     app.key_list_state.select(Some(selected + 1));
                 }
             }
         }
         KeyCode::Char(' ') | KeyCode::Enter => {
             if let Some(selected) = app.key_list_state.selected() {
-                // Desmarcar todas las llaves
-                for i in 0..app.selected_sops_keys.len() {
-                    app.selected_sops_keys[i] = false;
-                }
-                // Marcar solo la seleccionada
-                if let Some(checked) = app.selected_sops_keys.get_mut(selected) {
-                    *checked = true;
+                // Espacio marca o desmarca; Enter sin marcas usa la opción resaltada
+                if key.code == KeyCode::Char(' ') || !app.selected_sops_keys.contains(&true) {
+                    if let Some(checked) = app.selected_sops_keys.get_mut(selected) {
+                        *checked = !*checked || key.code == KeyCode::Enter;
+                    }
                 }
 
                 // Si es Enter, crear el config y el archivo
@@ -789,38 +1022,51 @@ fn handle_selecting_sops_keys_keys(app: &mut App, key: KeyEvent) -> Result<bool>
 }
 
 fn handle_editing_sops_config_keys(app: &mut App, key: KeyEvent) -> Result<bool> {
+    if app.sops_field_editing {
+        match key.code {
+            KeyCode::Esc => {
+                app.edit_buffer.clear();
+                app.sops_field_editing = false;
+            }
+            KeyCode::Enter => app.commit_sops_field_edit(),
+            KeyCode::Backspace => {
+                app.edit_buffer.pop();
+            }
+            KeyCode::Char(c) => app.edit_buffer.push(c),
+            _ => {}
+        }
+        return Ok(false);
+    }
+
+    let rule_count = app.sops_config.creation_rules.len();
     match key.code {
         KeyCode::Esc => {
+            app.sops_config = SopsConfig::default();
+            app.sops_preview.clear();
             app.input_mode = InputMode::Explorer;
-            app.edit_buffer.clear();
         }
-        KeyCode::Enter if key.modifiers.contains(KeyModifiers::CONTROL) => {
+        KeyCode::Char('s') if key.modifiers.contains(KeyModifiers::CONTROL) => {
             if let Err(e) = app.save_sops_config() {
-                app.set_temp_message(format!("❌ Error: {}", e));
+                app.set_temp_message(format!("❌ Error: {:#}", e));
             }
         }
-        KeyCode::Enter => {
-            app.edit_buffer.insert(app.cursor_position, '\n');
-            app.cursor_position += 1;
-        }
-        KeyCode::Char(c) => {
-            app.edit_buffer.insert(app.cursor_position, c);
-            app.cursor_position += 1;
-        }
-        KeyCode::Backspace => {
-            if app.cursor_position > 0 {
-                app.edit_buffer.remove(app.cursor_position - 1);
-                app.cursor_position -= 1;
-            }
-        }
-        KeyCode::Left => {
-            if app.cursor_position > 0 {
-                app.cursor_position -= 1;
-            }
-        }
-        KeyCode::Right => {
-            if app.cursor_position < app.edit_buffer.len() {
-                app.cursor_position += 1;
+        KeyCode::Up if key.modifiers.contains(KeyModifiers::SHIFT) => app.move_sops_rule(true),
+        KeyCode::Down if key.modifiers.contains(KeyModifiers::SHIFT) => app.move_sops_rule(false),
+        KeyCode::Up => app.sops_rule_selected = app.sops_rule_selected.saturating_sub(1),
+        KeyCode::Down if app.sops_rule_selected + 1 < rule_count => {
+            app.sops_rule_selected += 1;
+        }
+        KeyCode::Tab => app.sops_field_selected = (app.sops_field_selected + 1) % RULE_FIELDS.len(),
+        KeyCode::BackTab => {
+            app.sops_field_selected =
+                (app.sops_field_selected + RULE_FIELDS.len() - 1) % RULE_FIELDS.len();
+        }
+        KeyCode::Enter => app.start_sops_field_edit(),
+        KeyCode::Char('a') => app.add_sops_rule(),
+        KeyCode::Char('d') => app.delete_sops_rule(),
+        KeyCode::Char('e') => {
+            if let Err(e) = app.edit_sops_config_externally() {
+                app.set_temp_message(format!("❌ Error: {:#}", e));
             }
         }
         _ => {}
@@ -834,7 +1080,7 @@ fn handle_confirming_key_creation_keys(app: &mut App, key: KeyEvent) -> Result<b
             if let Err(e) = crate::sops::create_age_key_file() {
                 app.set_temp_message(format!("❌ Error: {}", e));
             } else {
-                app.age_keys = crate::sops::load_age_keys()?;
+                app.reload_age_keys()?;
                 app.set_temp_message(app.i18n.t("key_created").to_string());
             }
             app.input_mode = InputMode::Explorer;
@@ -852,25 +1098,181 @@ fn handle_selecting_sops_template_keys(app: &mut App, key: KeyEvent) -> Result<b
         KeyCode::Esc => {
             app.input_mode = InputMode::Explorer;
         }
+        KeyCode::Up if app.selected_sops_template > 0 => {
+            app.selected_sops_template -= 1;
+        }
+        KeyCode::Down if app.selected_sops_template + 1 < app.sops_templates.len() => {
+            app.selected_sops_template += 1;
+        }
+        KeyCode::Enter => {
+            // Ir al selector de llaves
+            app.open_sops_key_selector();
+        }
+        _ => {}
+    }
+    Ok(false)
+}
+
+fn handle_viewing_history_keys(app: &mut App, key: KeyEvent) -> Result<bool> {
+    let count = app.filtered_history().len();
+    match key.code {
+        KeyCode::Esc | KeyCode::Char('h') => {
+            app.history_events.clear();
+            app.history_filter = None;
+            app.history_loading = None;
+            app.input_mode = InputMode::Secrets;
+        }
+        KeyCode::Char('f') => app.toggle_history_filter(),
+        KeyCode::Down => {
+            if let Some(i) = app.history_state.selected() {
+                if i + 1 < count {
+                    app.history_state.select(Some(i + 1));
+                }
+            }
+        }
         KeyCode::Up => {
-            if app.selected_sops_template > 0 {
-                app.selected_sops_template -= 1;
+            if let Some(i) = app.history_state.selected() {
+                if i > 0 {
+                    app.history_state.select(Some(i - 1));
+                }
             }
         }
+        _ => {}
+    }
+    Ok(false)
+}
+
+fn handle_viewing_audit_keys(app: &mut App, key: KeyEvent) -> Result<bool> {
+    let count = app.filtered_audit_events().len();
+    match key.code {
+        KeyCode::Esc | KeyCode::Char('A') => {
+            app.audit_events.clear();
+            app.input_mode = if app.secrets.is_empty() {
+                InputMode::Explorer
+            } else {
+                InputMode::Secrets
+            };
+        }
+        KeyCode::Char('f') => app.toggle_audit_file_filter(),
+        KeyCode::Char('a') => app.cycle_audit_action_filter(),
         KeyCode::Down => {
-            if app.selected_sops_template < 3 {
-                app.selected_sops_template += 1;
+            if let Some(i) = app.audit_state.selected() {
+                if i + 1 < count {
+                    app.audit_state.select(Some(i + 1));
+                }
             }
         }
+        KeyCode::Up => {
+            if let Some(i) = app.audit_state.selected() {
+                if i > 0 {
+                    app.audit_state.select(Some(i - 1));
+                }
+            }
+        }
+        _ => {}
+    }
+    Ok(false)
+}
+
+fn handle_locked_keys(app: &mut App, key: KeyEvent) -> Result<bool> {
+    match key.code {
+        // Salir no expone nada: los secretos ya se borraron al bloquear
+        KeyCode::Char('c') | KeyCode::Char('q')
+            if key.modifiers.contains(KeyModifiers::CONTROL) =>
+        {
+            return Ok(true)
+        }
+        KeyCode::Enter => app.try_unlock(),
+        KeyCode::Esc => app.lock_buffer = Default::default(),
+        KeyCode::Backspace => {
+            app.lock_buffer.pop();
+        }
+        KeyCode::Char(c) => app.lock_buffer.push(c),
+        _ => {}
+    }
+    Ok(false)
+}
+
+fn handle_setting_lock_passphrase_keys(app: &mut App, key: KeyEvent) -> Result<bool> {
+    match key.code {
+        KeyCode::Esc => {
+            app.lock_buffer = Default::default();
+            app.lock_passphrase_first = None;
+            app.input_mode = InputMode::Settings;
+        }
+        KeyCode::Enter => app.submit_lock_passphrase(),
+        KeyCode::Backspace => {
+            app.lock_buffer.pop();
+        }
+        KeyCode::Char(c) => app.lock_buffer.push(c),
+        _ => {}
+    }
+    Ok(false)
+}
+
+fn handle_editing_metadata_keys(app: &mut App, key: KeyEvent) -> Result<bool> {
+    match key.code {
+        KeyCode::Esc => {
+            app.editing_field = 0;
+            app.input_mode = InputMode::Secrets;
+        }
+        KeyCode::Tab | KeyCode::Down => {
+            app.editing_field = (app.editing_field + 1) % app.meta_buffers.len();
+        }
+        KeyCode::BackTab | KeyCode::Up => {
+            app.editing_field =
+                (app.editing_field + app.meta_buffers.len() - 1) % app.meta_buffers.len();
+        }
         KeyCode::Enter => {
-            // Ir al selector de llaves
-            app.selected_sops_keys = vec![false; app.age_keys.len()];
-            if !app.age_keys.is_empty() {
-                app.selected_sops_keys[0] = true;
+            if let Err(e) = app.save_metadata_editor() {
+                app.set_temp_message(format!("❌ Error: {}", e));
             }
-            app.key_list_state.select(Some(0));
-            app.input_mode = InputMode::SelectingSopsKeys;
         }
+        KeyCode::Backspace => {
+            app.meta_buffers[app.editing_field].pop();
+        }
+        KeyCode::Char(c) => {
+            app.meta_buffers[app.editing_field].push(c);
+        }
+        _ => {}
+    }
+    Ok(false)
+}
+
+fn handle_fixing_permissions_keys(app: &mut App, key: KeyEvent) -> Result<bool> {
+    match key.code {
+        KeyCode::Char('c') | KeyCode::Char('q')
+            if key.modifiers.contains(KeyModifiers::CONTROL) =>
+        {
+            return Ok(true)
+        }
+        KeyCode::Char('y') | KeyCode::Char('Y') | KeyCode::Char('s') | KeyCode::Enter => {
+            app.fix_identity_permissions()
+        }
+        KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => app.ignore_identity_permissions(),
+        _ => {}
+    }
+    Ok(false)
+}
+
+fn handle_unlocking_identities_keys(app: &mut App, key: KeyEvent) -> Result<bool> {
+    match key.code {
+        KeyCode::Char('c') | KeyCode::Char('q')
+            if key.modifiers.contains(KeyModifiers::CONTROL) =>
+        {
+            return Ok(true)
+        }
+        KeyCode::Enter => app.unlock_identities(),
+        // Se puede seguir sin llaves y desbloquearlas más tarde con 'k'
+        KeyCode::Esc => {
+            app.lock_buffer = Default::default();
+            app.input_mode = InputMode::Explorer;
+            app.set_temp_message(app.i18n.t("identities_skipped").to_string());
+        }
+        KeyCode::Backspace => {
+            app.lock_buffer.pop();
+        }
+        KeyCode::Char(c) => app.lock_buffer.push(c),
         _ => {}
     }
     Ok(false)
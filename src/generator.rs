@@ -1,58 +1,623 @@
+use crate::perms::create_private_dir;
+use anyhow::Result;
 use base64::{engine::general_purpose, Engine as _};
-use rand::{thread_rng, Rng};
-use uuid::Uuid;
+use rand::{
+    distributions::{Distribution, Uniform},
+    rngs::OsRng,
+    seq::SliceRandom,
+    RngCore,
+};
+use serde::{Deserialize, Serialize};
+use std::{env, fs, process::Command};
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum TokenFormat {
     Hex,
     Base64,
+    Base64Url,
+    Base32,
     Uuid,
 }
 
-pub fn generate_password(length: usize, use_special: bool, use_numbers: bool) -> String {
-    let mut chars = "abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ".to_string();
+/// Material criptográfico que el generador puede producir
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum KeyKind {
+    SshEd25519,
+    SshRsa,
+    Age,
+    JwtHs256,
+    JwtHs384,
+    JwtHs512,
+}
 
-    if use_numbers {
-        chars.push_str("0123456789");
+impl KeyKind {
+    pub const ALL: [KeyKind; 6] = [
+        KeyKind::SshEd25519,
+        KeyKind::SshRsa,
+        KeyKind::Age,
+        KeyKind::JwtHs256,
+        KeyKind::JwtHs384,
+        KeyKind::JwtHs512,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            KeyKind::SshEd25519 => "SSH Ed25519",
+            KeyKind::SshRsa => "SSH RSA 4096",
+            KeyKind::Age => "age X25519",
+            KeyKind::JwtHs256 => "JWT HS256",
+            KeyKind::JwtHs384 => "JWT HS384",
+            KeyKind::JwtHs512 => "JWT HS512",
+        }
     }
 
-    if use_special {
-        chars.push_str("!@#$%^&*()_+-=[]{}|;:,.<>?");
+    /// Nivel de seguridad aproximado en bits
+    pub fn security_bits(&self) -> f64 {
+        match self {
+            KeyKind::SshEd25519 | KeyKind::Age => 128.0,
+            KeyKind::SshRsa => 140.0,
+            KeyKind::JwtHs256 => 256.0,
+            KeyKind::JwtHs384 => 384.0,
+            KeyKind::JwtHs512 => 512.0,
+        }
     }
+}
 
-    let mut rng = thread_rng();
-    (0..length)
-        .map(|_| {
-            let idx = rng.gen_range(0..chars.len());
-            chars.chars().nth(idx).unwrap()
-        })
+/// Parte privada (el valor del secreto) y, si existe, la pública
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyMaterial {
+    pub private: String,
+    pub public: Option<String>,
+}
+
+/// Sufijo de la llave hermana donde se guarda la parte pública
+pub const PUBLIC_KEY_SUFFIX: &str = "_PUBLIC";
+
+pub fn public_key_name(key: &str) -> String {
+    format!("{}{}", key, PUBLIC_KEY_SUFFIX)
+}
+
+pub fn generate_key(kind: KeyKind) -> Result<KeyMaterial> {
+    match kind {
+        KeyKind::SshEd25519 => ssh_keygen(&["-t", "ed25519"]),
+        KeyKind::SshRsa => ssh_keygen(&["-t", "rsa", "-b", "4096"]),
+        KeyKind::Age => age_keygen(),
+        // La clave HMAC debe tener al menos el tamaño de salida del hash (RFC 7518 §3.2)
+        KeyKind::JwtHs256 => Ok(jwt_secret(32)),
+        KeyKind::JwtHs384 => Ok(jwt_secret(48)),
+        KeyKind::JwtHs512 => Ok(jwt_secret(64)),
+    }
+}
+
+fn jwt_secret(bytes: usize) -> KeyMaterial {
+    KeyMaterial {
+        private: generate_token(TokenFormat::Base64Url, bytes),
+        public: None,
+    }
+}
+
+fn ssh_keygen(args: &[&str]) -> Result<KeyMaterial> {
+    let dir = env::temp_dir().join(format!(
+        "agesmith-keygen-{}-{:016x}",
+        std::process::id(),
+        OsRng.next_u64()
+    ));
+    // ssh-keygen solo escribe a un archivo: se usa un directorio 0700 que nadie más puede listar
+    create_private_dir(&dir)?;
+    let key_path = dir.join("id");
+
+    let output = Command::new("ssh-keygen")
+        .args(args)
+        .args(["-N", "", "-C", "agesmith", "-q", "-f"])
+        .arg(&key_path)
+        .output();
+
+    let result = match output {
+        Ok(output) if output.status.success() => fs::read_to_string(&key_path)
+            .and_then(|private| {
+                let public = fs::read_to_string(key_path.with_extension("pub"))?;
+                Ok(KeyMaterial {
+                    private,
+                    public: Some(public.trim().to_string()),
+                })
+            })
+            .map_err(anyhow::Error::from),
+        Ok(output) => Err(anyhow::anyhow!(
+            "ssh-keygen falló: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )),
+        Err(_) => Err(anyhow::anyhow!(
+            "No se pudo ejecutar ssh-keygen. ¿Está instalado OpenSSH?"
+        )),
+    };
+    // La llave privada solo queda en disco mientras se lee
+    fs::remove_dir_all(&dir).ok();
+    result
+}
+
+fn age_keygen() -> Result<KeyMaterial> {
+    let output = Command::new("age-keygen")
+        .output()
+        .map_err(|_| anyhow::anyhow!("No se pudo ejecutar age-keygen. ¿Está instalado age?"))?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "age-keygen falló: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    parse_age_keygen_output(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// Extrae la identidad y el recipient de la salida de `age-keygen`
+pub fn parse_age_keygen_output(output: &str) -> Result<KeyMaterial> {
+    let private = output
+        .lines()
+        .map(str::trim)
+        .find(|l| l.starts_with("AGE-SECRET-KEY-"))
+        .ok_or_else(|| anyhow::anyhow!("age-keygen no generó ninguna identidad"))?;
+    let public = output
+        .lines()
+        .find_map(|l| l.trim().strip_prefix("# public key:"))
+        .map(|p| p.trim().to_string());
+    Ok(KeyMaterial {
+        private: private.to_string(),
+        public,
+    })
+}
+
+const LOWERCASE: &str = "abcdefghijklmnopqrstuvwxyz";
+const UPPERCASE: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+const DIGITS: &str = "0123456789";
+pub const DEFAULT_SYMBOLS: &str = "!@#$%^&*()_+-=[]{}|;:,.<>?";
+/// Caracteres que se confunden fácilmente al leerlos
+const AMBIGUOUS: &str = "Il1O0o";
+
+/// Política de contraseñas configurable por nombre en `config.toml` (`[policies.<nombre>]`)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PasswordPolicy {
+    pub length: usize,
+    pub lowercase: bool,
+    pub uppercase: bool,
+    pub digits: bool,
+    pub symbols: bool,
+    pub symbol_set: String,
+    pub min_lowercase: usize,
+    pub min_uppercase: usize,
+    pub min_digits: usize,
+    pub min_symbols: usize,
+    pub exclude_ambiguous: bool,
+    /// Alfabeto personalizado: si se define, reemplaza a las clases de caracteres
+    pub alphabet: Option<String>,
+    /// Caracteres que nunca deben aparecer (p. ej. `#` o comillas en .env)
+    pub forbidden: String,
+}
+
+impl Default for PasswordPolicy {
+    fn default() -> Self {
+        Self {
+            length: 20,
+            lowercase: true,
+            uppercase: true,
+            digits: true,
+            symbols: true,
+            symbol_set: DEFAULT_SYMBOLS.to_string(),
+            min_lowercase: 1,
+            min_uppercase: 1,
+            min_digits: 1,
+            min_symbols: 1,
+            exclude_ambiguous: false,
+            alphabet: None,
+            forbidden: String::new(),
+        }
+    }
+}
+
+impl PasswordPolicy {
+    /// Equivalente a las opciones simples del generador (longitud + números + especiales)
+    pub fn simple(length: usize, use_special: bool, use_numbers: bool) -> Self {
+        Self {
+            length,
+            digits: use_numbers,
+            symbols: use_special,
+            ..Default::default()
+        }
+    }
+
+    fn filter_chars(&self, chars: &str) -> Vec<char> {
+        let mut result: Vec<char> = chars
+            .chars()
+            .filter(|c| !self.forbidden.contains(*c))
+            .filter(|c| !self.exclude_ambiguous || !AMBIGUOUS.contains(*c))
+            .collect();
+        result.sort_unstable();
+        result.dedup();
+        result
+    }
+
+    /// Clases habilitadas con su nombre, sus caracteres (ya filtrados, pueden quedar vacíos)
+    /// y el mínimo requerido de cada una
+    fn enabled_classes(&self) -> Vec<(&'static str, Vec<char>, usize)> {
+        if let Some(alphabet) = &self.alphabet {
+            return vec![("alfabeto", self.filter_chars(alphabet), 0)];
+        }
+
+        [
+            (self.lowercase, "minúsculas", LOWERCASE, self.min_lowercase),
+            (self.uppercase, "mayúsculas", UPPERCASE, self.min_uppercase),
+            (self.digits, "dígitos", DIGITS, self.min_digits),
+            (
+                self.symbols,
+                "símbolos",
+                self.symbol_set.as_str(),
+                self.min_symbols,
+            ),
+        ]
+        .into_iter()
+        .filter(|(enabled, _, _, _)| *enabled)
+        .map(|(_, name, chars, min)| (name, self.filter_chars(chars), min))
         .collect()
+    }
+
+    /// Clases con algún caracter permitido y el mínimo requerido de cada una
+    fn classes(&self) -> Vec<(Vec<char>, usize)> {
+        self.enabled_classes()
+            .into_iter()
+            .filter(|(_, chars, _)| !chars.is_empty())
+            .map(|(_, chars, min)| (chars, min))
+            .collect()
+    }
+
+    pub fn alphabet(&self) -> Vec<char> {
+        let mut chars: Vec<char> = self.classes().into_iter().flat_map(|(c, _)| c).collect();
+        chars.sort_unstable();
+        chars.dedup();
+        chars
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        if self.alphabet().is_empty() {
+            anyhow::bail!("La política no deja ningún carácter permitido");
+        }
+        // `forbidden` o `exclude_ambiguous` pueden vaciar una clase que tiene mínimo
+        for (name, chars, min) in self.enabled_classes() {
+            if chars.is_empty() && min > 0 {
+                anyhow::bail!(
+                    "La política exige {} {} pero todos están excluidos",
+                    min,
+                    name
+                );
+            }
+        }
+        let required: usize = self.classes().iter().map(|(_, min)| min).sum();
+        if required > self.length {
+            anyhow::bail!(
+                "La política exige {} caracteres pero la longitud es {}",
+                required,
+                self.length
+            );
+        }
+        Ok(())
+    }
+
+    /// Genera una contraseña que cumple los mínimos de cada clase
+    pub fn generate(&self) -> Result<String> {
+        self.validate()?;
+        let mut password: Vec<char> = Vec::with_capacity(self.length);
+        for (chars, min) in self.classes() {
+            password.extend(sample_uniform(&chars, min));
+        }
+        let alphabet = self.alphabet();
+        password.extend(sample_uniform(&alphabet, self.length - password.len()));
+
+        // Mezclar para que los caracteres obligatorios no queden al principio
+        password.shuffle(&mut OsRng);
+        Ok(password.into_iter().collect())
+    }
+
+    pub fn entropy_bits(&self) -> f64 {
+        let size = self.alphabet().len();
+        if size < 2 {
+            return 0.0;
+        }
+        self.length as f64 * (size as f64).log2()
+    }
+}
+
+/// Políticas incluidas; las de `config.toml` con el mismo nombre las reemplazan
+pub fn builtin_policies() -> Vec<(String, PasswordPolicy)> {
+    vec![
+        ("strong".to_string(), PasswordPolicy::default()),
+        (
+            "alphanumeric".to_string(),
+            PasswordPolicy {
+                symbols: false,
+                exclude_ambiguous: true,
+                ..Default::default()
+            },
+        ),
+        (
+            "dotenv-safe".to_string(),
+            PasswordPolicy {
+                forbidden: "#\"'`$\\ =;".to_string(),
+                ..Default::default()
+            },
+        ),
+        (
+            "url-safe".to_string(),
+            PasswordPolicy {
+                symbol_set: "-._~".to_string(),
+                ..Default::default()
+            },
+        ),
+    ]
+}
+
+/// Lista de palabras para frases de contraseña (BIP39 en inglés: 2048 palabras, 11 bits cada una)
+const WORDLIST: &str = include_str!("wordlist.txt");
+
+pub fn wordlist() -> Vec<&'static str> {
+    WORDLIST.lines().filter(|w| !w.is_empty()).collect()
+}
+
+/// Frase de contraseña estilo diceware
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PassphrasePolicy {
+    pub words: usize,
+    pub separator: String,
+    pub capitalize: bool,
+    /// Agrega un dígito al final de una palabra al azar
+    pub digit: bool,
+}
+
+impl Default for PassphrasePolicy {
+    fn default() -> Self {
+        Self {
+            words: 6,
+            separator: "-".to_string(),
+            capitalize: false,
+            digit: false,
+        }
+    }
+}
+
+impl PassphrasePolicy {
+    pub fn generate(&self) -> Result<String> {
+        if self.words == 0 {
+            anyhow::bail!("La frase necesita al menos una palabra");
+        }
+        let list = wordlist();
+
+        let mut words: Vec<String> = sample_uniform(&list, self.words)
+            .map(|word| {
+                if self.capitalize {
+                    let mut chars = word.chars();
+                    chars
+                        .next()
+                        .map(|first| first.to_uppercase().chain(chars).collect())
+                        .unwrap_or_default()
+                } else {
+                    word.to_string()
+                }
+            })
+            .collect();
+
+        if self.digit {
+            let idx = Uniform::from(0..words.len()).sample(&mut OsRng);
+            let digit = Uniform::from(b'0'..=b'9').sample(&mut OsRng);
+            words[idx].push(char::from(digit));
+        }
+        Ok(words.join(&self.separator))
+    }
+
+    pub fn entropy_bits(&self) -> f64 {
+        if self.words == 0 {
+            return 0.0;
+        }
+        let mut bits = self.words as f64 * (wordlist().len() as f64).log2();
+        if self.digit {
+            bits += (10.0 * self.words as f64).log2();
+        }
+        bits
+    }
+}
+
+/// Parámetros del generador que se recuerdan por llave para futuras rotaciones
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum GeneratorPolicy {
+    Password {
+        length: usize,
+        use_special: bool,
+        use_numbers: bool,
+    },
+    Token {
+        format: TokenFormat,
+        length: usize,
+    },
+    /// Política con nombre; se guarda una copia por si desaparece de la configuración
+    Named {
+        name: String,
+        policy: PasswordPolicy,
+    },
+    Passphrase(PassphrasePolicy),
+    Key {
+        algorithm: KeyKind,
+    },
+}
+
+impl GeneratorPolicy {
+    pub fn generate(&self) -> Result<String> {
+        self.generate_material().map(|m| m.private)
+    }
+
+    /// Como `generate`, pero conserva la parte pública de los pares de llaves
+    pub fn generate_material(&self) -> Result<KeyMaterial> {
+        let private = match self {
+            GeneratorPolicy::Password {
+                length,
+                use_special,
+                use_numbers,
+            } => PasswordPolicy::simple(*length, *use_special, *use_numbers).generate(),
+            GeneratorPolicy::Token { format, length } => Ok(generate_token(*format, *length)),
+            GeneratorPolicy::Named { policy, .. } => policy.generate(),
+            GeneratorPolicy::Passphrase(policy) => policy.generate(),
+            GeneratorPolicy::Key { algorithm } => return generate_key(*algorithm),
+        }?;
+        Ok(KeyMaterial {
+            private,
+            public: None,
+        })
+    }
+
+    pub fn entropy_bits(&self) -> f64 {
+        match self {
+            GeneratorPolicy::Password {
+                length,
+                use_special,
+                use_numbers,
+            } => PasswordPolicy::simple(*length, *use_special, *use_numbers).entropy_bits(),
+            GeneratorPolicy::Token { format, length } => match format {
+                TokenFormat::Uuid => 122.0,
+                _ => *length as f64 * 8.0,
+            },
+            GeneratorPolicy::Named { policy, .. } => policy.entropy_bits(),
+            GeneratorPolicy::Passphrase(policy) => policy.entropy_bits(),
+            GeneratorPolicy::Key { algorithm } => algorithm.security_bits(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub enum Strength {
+    VeryWeak,
+    Weak,
+    Fair,
+    Strong,
+    VeryStrong,
+}
+
+impl Strength {
+    pub fn from_bits(bits: f64) -> Self {
+        match bits {
+            b if b < 28.0 => Strength::VeryWeak,
+            b if b < 36.0 => Strength::Weak,
+            b if b < 60.0 => Strength::Fair,
+            b if b < 128.0 => Strength::Strong,
+            _ => Strength::VeryStrong,
+        }
+    }
+
+    /// Llave de traducción para mostrar el nivel
+    pub fn label_key(&self) -> &'static str {
+        match self {
+            Strength::VeryWeak => "strength_very_weak",
+            Strength::Weak => "strength_weak",
+            Strength::Fair => "strength_fair",
+            Strength::Strong => "strength_strong",
+            Strength::VeryStrong => "strength_very_strong",
+        }
+    }
+}
+
+/// Estimación de entropía de un valor existente según las clases de caracteres que usa.
+/// Los caracteres repetidos consecutivos no suman entropía.
+pub fn estimate_entropy(value: &str) -> f64 {
+    let mut pool = 0;
+    if value.chars().any(|c| c.is_ascii_lowercase()) {
+        pool += 26;
+    }
+    if value.chars().any(|c| c.is_ascii_uppercase()) {
+        pool += 26;
+    }
+    if value.chars().any(|c| c.is_ascii_digit()) {
+        pool += 10;
+    }
+    if value.chars().any(|c| c.is_ascii_punctuation() || c == ' ') {
+        pool += 33;
+    }
+    if !value.is_ascii() {
+        pool += 100;
+    }
+    if pool < 2 {
+        return 0.0;
+    }
+
+    let chars: Vec<char> = value.chars().collect();
+    let effective = chars
+        .iter()
+        .enumerate()
+        .filter(|(i, c)| *i == 0 || chars[i - 1] != **c)
+        .count();
+    effective as f64 * (pool as f64).log2()
+}
+
+pub fn generate_password(length: usize, use_special: bool, use_numbers: bool) -> String {
+    let mut policy = PasswordPolicy::simple(length, use_special, use_numbers);
+    if policy.validate().is_err() {
+        // Longitudes muy cortas: sin mínimos por clase
+        policy.min_lowercase = 0;
+        policy.min_uppercase = 0;
+        policy.min_digits = 0;
+        policy.min_symbols = 0;
+    }
+    policy.generate().unwrap_or_default()
+}
+
+/// Elige `count` elementos de `items` de forma uniforme con el RNG del sistema operativo.
+/// `Uniform` usa muestreo por rechazo, así que no hay sesgo de módulo.
+fn sample_uniform<T: Copy>(items: &[T], count: usize) -> impl Iterator<Item = T> + '_ {
+    let dist = Uniform::from(0..items.len());
+    (0..count).map(move |_| items[dist.sample(&mut OsRng)])
+}
+
+/// Bytes aleatorios del CSPRNG del sistema operativo
+pub fn random_bytes(length: usize) -> Vec<u8> {
+    let mut bytes = vec![0u8; length];
+    OsRng.fill_bytes(&mut bytes);
+    bytes
 }
 
+/// Genera un token a partir de exactamente `length` bytes aleatorios (UUID ignora la longitud)
 pub fn generate_token(format: TokenFormat, length: usize) -> String {
-    let mut rng = thread_rng();
+    let bytes = random_bytes(length);
 
     match format {
-        TokenFormat::Hex => (0..length)
-            .map(|_| format!("{:02x}", rng.gen::<u8>()))
-            .collect::<String>()
-            .chars()
-            .take(length)
-            .collect(),
-        TokenFormat::Base64 => {
-            let bytes: Vec<u8> = (0..(length * 3 / 4 + 1)).map(|_| rng.gen()).collect();
-            general_purpose::STANDARD
-                .encode(&bytes)
-                .chars()
-                .take(length)
-                .collect()
-        }
+        TokenFormat::Hex => bytes.iter().map(|b| format!("{:02x}", b)).collect(),
+        TokenFormat::Base64 => general_purpose::STANDARD.encode(&bytes),
+        TokenFormat::Base64Url => general_purpose::URL_SAFE_NO_PAD.encode(&bytes),
+        TokenFormat::Base32 => base32_encode(&bytes),
         TokenFormat::Uuid => {
             let mut bytes = [0u8; 16];
-            for byte in &mut bytes {
-                *byte = rng.gen();
-            }
-            Uuid::from_bytes(bytes).to_string()
+            OsRng.fill_bytes(&mut bytes);
+            // Fija los bits de versión (4) y variante (RFC 4122)
+            uuid::Builder::from_random_bytes(bytes)
+                .into_uuid()
+                .to_string()
+        }
+    }
+}
+
+/// Base32 de RFC 4648 sin relleno (el formato habitual de los secretos TOTP)
+pub fn base32_encode(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+    let mut result = String::with_capacity(bytes.len().div_ceil(5) * 8);
+    let mut buffer: u16 = 0;
+    let mut bits = 0;
+
+    for &byte in bytes {
+        buffer = (buffer << 8) | byte as u16;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            result.push(ALPHABET[((buffer >> bits) & 0x1f) as usize] as char);
         }
     }
+    if bits > 0 {
+        result.push(ALPHABET[((buffer << (5 - bits)) & 0x1f) as usize] as char);
+    }
+    result
 }
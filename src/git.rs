@@ -0,0 +1,332 @@
+use crate::diff::unified_diff;
+use crate::perms::{create_private_dir, write_private};
+use crate::sops::{decrypt_and_parse, detect_key_index, get_sops_recipients, load_age_keys};
+use anyhow::{Context, Result};
+use rand::{rngs::OsRng, RngCore};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::HashMap,
+    env, fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+use zeroize::Zeroize;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TextconvMode {
+    Values,
+    KeysOnly,
+    HashedValues,
+}
+
+/// Separa los flags de modo (`--keys-only`, `--hash-values`) de los argumentos posicionales
+fn parse_args(args: &[String]) -> Result<(TextconvMode, Vec<String>)> {
+    let mut mode = TextconvMode::Values;
+    let mut positional = Vec::new();
+
+    for arg in args {
+        match arg.as_str() {
+            "--keys-only" => mode = TextconvMode::KeysOnly,
+            "--hash-values" => mode = TextconvMode::HashedValues,
+            flag if flag.starts_with("--") => anyhow::bail!("Opción desconocida: {}", flag),
+            _ => positional.push(arg.clone()),
+        }
+    }
+
+    Ok((mode, positional))
+}
+
+/// Descifra un archivo usando la misma auto-detección de llave que la TUI
+pub fn decrypt_with_detected_key(file_path: &PathBuf) -> Result<Vec<(String, String)>> {
+    let keys = load_age_keys(&crate::config::load_config()?.identity_files)?;
+    let recipients = get_sops_recipients(file_path).unwrap_or_default();
+    let key = detect_key_index(&keys, &recipients).map(|i| keys[i].key.as_str());
+    decrypt_and_parse(file_path, key)
+}
+
+fn hash_value(value: &str) -> String {
+    let digest = Sha256::digest(value.as_bytes());
+    let hex: String = digest.iter().map(|b| format!("{:02x}", b)).collect();
+    format!("sha256:{}", &hex[..16])
+}
+
+/// Una línea por secreto para que los diffs sean estables y legibles
+pub fn render_secrets(secrets: &[(String, String)], mode: TextconvMode) -> String {
+    let mut out = String::new();
+    for (key, value) in secrets {
+        let line = match mode {
+            TextconvMode::Values => format!(
+                "{}: {}",
+                key,
+                value.replace('\\', "\\\\").replace('\n', "\\n")
+            ),
+            TextconvMode::KeysOnly => key.clone(),
+            TextconvMode::HashedValues => format!("{}: {}", key, hash_value(value)),
+        };
+        out.push_str(&line);
+        out.push('\n');
+    }
+    out
+}
+
+/// Texto que git debe comparar para un archivo. Los archivos que no son SOPS
+/// (o que ninguna llave local puede descifrar) se devuelven tal cual.
+pub fn textconv(file_path: &PathBuf, mode: TextconvMode) -> Result<String> {
+    if file_path.to_str() == Some("/dev/null") {
+        return Ok(String::new());
+    }
+
+    match decrypt_with_detected_key(file_path) {
+        Ok(secrets) => Ok(render_secrets(&secrets, mode)),
+        Err(_) => Ok(String::from_utf8_lossy(&fs::read(file_path)?).into_owned()),
+    }
+}
+
+/// `agesmith textconv [--keys-only|--hash-values] FILE`
+pub fn run_textconv(args: &[String]) -> Result<()> {
+    let (mode, files) = parse_args(args)?;
+    let [file] = files.as_slice() else {
+        anyhow::bail!("Uso: agesmith textconv [--keys-only|--hash-values] FILE");
+    };
+
+    print!("{}", textconv(&PathBuf::from(file), mode)?);
+    Ok(())
+}
+
+/// `agesmith diff-driver [--keys-only|--hash-values] PATH OLD-FILE OLD-HEX OLD-MODE NEW-FILE NEW-HEX NEW-MODE`
+///
+/// Implementa la interfaz de GIT_EXTERNAL_DIFF / `diff.<driver>.command`.
+pub fn run_diff_driver(args: &[String]) -> Result<()> {
+    let (mode, positional) = parse_args(args)?;
+    if positional.len() < 7 {
+        anyhow::bail!(
+            "Uso: agesmith diff-driver [--keys-only|--hash-values] PATH OLD-FILE OLD-HEX OLD-MODE NEW-FILE NEW-HEX NEW-MODE"
+        );
+    }
+
+    let path = &positional[0];
+    // En renombres git pasa la ruta nueva como octavo argumento
+    let new_path = positional.get(7).unwrap_or(path);
+
+    let old: Vec<String> = textconv(&PathBuf::from(&positional[1]), mode)?
+        .lines()
+        .map(String::from)
+        .collect();
+    let new: Vec<String> = textconv(&PathBuf::from(&positional[4]), mode)?
+        .lines()
+        .map(String::from)
+        .collect();
+
+    let diff = unified_diff(
+        &format!("a/{}", path),
+        &format!("b/{}", new_path),
+        &old,
+        &new,
+        3,
+    );
+    if !diff.is_empty() {
+        println!("diff --git a/{} b/{}", path, new_path);
+        print!("{}", diff);
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum KeyChange {
+    Added,
+    Removed,
+    Changed,
+    /// Revisión que ninguna llave local puede descifrar
+    Unreadable,
+    /// El archivo se eliminó en esta revisión
+    FileDeleted,
+}
+
+#[derive(Debug, Clone)]
+pub struct HistoryEvent {
+    pub commit: String,
+    pub author: String,
+    pub date: String,
+    /// Vacía en los eventos que afectan a toda la revisión
+    pub key: String,
+    pub change: KeyChange,
+}
+
+/// Una revisión del archivo según `git log --name-status`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Revision {
+    pub commit: String,
+    pub author: String,
+    pub date: String,
+    pub path: String,
+    pub deleted: bool,
+}
+
+fn run_git(dir: &Path, args: &[&str]) -> Result<String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .output()
+        .context("No se pudo ejecutar git")?;
+
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    } else {
+        anyhow::bail!(
+            "Error de git: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )
+    }
+}
+
+/// Descifra el contenido de una revisión escribiéndolo (aún cifrado) en un archivo
+/// temporal con el mismo nombre, para que SOPS detecte el formato por la extensión.
+fn decrypt_revision(
+    repo_root: &Path,
+    commit: &str,
+    rel_path: &str,
+    age_key: Option<&str>,
+) -> Result<HashMap<String, String>> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(repo_root)
+        .arg("show")
+        .arg(format!("{}:{}", commit, rel_path))
+        .output()
+        .context("No se pudo ejecutar git")?;
+    if !output.status.success() {
+        anyhow::bail!("git show falló para {}:{}", commit, rel_path);
+    }
+
+    let file_name = Path::new(rel_path)
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("secrets");
+    // Directorio 0700 con nombre aleatorio: nadie puede adelantarse con un enlace a esa ruta.
+    // El archivo conserva su nombre para que SOPS detecte el formato.
+    let dir = env::temp_dir().join(format!(
+        "agesmith-history-{}-{:016x}",
+        std::process::id(),
+        OsRng.next_u64()
+    ));
+    create_private_dir(&dir)?;
+    let tmp_path = dir.join(file_name);
+
+    // Las revisiones antiguas pueden tener otros recipients: reintentar con todas las llaves
+    let result = write_private(&tmp_path, &output.stdout).and_then(|()| {
+        decrypt_and_parse(&tmp_path, age_key).or_else(|_| decrypt_and_parse(&tmp_path, None))
+    });
+    fs::remove_dir_all(&dir).ok();
+
+    Ok(result?.into_iter().collect())
+}
+
+/// Los valores descifrados solo sirven para comparar revisiones
+fn wipe_values(secrets: &mut HashMap<String, String>) {
+    secrets.values_mut().for_each(Zeroize::zeroize);
+    secrets.clear();
+}
+
+/// Interpreta la salida de `git log --name-status` con el formato que usa `file_history`,
+/// de más antigua a más reciente
+pub fn parse_log(log: &str) -> Vec<Revision> {
+    // Cada registro: "hash\x1fautor\x1ffecha\n\nM\truta/relativa\n" (R100\tvieja\tnueva en renombres)
+    let mut revisions: Vec<Revision> = log
+        .split('\x1e')
+        .filter_map(|record| {
+            let mut lines = record.lines().filter(|l| !l.trim().is_empty());
+            let mut header = lines.next()?.split('\x1f');
+            let status = lines.next()?;
+            Some(Revision {
+                commit: header.next()?.to_string(),
+                author: header.next()?.to_string(),
+                date: header.next()?.to_string(),
+                path: status.rsplit('\t').next()?.trim().to_string(),
+                deleted: status.starts_with('D'),
+            })
+        })
+        .collect();
+    revisions.reverse();
+    revisions
+}
+
+/// Recorre el historial git del archivo y devuelve, de más reciente a más antiguo,
+/// cuándo se agregó, eliminó o cambió cada llave. Los valores nunca se incluyen.
+pub fn file_history(file_path: &Path, age_key: Option<&str>) -> Result<Vec<HistoryEvent>> {
+    let dir = file_path
+        .parent()
+        .filter(|p| !p.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    let repo_root = PathBuf::from(run_git(dir, &["rev-parse", "--show-toplevel"])?.trim());
+
+    let file_name = file_path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .context("Nombre de archivo inválido")?;
+    let log = run_git(
+        dir,
+        &[
+            "log",
+            "--follow",
+            "--name-status",
+            "--date=short",
+            "--format=%x1e%h%x1f%an%x1f%ad",
+            "--",
+            file_name,
+        ],
+    )?;
+
+    let mut events = Vec::new();
+    let mut previous: HashMap<String, String> = HashMap::new();
+
+    for revision in parse_log(&log) {
+        let event = |key: String, change: KeyChange| HistoryEvent {
+            commit: revision.commit.clone(),
+            author: revision.author.clone(),
+            date: revision.date.clone(),
+            key,
+            change,
+        };
+
+        // Borrar el archivo no es borrar sus llaves: si vuelve a crearse, todas son nuevas
+        if revision.deleted {
+            events.push(event(String::new(), KeyChange::FileDeleted));
+            wipe_values(&mut previous);
+            continue;
+        }
+
+        let current = match decrypt_revision(&repo_root, &revision.commit, &revision.path, age_key)
+        {
+            Ok(secrets) => secrets,
+            // Los cambios se atribuyen a la siguiente revisión legible
+            Err(_) => {
+                events.push(event(String::new(), KeyChange::Unreadable));
+                continue;
+            }
+        };
+
+        let mut changes: Vec<(String, KeyChange)> = Vec::new();
+        for (key, value) in &current {
+            match previous.get(key) {
+                None => changes.push((key.clone(), KeyChange::Added)),
+                Some(old) if old != value => changes.push((key.clone(), KeyChange::Changed)),
+                _ => {}
+            }
+        }
+        for key in previous.keys() {
+            if !current.contains_key(key) {
+                changes.push((key.clone(), KeyChange::Removed));
+            }
+        }
+        changes.sort_by(|a, b| a.0.cmp(&b.0));
+
+        events.extend(changes.into_iter().map(|(key, change)| event(key, change)));
+        wipe_values(&mut previous);
+        previous = current;
+    }
+    wipe_values(&mut previous);
+
+    events.reverse();
+    Ok(events)
+}
@@ -11,6 +11,8 @@ pub fn show_help(i18n: &I18n) -> Vec<(String, Vec<(&'static str, String)>)> {
                 ("i", i18n.t("help_init_sops").to_string()),
                 ("Tab", i18n.t("help_change_secrets").to_string()),
                 ("k", i18n.t("help_key_selector_open").to_string()),
+                ("A", i18n.t("help_audit").to_string()),
+                ("Ctrl+L", i18n.t("help_lock").to_string()),
             ],
         ),
         (
@@ -19,6 +21,8 @@ pub fn show_help(i18n: &I18n) -> Vec<(String, Vec<(&'static str, String)>)> {
                 ("↑/↓", i18n.t("help_nav_secrets").to_string()),
                 ("v", i18n.t("help_show_hide").to_string()),
                 ("z", i18n.t("help_zoom").to_string()),
+                ("h", i18n.t("help_history").to_string()),
+                ("m", i18n.t("help_metadata").to_string()),
                 ("c", i18n.t("help_copy_value").to_string()),
                 ("C", i18n.t("help_copy_key").to_string()),
                 ("f", i18n.t("help_favorite").to_string()),
@@ -27,6 +31,11 @@ pub fn show_help(i18n: &I18n) -> Vec<(String, Vec<(&'static str, String)>)> {
                 ("d", i18n.t("help_delete").to_string()),
                 ("s", i18n.t("help_save").to_string()),
                 ("g", i18n.t("help_generate").to_string()),
+                ("r", i18n.t("help_rotate").to_string()),
+                ("R", i18n.t("help_rotate_marked").to_string()),
+                ("u", i18n.t("help_rotate_undo").to_string()),
+                ("U", i18n.t("help_rotate_undo_batch").to_string()),
+                ("y", i18n.t("help_rekey").to_string()),
                 ("/", i18n.t("help_search_secrets").to_string()),
                 ("k", i18n.t("help_key_selector_open").to_string()),
                 ("Tab", i18n.t("help_back_explorer").to_string()),
@@ -110,6 +110,13 @@ impl I18n {
                 "🔒 Session locked due to inactivity",
             ),
         );
+        translations.insert(
+            "session_locked_discarded",
+            (
+                "🔒 Sesión bloqueada por inactividad; se descartaron los cambios sin guardar",
+                "🔒 Session locked due to inactivity; unsaved changes were discarded",
+            ),
+        );
         translations.insert(
             "sops_initialized",
             ("✓ Archivo .sops.yaml creado", "✓ .sops.yaml file created"),
@@ -189,8 +196,8 @@ impl I18n {
         translations.insert(
             "key_manager_help",
             (
-                "[↑↓] Navegar | [n] Nueva llave | [d] Eliminar | [Esc] Cerrar",
-                "[↑↓] Navigate | [n] New key | [d] Delete | [Esc] Close",
+                "[↑↓] Navegar | [n] Nueva llave | [c] Copiar pública | [e] Comentario | [x] Exportar | [i] Importar | [b] Respaldo | [u] Uso | [d] Eliminar | [Esc] Cerrar",
+                "[↑↓] Navigate | [n] New key | [c] Copy public | [e] Comment | [x] Export | [i] Import | [b] Backup | [u] Usage | [d] Delete | [Esc] Close",
             ),
         );
         translations.insert(
@@ -429,20 +436,23 @@ impl I18n {
         );
         translations.insert(
             "select_sops_keys_title",
-            ("🔑 Seleccionar Llaves para SOPS", "🔑 Select Keys for SOPS"),
+            (
+                "🔑 Seleccionar Recipients para SOPS",
+                "🔑 Select Recipients for SOPS",
+            ),
         );
         translations.insert(
             "footer_select_sops_keys",
             (
-                "[↑↓] Navegar | [Espacio/Enter] Seleccionar | [Esc] Cancelar",
-                "[↑↓] Navigate | [Space/Enter] Select | [Esc] Cancel",
+                "[↑↓] Navegar | [Espacio] Marcar | [Enter] Crear | [Esc] Cancelar",
+                "[↑↓] Navigate | [Space] Toggle | [Enter] Create | [Esc] Cancel",
             ),
         );
         translations.insert(
             "footer_edit_sops",
             (
-                "[Ctrl+Enter] Guardar | [Esc] Cancelar",
-                "[Ctrl+Enter] Save | [Esc] Cancel",
+                "[↑↓] Regla | [Shift+↑↓] Mover | [Tab] Campo | [Enter] Editar | [a] Añadir | [d] Borrar | [e] Editor externo | [Ctrl+S] Guardar | [Esc] Cancelar",
+                "[↑↓] Rule | [Shift+↑↓] Move | [Tab] Field | [Enter] Edit | [a] Add | [d] Delete | [e] External editor | [Ctrl+S] Save | [Esc] Cancel",
             ),
         );
         translations.insert(
@@ -727,24 +737,87 @@ impl I18n {
         translations.insert("decrypting", ("Desencriptando", "Decrypting"));
         translations.insert("saving", ("Guardando", "Saving"));
         translations.insert("current_path", ("Ruta actual", "Current path"));
-        translations.insert("settings_title", ("⚙️  Configuración [Ctrl+S]", "⚙️  Settings [Ctrl+S]"));
+        translations.insert(
+            "settings_title",
+            ("⚙️  Configuración [Ctrl+S]", "⚙️  Settings [Ctrl+S]"),
+        );
         translations.insert("change", ("Cambiar", "Change"));
         translations.insert("close", ("Cerrar", "Close"));
         translations.insert("cancel", ("Cancelar", "Cancel"));
         translations.insert("confirm", ("Confirmar", "Confirm"));
         translations.insert("na", ("N/D", "N/A"));
         translations.insert("unknown", ("Desconocido", "Unknown"));
-        translations.insert("create_encrypted_file_title", ("🔐 Crear archivo encriptado con SOPS", "🔐 Create encrypted file with SOPS"));
-        translations.insert("file_name_prompt", ("Nombre del archivo (Enter para secrets.{})", "File name (Enter for secrets.{})"));
-        translations.insert("select_file_format_title", ("📄 Seleccionar formato de archivo para SOPS", "📄 Select file format for SOPS"));
-        translations.insert("environment_variables", ("Variables de entorno (.env)", "Environment variables (.env)"));
-        translations.insert("json_config", ("Configuración JSON (.json)", "JSON configuration (.json)"));
-        translations.insert("yaml_config", ("Configuración YAML (.yaml/.yml)", "YAML configuration (.yaml/.yml)"));
-        translations.insert("ini_config", ("Configuración INI (.ini)", "INI configuration (.ini)"));
-        translations.insert("value_field_generate", ("Valor | [Ctrl+g] Generar | Usa \"texto\" para forzar string", "Value | [Ctrl+g] Generate | Use \"text\" to force string"));
-        translations.insert("new_folder_title_help", ("📁 Nueva Carpeta | [Enter] Crear | [Esc] Cancelar", "📁 New Folder | [Enter] Create | [Esc] Cancel"));
-        translations.insert("rename_title_help", ("✏️ Renombrar | [Enter] Guardar | [Esc] Cancelar", "✏️ Rename | [Enter] Save | [Esc] Cancel"));
-        translations.insert("create_encrypted_file_title_help", ("🔐 Crear archivo encriptado con SOPS | [Enter] Crear | [Esc] Cancelar", "🔐 Create encrypted file with SOPS | [Enter] Create | [Esc] Cancel"));
+        translations.insert(
+            "create_encrypted_file_title",
+            (
+                "🔐 Crear archivo encriptado con SOPS",
+                "🔐 Create encrypted file with SOPS",
+            ),
+        );
+        translations.insert(
+            "file_name_prompt",
+            (
+                "Nombre del archivo (Enter para secrets.{})",
+                "File name (Enter for secrets.{})",
+            ),
+        );
+        translations.insert(
+            "select_file_format_title",
+            (
+                "📄 Seleccionar formato de archivo para SOPS",
+                "📄 Select file format for SOPS",
+            ),
+        );
+        translations.insert(
+            "environment_variables",
+            (
+                "Variables de entorno (.env)",
+                "Environment variables (.env)",
+            ),
+        );
+        translations.insert(
+            "json_config",
+            ("Configuración JSON (.json)", "JSON configuration (.json)"),
+        );
+        translations.insert(
+            "yaml_config",
+            (
+                "Configuración YAML (.yaml/.yml)",
+                "YAML configuration (.yaml/.yml)",
+            ),
+        );
+        translations.insert(
+            "ini_config",
+            ("Configuración INI (.ini)", "INI configuration (.ini)"),
+        );
+        translations.insert(
+            "value_field_generate",
+            (
+                "Valor | [Ctrl+g] Generar | Usa \"texto\" para forzar string",
+                "Value | [Ctrl+g] Generate | Use \"text\" to force string",
+            ),
+        );
+        translations.insert(
+            "new_folder_title_help",
+            (
+                "📁 Nueva Carpeta | [Enter] Crear | [Esc] Cancelar",
+                "📁 New Folder | [Enter] Create | [Esc] Cancel",
+            ),
+        );
+        translations.insert(
+            "rename_title_help",
+            (
+                "✏️ Renombrar | [Enter] Guardar | [Esc] Cancelar",
+                "✏️ Rename | [Enter] Save | [Esc] Cancel",
+            ),
+        );
+        translations.insert(
+            "create_encrypted_file_title_help",
+            (
+                "🔐 Crear archivo encriptado con SOPS | [Enter] Crear | [Esc] Cancelar",
+                "🔐 Create encrypted file with SOPS | [Enter] Create | [Esc] Cancel",
+            ),
+        );
         translations.insert("select_file_format_title_help", ("📄 Seleccionar formato de archivo para SOPS | [↑↓] Navegar | [Enter] Continuar | [Esc] Cancelar", "📄 Select file format for SOPS | [↑↓] Navigate | [Enter] Continue | [Esc] Cancel"));
 
         // Settings
@@ -786,6 +859,691 @@ impl I18n {
             ),
         );
 
+        // Historial
+        translations.insert("history_title", ("📜 Historial de", "📜 History of"));
+        translations.insert("history_added", ("agregada", "added"));
+        translations.insert("history_removed", ("eliminada", "removed"));
+        translations.insert("history_changed", ("cambiada", "changed"));
+        translations.insert(
+            "history_empty",
+            (
+                "Sin cambios en el historial git",
+                "No changes found in git history",
+            ),
+        );
+        translations.insert(
+            "history_error",
+            (
+                "❌ No se pudo leer el historial git",
+                "❌ Could not read git history",
+            ),
+        );
+        translations.insert("history_filtered", ("filtrado por", "filtered by"));
+        translations.insert("history_loading", ("cargando…", "loading…"));
+        translations.insert("history_unreadable", ("sin descifrar", "unreadable"));
+        translations.insert(
+            "history_file_deleted",
+            ("archivo eliminado", "file deleted"),
+        );
+        translations.insert("history_date", ("Fecha", "Date"));
+        translations.insert("history_commit", ("Commit", "Commit"));
+        translations.insert("history_author", ("Autor", "Author"));
+        translations.insert(
+            "footer_history",
+            (
+                "[↑↓] Navegar | [f] Filtrar por secreto seleccionado | [Esc] Cerrar",
+                "[↑↓] Navigate | [f] Filter by selected secret | [Esc] Close",
+            ),
+        );
+        translations.insert(
+            "help_history",
+            (
+                "Ver historial git de cambios por llave",
+                "Show git history of changes per key",
+            ),
+        );
+        translations.insert("cmd_history", ("[h] Historial", "[h] History"));
+
+        // Auditoría
+        translations.insert("audit_title", ("📋 Log de auditoría", "📋 Audit log"));
+        translations.insert("audit_all_actions", ("todas", "all"));
+        translations.insert("audit_action", ("acción", "action"));
+        translations.insert("audit_file", ("archivo", "file"));
+        translations.insert("audit_header_time", ("Hora", "Time"));
+        translations.insert("audit_header_action", ("Acción", "Action"));
+        translations.insert("audit_header_file", ("Archivo", "File"));
+        translations.insert(
+            "audit_empty",
+            ("Sin eventos registrados", "No events recorded"),
+        );
+        translations.insert(
+            "footer_audit",
+            (
+                "[↑↓] Navegar | [f] Filtrar por archivo | [a] Filtrar por acción | [Esc] Cerrar",
+                "[↑↓] Navigate | [f] Filter by file | [a] Filter by action | [Esc] Close",
+            ),
+        );
+        translations.insert(
+            "help_audit",
+            (
+                "Ver log de auditoría de accesos y cambios",
+                "Show audit log of access and changes",
+            ),
+        );
+
+        // Metadatos de expiración
+        translations.insert("meta_title", ("⏳ Metadatos de", "⏳ Metadata for"));
+        translations.insert("meta_owner", ("Responsable", "Owner"));
+        translations.insert(
+            "meta_expires",
+            ("Expira (AAAA-MM-DD)", "Expires (YYYY-MM-DD)"),
+        );
+        translations.insert(
+            "meta_rotation_days",
+            ("Rotar cada N días", "Rotate every N days"),
+        );
+        translations.insert("meta_created", ("Creado", "Created"));
+        translations.insert("meta_rotated", ("Rotado", "Rotated"));
+        translations.insert("meta_saved", ("Metadatos guardados", "Metadata saved"));
+        translations.insert("error_meta_date", ("Fecha inválida", "Invalid date"));
+        translations.insert(
+            "error_meta_days",
+            ("Número de días inválido", "Invalid number of days"),
+        );
+        translations.insert("cmd_metadata", ("[m] Metadatos", "[m] Metadata"));
+        translations.insert(
+            "footer_metadata",
+            (
+                "[Tab/↑↓] Cambiar campo | [Enter] Guardar | [Esc] Cancelar",
+                "[Tab/↑↓] Switch field | [Enter] Save | [Esc] Cancel",
+            ),
+        );
+        translations.insert(
+            "help_metadata",
+            (
+                "Editar responsable, expiración y rotación",
+                "Edit owner, expiry and rotation period",
+            ),
+        );
+
+        // Generador y rotación
+        translations.insert("gen_title", ("🎲 Generador", "🎲 Generator"));
+        translations.insert("gen_rotate_title", ("🔄 Rotar", "🔄 Rotate"));
+        translations.insert("gen_password", ("🔐 Contraseña", "🔐 Password"));
+        translations.insert("gen_token", ("🎟 Token", "🎟 Token"));
+        translations.insert("gen_length", ("longitud", "length"));
+        translations.insert("gen_special", ("especiales", "special"));
+        translations.insert("gen_numbers", ("números", "numbers"));
+        translations.insert("gen_format", ("formato", "format"));
+        translations.insert(
+            "footer_generator",
+            (
+                "[↑↓] Tipo | [←→] Cambiar valor | [+/-] Longitud | [s] Especiales/separador | [n] Números | [c] Mayúsculas | [p] Llave pública | [Enter] Generar | [Esc] Cancelar",
+                "[↑↓] Type | [←→] Change value | [+/-] Length | [s] Special/separator | [n] Numbers | [c] Capitalize | [p] Public key | [Enter] Generate | [Esc] Cancel",
+            ),
+        );
+        translations.insert("rotated", ("Secreto rotado", "Secret rotated"));
+        translations.insert("rotated_files", ("archivos marcados", "marked files"));
+        translations.insert("rotation_undone", ("Rotación deshecha", "Rotation undone"));
+        translations.insert(
+            "batch_rotation_undone",
+            ("Rotación en lote deshecha", "Batch rotation undone"),
+        );
+        translations.insert(
+            "no_rotation_to_undo",
+            ("No hay rotación para deshacer", "No rotation to undo"),
+        );
+        translations.insert("cmd_rotate", ("[r] Rotar", "[r] Rotate"));
+        translations.insert(
+            "help_rotate",
+            (
+                "Rotar el secreto con su política recordada",
+                "Rotate secret using its remembered policy",
+            ),
+        );
+        translations.insert(
+            "help_rotate_marked",
+            (
+                "Rotar la llave en todos los archivos marcados",
+                "Rotate key in all marked files",
+            ),
+        );
+        translations.insert(
+            "help_rotate_undo",
+            (
+                "Deshacer rotación pendiente del secreto",
+                "Undo pending rotation of the secret",
+            ),
+        );
+        translations.insert(
+            "help_rotate_undo_batch",
+            (
+                "Deshacer la última rotación en lote",
+                "Undo the last batch rotation",
+            ),
+        );
+
+        // Políticas y fortaleza
+        translations.insert("gen_policy", ("📜 Política", "📜 Policy"));
+        translations.insert("strength", ("Fortaleza", "Strength"));
+        translations.insert("strength_very_weak", ("muy débil", "very weak"));
+        translations.insert("strength_weak", ("débil", "weak"));
+        translations.insert("strength_fair", ("aceptable", "fair"));
+        translations.insert("strength_strong", ("fuerte", "strong"));
+        translations.insert("strength_very_strong", ("muy fuerte", "very strong"));
+
+        // Frases de contraseña
+        translations.insert(
+            "gen_passphrase",
+            ("🎲 Frase de contraseña", "🎲 Passphrase"),
+        );
+        translations.insert("gen_words", ("palabras", "words"));
+        translations.insert("gen_separator", ("separador", "separator"));
+        translations.insert("gen_capitalize", ("mayúsculas", "capitalize"));
+
+        // Material criptográfico
+        translations.insert("gen_key", ("🔑 Llave", "🔑 Key"));
+        translations.insert("gen_bytes", ("bytes", "bytes"));
+        translations.insert(
+            "gen_public_sibling",
+            ("guardar pública", "store public key"),
+        );
+
+        // Bloqueo de sesión
+        translations.insert("lock_title", ("🔒 Sesión bloqueada", "🔒 Session locked"));
+        translations.insert(
+            "lock_prompt",
+            ("Autentícate para continuar", "Authenticate to continue"),
+        );
+        translations.insert(
+            "lock_with_passphrase",
+            ("frase de desbloqueo", "unlock passphrase"),
+        );
+        translations.insert(
+            "lock_with_identity",
+            (
+                "pega una identidad age (AGE-SECRET-KEY-...)",
+                "paste an age identity (AGE-SECRET-KEY-...)",
+            ),
+        );
+        translations.insert("unlocked", ("Sesión desbloqueada", "Session unlocked"));
+        translations.insert(
+            "unlock_failed",
+            ("Autenticación fallida", "Authentication failed"),
+        );
+        translations.insert(
+            "footer_locked",
+            (
+                "[Enter] Desbloquear | [Esc] Borrar | [Ctrl+C] Salir",
+                "[Enter] Unlock | [Esc] Clear | [Ctrl+C] Quit",
+            ),
+        );
+        translations.insert(
+            "footer_lock_passphrase",
+            (
+                "[Enter] Guardar (vacía = eliminar) | [Esc] Cancelar",
+                "[Enter] Save (empty = remove) | [Esc] Cancel",
+            ),
+        );
+        translations.insert(
+            "settings_lock_passphrase",
+            ("🔑 Frase de desbloqueo", "🔑 Unlock passphrase"),
+        );
+        translations.insert(
+            "settings_lock_passphrase_repeat",
+            ("🔑 Repite la frase", "🔑 Repeat the passphrase"),
+        );
+        translations.insert(
+            "lock_passphrase_mismatch",
+            (
+                "Las frases no coinciden, vuelve a escribirla",
+                "Passphrases do not match, type it again",
+            ),
+        );
+        translations.insert(
+            "settings_lock_passphrase_help",
+            (
+                "Se pide al desbloquear la sesión; también sirve tu identidad age",
+                "Required to unlock the session; your age identity also works",
+            ),
+        );
+        translations.insert("settings_configured", ("configurada", "set"));
+        translations.insert("settings_not_configured", ("sin configurar", "not set"));
+        translations.insert(
+            "settings_enter_change",
+            ("[Enter] cambiar", "[Enter] change"),
+        );
+        translations.insert(
+            "lock_passphrase_saved",
+            ("Frase de desbloqueo guardada", "Unlock passphrase saved"),
+        );
+        translations.insert(
+            "lock_passphrase_cleared",
+            ("Frase de desbloqueo eliminada", "Unlock passphrase removed"),
+        );
+        translations.insert("help_lock", ("Bloquear la sesión", "Lock the session"));
+
+        translations.insert(
+            "save_before_lock",
+            (
+                "Hay cambios sin guardar: guarda con [s] antes de bloquear",
+                "There are unsaved changes: save with [s] before locking",
+            ),
+        );
+        translations.insert(
+            "session_locked_manual",
+            ("🔒 Sesión bloqueada", "🔒 Session locked"),
+        );
+
+        translations.insert(
+            "unlock_identities_title",
+            ("🔐 Identidades age cifradas", "🔐 Encrypted age identities"),
+        );
+        translations.insert(
+            "enter_identity_passphrase",
+            (
+                "Frase del archivo de identidades:",
+                "Identity file passphrase:",
+            ),
+        );
+        translations.insert(
+            "identities_unlocked",
+            ("Identidades desbloqueadas", "Identities unlocked"),
+        );
+        translations.insert(
+            "identities_skipped",
+            (
+                "Sin identidades: pulsa 'k' para desbloquearlas",
+                "No identities loaded: press 'k' to unlock them",
+            ),
+        );
+        translations.insert(
+            "identity_file_read_only",
+            (
+                "El archivo de identidades está cifrado con frase; edítalo con age",
+                "The identity file is passphrase-encrypted; edit it with age",
+            ),
+        );
+        translations.insert(
+            "footer_unlock_identities",
+            (
+                "[Enter] Desbloquear | [Esc] Continuar sin llaves | [Ctrl+C] Salir",
+                "[Enter] Unlock | [Esc] Continue without keys | [Ctrl+C] Quit",
+            ),
+        );
+
+        translations.insert(
+            "key_from_env_read_only",
+            (
+                "La llave viene de $SOPS_AGE_KEY y no se puede eliminar aquí",
+                "This key comes from $SOPS_AGE_KEY and can't be deleted here",
+            ),
+        );
+
+        translations.insert(
+            "ssh_key_read_only",
+            (
+                "Las llaves SSH se gestionan en ~/.ssh, no desde aquí",
+                "SSH keys are managed in ~/.ssh, not from here",
+            ),
+        );
+
+        translations.insert(
+            "plugin_missing",
+            ("plugin no instalado", "plugin not installed"),
+        );
+
+        translations.insert(
+            "key_actions_cmd",
+            (
+                "[c] Copiar pública | [e] Comentario | [x] Exportar",
+                "[c] Copy public | [e] Comment | [x] Export",
+            ),
+        );
+        translations.insert(
+            "key_transfer_cmd",
+            (
+                "[i] Importar | [b] Respaldo | [u] Uso",
+                "[i] Import | [b] Backup | [u] Usage",
+            ),
+        );
+        translations.insert(
+            "footer_key_io",
+            (
+                "[Enter] Confirmar | [Esc] Cancelar",
+                "[Enter] Confirm | [Esc] Cancel",
+            ),
+        );
+        translations.insert(
+            "public_key_copied",
+            ("✓ Llave pública copiada", "✓ Public key copied"),
+        );
+        translations.insert(
+            "no_public_key",
+            (
+                "La llave no tiene llave pública conocida",
+                "The key has no known public key",
+            ),
+        );
+        translations.insert(
+            "import_keys_title",
+            (" Importar identidades ", " Import identities "),
+        );
+        translations.insert(
+            "import_keys_prompt",
+            (
+                "Ruta de un archivo de identidades o pega las llaves:",
+                "Path to an identity file or paste the keys:",
+            ),
+        );
+        translations.insert(
+            "identities_pasted",
+            ("identidades pegadas", "identities pasted"),
+        );
+        translations.insert(
+            "keys_imported",
+            ("identidades importadas", "identities imported"),
+        );
+        translations.insert(
+            "no_keys_imported",
+            (
+                "No se encontraron identidades nuevas",
+                "No new identities found",
+            ),
+        );
+        translations.insert(
+            "export_key_title",
+            (" Exportar identidad ", " Export identity "),
+        );
+        translations.insert(
+            "export_key_prompt",
+            ("Archivo de destino:", "Destination file:"),
+        );
+        translations.insert(
+            "key_passphrase_title",
+            (" Frase de la identidad ", " Identity passphrase "),
+        );
+        translations.insert(
+            "export_passphrase_prompt",
+            (
+                "Frase para cifrar la identidad:",
+                "Passphrase to encrypt the identity:",
+            ),
+        );
+        translations.insert(
+            "export_passphrase_required",
+            (
+                "Se necesita una frase: la identidad no se exporta en texto plano",
+                "A passphrase is required: identities are never exported in plaintext",
+            ),
+        );
+        translations.insert("key_exported", ("Identidad exportada", "Identity exported"));
+        translations.insert(
+            "export_file_exists",
+            (
+                "El archivo ya existe, elige otra ruta",
+                "The file already exists, choose another path",
+            ),
+        );
+        translations.insert(
+            "key_comment_title",
+            (" Comentario de la llave ", " Key comment "),
+        );
+        translations.insert(
+            "key_comment_prompt",
+            (
+                "Nuevo comentario (vacío = eliminarlo):",
+                "New comment (empty = remove it):",
+            ),
+        );
+        translations.insert(
+            "key_comment_saved",
+            ("✓ Comentario actualizado", "✓ Comment updated"),
+        );
+        translations.insert(
+            "backup_keys_title",
+            (" Respaldar llaves ", " Back up keys "),
+        );
+        translations.insert(
+            "backup_keys_prompt",
+            (
+                "Recipient del respaldo (age1..., ssh-..., nombre o @grupo):",
+                "Backup recipient (age1..., ssh-..., name or @group):",
+            ),
+        );
+        translations.insert("keys_backed_up", ("Llaves respaldadas", "Keys backed up"));
+
+        translations.insert(
+            "no_dependent_files",
+            (
+                "Ningún archivo conocido depende solo de esta llave",
+                "No known file depends only on this key",
+            ),
+        );
+        translations.insert(
+            "dependent_files",
+            (
+                "archivos solo se pueden descifrar con esta llave:",
+                "files can only be decrypted with this key:",
+            ),
+        );
+        translations.insert("more_files", ("archivos más", "more files"));
+        translations.insert(
+            "key_backup_notice",
+            (
+                "Se guardará un respaldo cifrado del archivo de llaves",
+                "An encrypted backup of the keys file will be kept",
+            ),
+        );
+        translations.insert("backup_saved", ("respaldo", "backup"));
+        translations.insert(
+            "backup_passphrase_prompt",
+            (
+                "No quedan otras llaves: frase para cifrar el respaldo:",
+                "No other keys left: passphrase to encrypt the backup:",
+            ),
+        );
+        translations.insert(
+            "backup_passphrase_required",
+            (
+                "Se necesita una frase para el respaldo",
+                "A passphrase is required for the backup",
+            ),
+        );
+
+        translations.insert(
+            "insecure_permissions_title",
+            (" Permisos inseguros ", " Insecure permissions "),
+        );
+        translations.insert(
+            "insecure_identity_files",
+            (
+                "Otros usuarios pueden acceder a estos archivos de identidades:",
+                "Other users can access these identity files:",
+            ),
+        );
+        translations.insert(
+            "fix_permissions_question",
+            (
+                "¿Restringirlos a tu usuario (chmod 600)?",
+                "Restrict them to your user (chmod 600)?",
+            ),
+        );
+        translations.insert(
+            "footer_fix_permissions",
+            (
+                "[y/s] Corregir | [n/Esc] Ignorar",
+                "[y/s] Fix | [n/Esc] Ignore",
+            ),
+        );
+        translations.insert(
+            "permissions_fixed",
+            ("✓ Permisos corregidos", "✓ Permissions fixed"),
+        );
+        translations.insert(
+            "permissions_ignored",
+            (
+                "⚠ Archivos de identidades con permisos inseguros",
+                "⚠ Identity files with insecure permissions",
+            ),
+        );
+
+        translations.insert("key_usage_title", (" Uso de las llaves ", " Key usage "));
+        translations.insert("sops_files_found", ("archivos SOPS", "SOPS files"));
+        translations.insert(
+            "usage_local_identities",
+            ("Identidades locales", "Local identities"),
+        );
+        translations.insert(
+            "usage_known_recipients",
+            ("Otros recipients", "Other recipients"),
+        );
+        translations.insert("usage_unused", ("sin uso", "unused"));
+        translations.insert("files", ("archivos", "files"));
+        translations.insert(
+            "usage_orphaned",
+            (
+                "Ninguna llave local puede descifrar",
+                "No local key can decrypt",
+            ),
+        );
+        translations.insert(
+            "usage_no_orphans",
+            (
+                "✓ Todos los archivos se pueden descifrar con una llave local",
+                "✓ Every file can be decrypted with a local key",
+            ),
+        );
+        translations.insert(
+            "footer_key_usage",
+            (
+                "[↑↓/PgUp/PgDn] Desplazar | [r] Volver a escanear | [Esc] Cerrar",
+                "[↑↓/PgUp/PgDn] Scroll | [r] Rescan | [Esc] Close",
+            ),
+        );
+
+        translations.insert(
+            "sops_rules_title",
+            ("Reglas (en orden)", "Rules (in order)"),
+        );
+        translations.insert(
+            "sops_no_rules",
+            ("Sin reglas: [a] añadir", "No rules: [a] add"),
+        );
+        translations.insert("sops_any_file", ("(cualquier archivo)", "(any file)"));
+        translations.insert("sops_other_fields", ("Otros campos", "Other fields"));
+        translations.insert(
+            "sops_rule_matches",
+            (
+                "Archivos que resuelve esta regla",
+                "Files resolved by this rule",
+            ),
+        );
+        translations.insert(
+            "sops_unsaved_changes",
+            (
+                "Hay cambios sin guardar: guarda con Ctrl+S antes de abrir el editor externo",
+                "There are unsaved changes: save with Ctrl+S before opening the external editor",
+            ),
+        );
+        translations.insert(
+            "sops_comments_lost",
+            (
+                "El .sops.yaml tiene comentarios que se perderán al guardar: pulsa Ctrl+S otra vez para guardar igualmente",
+                "The .sops.yaml has comments that will be lost on save: press Ctrl+S again to save anyway",
+            ),
+        );
+        translations.insert("footer_edit_sops_field", ("[Enter] Aplicar | [Esc] Cancelar | Recipients: llaves, nombres o @grupos separados por comas; grupos con |", "[Enter] Apply | [Esc] Cancel | Recipients: keys, names or @groups separated by commas; groups with |"));
+
+        translations.insert("sops_rule", ("Regla", "Rule"));
+        translations.insert(
+            "sops_rule_added",
+            (
+                "Regla añadida al .sops.yaml: regla",
+                "Rule added to .sops.yaml: rule",
+            ),
+        );
+        translations.insert(
+            "sops_rule_reused",
+            (
+                "El archivo ya usa esos recipients: regla",
+                "The file already uses those recipients: rule",
+            ),
+        );
+        translations.insert(
+            "sops_rule_shadowed_by",
+            (
+                "nunca se aplica, la tapa la regla",
+                "never applies, shadowed by rule",
+            ),
+        );
+        translations.insert(
+            "sops_resolves_to",
+            ("El archivo usa la regla", "The file resolves to rule"),
+        );
+        translations.insert(
+            "sops_no_matching_rule",
+            (
+                "Ninguna regla coincide: se añadirá una al final de",
+                "No rule matches: one will be appended to",
+            ),
+        );
+        translations.insert(
+            "sops_new_rule_hint",
+            (
+                "Con otros recipients se añadirá una regla delante de ella",
+                "Choosing other recipients adds a rule before it",
+            ),
+        );
+
+        translations.insert(
+            "sops_no_rule",
+            ("ninguna regla coincide", "no rule matches"),
+        );
+        translations.insert(
+            "recipient_drift",
+            (
+                "Recipients distintos de la regla",
+                "Recipients differ from rule",
+            ),
+        );
+        translations.insert("cmd_rekey", ("re-cifrar", "re-key"));
+        translations.insert(
+            "no_recipient_drift",
+            (
+                "✓ Los recipients ya coinciden con la regla",
+                "✓ Recipients already match the rule",
+            ),
+        );
+        translations.insert(
+            "save_before_rekey",
+            (
+                "Guarda los cambios antes de re-cifrar",
+                "Save your changes before re-keying",
+            ),
+        );
+        translations.insert(
+            "error_rekey",
+            (
+                "Error al actualizar los recipients",
+                "Error updating recipients",
+            ),
+        );
+        translations.insert(
+            "rekeyed_to_rule",
+            (
+                "Recipients actualizados según la regla",
+                "Recipients updated to rule",
+            ),
+        );
+        translations.insert(
+            "help_rekey",
+            (
+                "Re-cifrar para los recipients de la regla del .sops.yaml",
+                "Re-key to the recipients of the .sops.yaml rule",
+            ),
+        );
+
         Self { lang, translations }
     }
 
@@ -0,0 +1,297 @@
+use crate::perms::write_private_atomic;
+use crate::sops::{
+    get_sops_recipients, is_encrypted_identity_file, parse_age_keys, AgeKey, KeySource,
+};
+use age::secrecy::SecretString;
+use anyhow::{Context, Result};
+use std::{
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+use zeroize::Zeroizing;
+
+/// Bloque de una identidad en el formato de `age-keygen`, con su comentario justo
+/// antes de la llave para que `parse_age_keys` lo recupere
+pub fn identity_block(key: &AgeKey) -> String {
+    let mut block = String::new();
+    if let Some(public_key) = &key.public_key {
+        block.push_str(&format!("# public key: {}\n", public_key));
+    }
+    if let Some(comment) = key.comment.as_deref().filter(|c| !is_metadata_comment(c)) {
+        block.push_str(&format!("# {}\n", comment));
+    }
+    block.push_str(&key.key);
+    block.push('\n');
+    block
+}
+
+/// Líneas que escribe `age-keygen` (o un plugin) y que no son el nombre de la llave
+pub fn is_metadata_comment(comment: &str) -> bool {
+    comment.split_once(':').is_some_and(|(label, _)| {
+        matches!(
+            label.trim().to_lowercase().as_str(),
+            "created" | "public key" | "recipient"
+        )
+    })
+}
+
+/// Identidades de un archivo o texto pegado
+pub fn parse_identities(text: &str) -> Vec<AgeKey> {
+    parse_age_keys(text, &KeySource::Env)
+}
+
+/// Identidades que no están ya en `existing`; las llaves age inválidas se descartan
+pub fn new_identities(keys: Vec<AgeKey>, existing: &[AgeKey]) -> Vec<AgeKey> {
+    let mut found: Vec<AgeKey> = Vec::new();
+    for key in keys {
+        let valid = !key.key.starts_with("AGE-SECRET-KEY-")
+            || age::x25519::Identity::from_str(&key.key).is_ok();
+        let known = existing
+            .iter()
+            .chain(found.iter())
+            .any(|k| k.key == key.key);
+        if valid && !known {
+            found.push(key);
+        }
+    }
+    found
+}
+
+/// Añade las identidades al final del archivo de llaves, separadas por una línea vacía
+pub fn append_identities(path: &Path, keys: &[AgeKey]) -> Result<()> {
+    let mut content = fs::read_to_string(path).unwrap_or_default();
+    for key in keys {
+        if !content.trim().is_empty() {
+            content = format!("{}\n\n", content.trim_end());
+        }
+        content.push_str(&identity_block(key));
+    }
+    write_private_atomic(path, content.as_bytes())
+}
+
+/// Quita la llave y los comentarios inmediatamente encima (su bloque de `age-keygen`)
+pub fn remove_identity(content: &str, key: &str) -> Result<String> {
+    let mut lines: Vec<&str> = content.lines().collect();
+    let idx = lines
+        .iter()
+        .position(|l| l.trim() == key)
+        .context("La llave no está en el archivo")?;
+    let start = lines[..idx]
+        .iter()
+        .rposition(|l| !l.trim().starts_with('#'))
+        .map_or(0, |i| i + 1);
+    lines.drain(start..=idx);
+
+    // Sin líneas vacías sobrantes donde estaba el bloque
+    let mut result: Vec<&str> = Vec::new();
+    for line in lines {
+        if line.trim().is_empty() && result.last().is_none_or(|l| l.trim().is_empty()) {
+            continue;
+        }
+        result.push(line);
+    }
+    while result.last().is_some_and(|l| l.trim().is_empty()) {
+        result.pop();
+    }
+    if result.is_empty() {
+        return Ok(String::new());
+    }
+    Ok(format!("{}\n", result.join("\n")))
+}
+
+/// Cambia el comentario de la llave; uno vacío lo elimina
+pub fn set_comment(content: &str, key: &str, comment: &str) -> Result<String> {
+    let mut lines: Vec<String> = content.lines().map(str::to_string).collect();
+    let idx = lines
+        .iter()
+        .position(|l| l.trim() == key)
+        .context("La llave no está en el archivo")?;
+
+    let previous = idx
+        .checked_sub(1)
+        .map(|i| lines[i].trim())
+        .filter(|l| l.starts_with('#'))
+        .map(|l| l.trim_start_matches('#').trim().to_string());
+    let has_name = previous.as_deref().is_some_and(|c| !is_metadata_comment(c));
+
+    let comment = comment.trim();
+    match (has_name, comment.is_empty()) {
+        (true, true) => {
+            lines.remove(idx - 1);
+        }
+        (true, false) => lines[idx - 1] = format!("# {}", comment),
+        (false, false) => lines.insert(idx, format!("# {}", comment)),
+        (false, true) => {}
+    }
+
+    let mut result = lines.join("\n");
+    if content.ends_with('\n') {
+        result.push('\n');
+    }
+    Ok(result)
+}
+
+/// Cifra con frase (scrypt) en formato armado, compatible con `age -d`
+pub fn encrypt_with_passphrase(plaintext: &[u8], passphrase: &str) -> Result<Vec<u8>> {
+    let encryptor =
+        age::Encryptor::with_user_passphrase(SecretString::from(passphrase.to_string()));
+    encrypt_armored(encryptor, plaintext)
+}
+
+/// Cifra para recipients age (`age1...`) o SSH (`ssh-ed25519 ...`, `ssh-rsa ...`)
+pub fn encrypt_to_recipients(plaintext: &[u8], recipients: &[String]) -> Result<Vec<u8>> {
+    let mut parsed: Vec<Box<dyn age::Recipient>> = Vec::new();
+    for recipient in recipients {
+        if let Ok(r) = age::x25519::Recipient::from_str(recipient) {
+            parsed.push(Box::new(r));
+        } else if let Ok(r) = age::ssh::Recipient::from_str(recipient) {
+            parsed.push(Box::new(r));
+        } else {
+            anyhow::bail!("Recipient no soportado: {}", recipient);
+        }
+    }
+    if parsed.is_empty() {
+        anyhow::bail!("No se indicó ningún recipient");
+    }
+
+    let encryptor = age::Encryptor::with_recipients(parsed.iter().map(|r| r.as_ref()))
+        .map_err(|e| anyhow::anyhow!("No se pudo cifrar: {}", e))?;
+    encrypt_armored(encryptor, plaintext)
+}
+
+fn encrypt_armored(encryptor: age::Encryptor, plaintext: &[u8]) -> Result<Vec<u8>> {
+    use age::armor::{ArmoredWriter, Format};
+
+    let mut output = Vec::new();
+    let armored = ArmoredWriter::wrap_output(&mut output, Format::AsciiArmor)?;
+    let mut writer = encryptor.wrap_output(armored)?;
+    writer.write_all(plaintext)?;
+    writer.finish()?.finish()?;
+    Ok(output)
+}
+
+/// Une los archivos de identidades en texto plano, cada uno precedido de su ruta como
+/// comentario; el resultado sigue siendo un archivo de identidades válido. Los que no
+/// existen o ya están cifrados con frase se omiten.
+pub fn bundle_identity_files(paths: &[PathBuf]) -> Result<Zeroizing<String>> {
+    let mut bundle = Zeroizing::new(String::new());
+    for path in paths.iter().filter(|p| p.is_file()) {
+        let content = Zeroizing::new(fs::read(path)?);
+        if is_encrypted_identity_file(&content) {
+            continue;
+        }
+        let text = std::str::from_utf8(&content)
+            .with_context(|| format!("{} no es texto", path.display()))?;
+        bundle.push_str(&format!("# {}\n", path.display()));
+        bundle.push_str(text.trim_end());
+        bundle.push_str("\n\n");
+    }
+    if bundle.is_empty() {
+        anyhow::bail!("No hay archivos de identidades en texto plano que respaldar");
+    }
+    Ok(bundle)
+}
+
+/// Respaldo cifrado del archivo de llaves: `<dir>/backups/<nombre>-<fecha>.age`
+pub fn backup_path(keys_path: &Path, timestamp: &str) -> PathBuf {
+    let dir = keys_path.parent().unwrap_or(Path::new("."));
+    let name = keys_path
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "keys".to_string());
+    dir.join("backups")
+        .join(format!("{}-{}.age", name, timestamp))
+}
+
+/// Profundidad máxima al buscar archivos SOPS, para no recorrer todo el disco
+const MAX_SCAN_DEPTH: usize = 8;
+
+/// Archivos bajo `dir`, sin entrar en directorios ocultos ni de dependencias
+pub fn tree_files(dir: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    collect_files(dir, 0, &mut files);
+    files.sort();
+    files
+}
+
+/// Copia en claro que deja AgeSmith mientras guarda `path` (`secrets.yaml.bak`)
+pub fn save_backup_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".bak");
+    path.with_file_name(name)
+}
+
+/// Ruta relativa a `root` para mostrarla en un informe; las de fuera se muestran completas
+pub fn display_relative<'a>(root: &Path, path: &'a Path) -> std::path::Display<'a> {
+    path.strip_prefix(root).unwrap_or(path).display()
+}
+
+/// Archivos SOPS con recipients age bajo `roots` (archivos o directorios), ordenados
+pub fn sops_files(roots: &[PathBuf]) -> Vec<(PathBuf, Vec<String>)> {
+    let mut files = Vec::new();
+    for root in roots {
+        collect_files(root, 0, &mut files);
+    }
+    files.sort();
+    files.dedup();
+
+    files
+        .into_iter()
+        .filter_map(|path| {
+            let recipients = get_sops_recipients(&path).ok()?;
+            (!recipients.is_empty()).then_some((path, recipients))
+        })
+        .collect()
+}
+
+/// Archivos SOPS bajo `roots` que `key` puede descifrar y ninguna de las llaves de `others`
+pub fn files_depending_on(key: &AgeKey, others: &[AgeKey], roots: &[PathBuf]) -> Vec<PathBuf> {
+    sops_files(roots)
+        .into_iter()
+        .filter(|(_, recipients)| {
+            key.is_recipient_of(recipients)
+                && !others
+                    .iter()
+                    .filter(|k| k.key != key.key)
+                    .any(|k| k.is_recipient_of(recipients))
+        })
+        .map(|(path, _)| path)
+        .collect()
+}
+
+fn collect_files(path: &Path, depth: usize, found: &mut Vec<PathBuf>) {
+    if path.is_file() {
+        // Los archivos SOPS guardan sus metadatos en texto; se omiten los muy grandes
+        let small = fs::metadata(path).is_ok_and(|m| m.len() < 5 * 1024 * 1024);
+        if small {
+            found.push(path.to_path_buf());
+        }
+        return;
+    }
+    if depth >= MAX_SCAN_DEPTH {
+        return;
+    }
+    let Ok(entries) = fs::read_dir(path) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let name = entry.file_name().to_string_lossy().into_owned();
+        // `file_type` no sigue enlaces: un enlace a un directorio ancestro no cicla. Los
+        // enlaces a archivos sí se revisan.
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        if file_type.is_dir() {
+            // Omitir .git, otros directorios ocultos y dependencias; `.env` sí se revisa
+            let skipped = name.starts_with('.') || name == "node_modules" || name == "target";
+            if !skipped {
+                collect_files(&path, depth + 1, found);
+            }
+        } else if file_type.is_file() || path.is_file() {
+            collect_files(&path, depth + 1, found);
+        }
+    }
+}
@@ -0,0 +1,108 @@
+use crate::sops::AgeKey;
+use age::secrecy::SecretString;
+use anyhow::Result;
+use base64::{engine::general_purpose, Engine as _};
+use rand::{rngs::OsRng, RngCore};
+use sha2::{Digest, Sha256};
+use std::{
+    io::{Read, Write},
+    iter,
+    str::FromStr,
+};
+
+/// Prefijo de `lock_passphrase_hash`: un archivo age cifrado con la frase, en base64
+const SCRYPT_PREFIX: &str = "age-scrypt$";
+
+/// Comparación en tiempo constante para no filtrar cuántos bytes coinciden
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// Valor para `lock_passphrase_hash`: un reto cifrado con la frase (scrypt de age).
+/// Solo la frase correcta lo descifra, y cada intento cuesta lo mismo que a `age -d`.
+pub fn hash_passphrase(passphrase: &str) -> Result<String> {
+    let encryptor =
+        age::Encryptor::with_user_passphrase(SecretString::from(passphrase.to_string()));
+    let mut ciphertext = Vec::new();
+    let mut writer = encryptor.wrap_output(&mut ciphertext)?;
+    writer.write_all(b"agesmith")?;
+    writer.finish()?;
+    Ok(format!(
+        "{}{}",
+        SCRYPT_PREFIX,
+        general_purpose::STANDARD.encode(ciphertext)
+    ))
+}
+
+pub fn verify_passphrase(passphrase: &str, stored: &str) -> bool {
+    let Some(ciphertext) = stored
+        .strip_prefix(SCRYPT_PREFIX)
+        .and_then(|b64| general_purpose::STANDARD.decode(b64).ok())
+    else {
+        return false;
+    };
+    let Ok(decryptor) = age::Decryptor::new(&ciphertext[..]) else {
+        return false;
+    };
+    if !decryptor.is_scrypt() {
+        return false;
+    }
+    let identity = age::scrypt::Identity::new(SecretString::from(passphrase.to_string()));
+    let Ok(mut reader) = decryptor.decrypt(iter::once(&identity as &dyn age::Identity)) else {
+        return false;
+    };
+    let mut plaintext = Vec::new();
+    reader.read_to_end(&mut plaintext).is_ok() && plaintext == b"agesmith"
+}
+
+/// Reto cifrado a las identidades age del usuario: solo quien tenga una de ellas
+/// puede descifrarlo para desbloquear la sesión
+pub struct Challenge {
+    ciphertext: Vec<u8>,
+    digest: [u8; 32],
+}
+
+impl Challenge {
+    /// Devuelve `None` si no hay identidades age utilizables
+    pub fn new(keys: &[AgeKey]) -> Result<Option<Self>> {
+        let recipients: Vec<age::x25519::Recipient> = keys
+            .iter()
+            .filter_map(|k| age::x25519::Identity::from_str(&k.key).ok())
+            .map(|identity| identity.to_public())
+            .collect();
+        if recipients.is_empty() {
+            return Ok(None);
+        }
+
+        let mut nonce = [0u8; 32];
+        OsRng.fill_bytes(&mut nonce);
+
+        let encryptor =
+            age::Encryptor::with_recipients(recipients.iter().map(|r| r as &dyn age::Recipient))?;
+        let mut ciphertext = Vec::new();
+        let mut writer = encryptor.wrap_output(&mut ciphertext)?;
+        writer.write_all(&nonce)?;
+        writer.finish()?;
+
+        Ok(Some(Self {
+            ciphertext,
+            digest: Sha256::digest(nonce).into(),
+        }))
+    }
+
+    /// Intenta descifrar el reto con la identidad indicada (`AGE-SECRET-KEY-...`)
+    pub fn verify(&self, identity: &str) -> bool {
+        let Ok(identity) = age::x25519::Identity::from_str(identity.trim()) else {
+            return false;
+        };
+        let Ok(decryptor) = age::Decryptor::new(&self.ciphertext[..]) else {
+            return false;
+        };
+        let Ok(mut reader) = decryptor.decrypt(iter::once(&identity as &dyn age::Identity)) else {
+            return false;
+        };
+        let mut nonce = Vec::new();
+        reader.read_to_end(&mut nonce).is_ok()
+            && constant_time_eq(&Sha256::digest(&nonce), &self.digest)
+    }
+}
@@ -1,24 +1,36 @@
 use anyhow::Result;
 use crossterm::{
     cursor::{SetCursorStyle, Show},
-    event::{self, Event, KeyEventKind},
+    event::{self, DisableBracketedPaste, EnableBracketedPaste, Event, KeyEventKind},
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 use ratatui::{backend::CrosstermBackend, Terminal};
 use std::{env, io::stdout, path::PathBuf, time::Duration};
 
+mod addressbook;
+mod audit;
 mod config;
+mod diff;
+mod doctor;
 mod events;
 mod generator;
+mod git;
 mod help;
 mod i18n;
+mod keystore;
+mod lock;
+mod metadata;
+mod perms;
+mod secmem;
 mod sops;
+mod sopsconfig;
 mod state;
 mod ui;
+mod usage;
 
 use config::{load_config, load_favorites, save_favorites};
-use events::handle_key_event;
+use events::{handle_key_event, handle_paste};
 use sops::load_age_keys;
 use state::App;
 use ui::ui;
@@ -26,6 +38,19 @@ use ui::ui;
 #[tokio::main]
 async fn main() -> Result<()> {
     let args: Vec<String> = env::args().collect();
+
+    // Subcomandos no interactivos
+    match args.get(1).map(String::as_str) {
+        Some("textconv") => return git::run_textconv(&args[2..]),
+        Some("diff-driver") => return git::run_diff_driver(&args[2..]),
+        Some("check-expiry") => {
+            let warning_days = load_config()?.expiry_warning_days;
+            return metadata::run_check_expiry(&args[2..], warning_days);
+        }
+        Some("doctor") => return doctor::run_doctor(&args[2..], &load_config()?.identity_files),
+        _ => {}
+    }
+
     let start_dir = if args.len() >= 2 {
         PathBuf::from(&args[1])
     } else {
@@ -42,12 +67,15 @@ async fn main() -> Result<()> {
         start_dir
     };
 
-    let age_keys = load_age_keys()?;
-    if age_keys.is_empty() {
-        eprintln!("Advertencia: No se encontraron llaves en ~/.config/sops/age/keys.txt");
+    let config = load_config()?;
+    let age_keys = load_age_keys(&config.identity_files)?;
+    if age_keys.is_empty() && sops::encrypted_identity_files(&config.identity_files).is_empty() {
+        eprintln!(
+            "Advertencia: No se encontraron llaves en {}",
+            sops::age_keys_path()?.display()
+        );
     }
 
-    let config = load_config()?;
     let favorites = load_favorites().unwrap_or_default();
     let mut app = App::new(start_dir, config, age_keys, favorites)?;
 
@@ -56,6 +84,7 @@ async fn main() -> Result<()> {
     execute!(
         stdout,
         EnterAlternateScreen,
+        EnableBracketedPaste,
         Show,
         SetCursorStyle::BlinkingBar
     )?;
@@ -63,9 +92,14 @@ async fn main() -> Result<()> {
     let mut terminal = Terminal::new(backend)?;
 
     let res = run_app(&mut terminal, &mut app).await;
+    app.wipe_secrets();
 
     disable_raw_mode()?;
-    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+    execute!(
+        terminal.backend_mut(),
+        DisableBracketedPaste,
+        LeaveAlternateScreen
+    )?;
     terminal.show_cursor()?;
 
     if let Err(err) = &res {
@@ -84,22 +118,33 @@ async fn run_app<B: ratatui::backend::Backend>(
     loop {
         terminal.draw(|f| ui(f, app))?;
         app.clear_expired_message();
+        app.poll_history();
 
         // Check auto-lock
         if app.check_auto_lock() {
+            // Por inactividad se bloquea igual: los cambios sin guardar se descartan
+            let key = if app.is_modified {
+                "session_locked_discarded"
+            } else {
+                "session_locked"
+            };
             app.lock();
-            app.set_temp_message(app.i18n.t("session_locked").to_string());
+            app.set_temp_message(app.i18n.t(key).to_string());
         }
 
         if event::poll(Duration::from_millis(100))? {
-            if let Event::Key(key) = event::read()? {
-                if key.kind != KeyEventKind::Press {
-                    continue;
-                }
-
-                if handle_key_event(app, key)? {
-                    return Ok(());
+            match event::read()? {
+                Event::Key(key) => {
+                    if key.kind != KeyEventKind::Press {
+                        continue;
+                    }
+
+                    if handle_key_event(app, key)? {
+                        return Ok(());
+                    }
                 }
+                Event::Paste(text) => handle_paste(app, &text),
+                _ => {}
             }
         }
     }
@@ -0,0 +1,266 @@
+use crate::generator::GeneratorPolicy;
+use crate::perms::write_private;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Local, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Sufijo del archivo de metadatos (sin cifrar) que acompaña a cada archivo de secretos
+pub const SIDECAR_SUFFIX: &str = ".agesmith.toml";
+
+/// Periodos de rotación que acepta el editor de metadatos (hasta cien años)
+pub const ROTATION_DAYS_RANGE: std::ops::RangeInclusive<u32> = 1..=36500;
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SecretMeta {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rotated: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub owner: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires: Option<NaiveDate>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rotation_days: Option<u32>,
+    /// Política del generador usada en la última rotación
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub policy: Option<GeneratorPolicy>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExpiryStatus {
+    Ok,
+    ExpiringSoon,
+    Expired,
+}
+
+impl SecretMeta {
+    pub fn is_empty(&self) -> bool {
+        *self == SecretMeta::default()
+    }
+
+    /// Fecha límite: la menor entre `expires` y la última rotación + `rotation_days`.
+    /// Un periodo que se sale del calendario no da fecha límite.
+    pub fn due_date(&self) -> Option<NaiveDate> {
+        let by_rotation = self.rotation_days.and_then(|days| {
+            self.rotated
+                .or(self.created)
+                .and_then(|t| t.checked_add_signed(Duration::days(days as i64)))
+                .map(|t| t.date_naive())
+        });
+        match (self.expires, by_rotation) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        }
+    }
+
+    pub fn status(&self, today: NaiveDate, warning_days: i64) -> ExpiryStatus {
+        match self.due_date() {
+            Some(due) if due < today => ExpiryStatus::Expired,
+            Some(due) if (due - today).num_days() <= warning_days => ExpiryStatus::ExpiringSoon,
+            _ => ExpiryStatus::Ok,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FileMetadata {
+    #[serde(default)]
+    pub secrets: BTreeMap<String, SecretMeta>,
+}
+
+impl FileMetadata {
+    pub fn get(&self, key: &str) -> Option<&SecretMeta> {
+        self.secrets.get(key)
+    }
+
+    pub fn entry(&mut self, key: &str) -> &mut SecretMeta {
+        self.secrets.entry(key.to_string()).or_default()
+    }
+
+    pub fn mark_created(&mut self, key: &str) {
+        let now = Utc::now();
+        let meta = self.entry(key);
+        meta.created = Some(now);
+        meta.rotated = Some(now);
+    }
+
+    pub fn mark_rotated(&mut self, key: &str) {
+        self.entry(key).rotated = Some(Utc::now());
+    }
+
+    pub fn rename(&mut self, old: &str, new: &str) {
+        if let Some(meta) = self.secrets.remove(old) {
+            self.secrets.insert(new.to_string(), meta);
+        }
+    }
+
+    pub fn remove(&mut self, key: &str) {
+        self.secrets.remove(key);
+    }
+}
+
+pub fn sidecar_path(file_path: &Path) -> PathBuf {
+    let mut name = file_path
+        .file_name()
+        .map(|n| n.to_os_string())
+        .unwrap_or_default();
+    name.push(SIDECAR_SUFFIX);
+    file_path.with_file_name(name)
+}
+
+pub fn load_metadata(file_path: &Path) -> Result<FileMetadata> {
+    let path = sidecar_path(file_path);
+    if !path.exists() {
+        return Ok(FileMetadata::default());
+    }
+    let content = fs::read_to_string(&path)?;
+    toml::from_str(&content).with_context(|| format!("Metadatos inválidos en {}", path.display()))
+}
+
+/// Guarda los metadatos junto al archivo. Si no queda nada que guardar, elimina el sidecar.
+pub fn save_metadata(file_path: &Path, metadata: &FileMetadata) -> Result<()> {
+    let path = sidecar_path(file_path);
+    let mut metadata = metadata.clone();
+    metadata.secrets.retain(|_, meta| !meta.is_empty());
+
+    if metadata.secrets.is_empty() {
+        if path.exists() {
+            fs::remove_file(&path)?;
+        }
+        return Ok(());
+    }
+
+    write_private(&path, toml::to_string_pretty(&metadata)?.as_bytes())?;
+    Ok(())
+}
+
+#[derive(Debug, Clone)]
+pub struct ExpiryFinding {
+    pub file: PathBuf,
+    pub key: String,
+    pub owner: Option<String>,
+    pub due: NaiveDate,
+    pub status: ExpiryStatus,
+}
+
+fn collect_sidecars(dir: &Path, found: &mut Vec<PathBuf>) -> Result<()> {
+    for entry in fs::read_dir(dir)?.flatten() {
+        let path = entry.path();
+        let name = entry.file_name().to_string_lossy().into_owned();
+        // `file_type` no sigue enlaces: un enlace a un directorio ancestro no cicla
+        let is_dir = entry.file_type().is_ok_and(|t| t.is_dir());
+        if is_dir {
+            // Omitir .git y otros directorios ocultos
+            if !name.starts_with('.') {
+                collect_sidecars(&path, found)?;
+            }
+        } else if name.ends_with(SIDECAR_SUFFIX) {
+            found.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Busca secretos vencidos o por vencer en todos los sidecars bajo `root`
+pub fn scan_expiry(root: &Path, today: NaiveDate, warning_days: i64) -> Result<Vec<ExpiryFinding>> {
+    let mut sidecars = Vec::new();
+    if root.is_dir() {
+        collect_sidecars(root, &mut sidecars)?;
+    } else {
+        sidecars.push(sidecar_path(root));
+    }
+    sidecars.sort();
+
+    let mut findings = Vec::new();
+    for sidecar in sidecars.into_iter().filter(|p| p.exists()) {
+        let content = fs::read_to_string(&sidecar)?;
+        let metadata: FileMetadata = toml::from_str(&content)
+            .with_context(|| format!("Metadatos inválidos en {}", sidecar.display()))?;
+        let name = sidecar.to_string_lossy();
+        let file = PathBuf::from(name.trim_end_matches(SIDECAR_SUFFIX));
+
+        for (key, meta) in metadata.secrets {
+            let status = meta.status(today, warning_days);
+            if let (Some(due), true) = (meta.due_date(), status != ExpiryStatus::Ok) {
+                findings.push(ExpiryFinding {
+                    file: file.clone(),
+                    key,
+                    owner: meta.owner,
+                    due,
+                    status,
+                });
+            }
+        }
+    }
+    Ok(findings)
+}
+
+/// `agesmith check-expiry [--warn-days N] [--strict] [PATH...]`
+///
+/// Termina con error si hay secretos vencidos (o por vencer con `--strict`), para CI.
+pub fn run_check_expiry(args: &[String], default_warning_days: i64) -> Result<()> {
+    let mut warning_days = default_warning_days;
+    let mut strict = false;
+    let mut paths = Vec::new();
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--warn-days" => {
+                warning_days = iter
+                    .next()
+                    .and_then(|v| v.parse().ok())
+                    .context("--warn-days espera un número")?;
+            }
+            "--strict" => strict = true,
+            flag if flag.starts_with("--") => anyhow::bail!("Opción desconocida: {}", flag),
+            _ => paths.push(PathBuf::from(arg)),
+        }
+    }
+    if paths.is_empty() {
+        paths.push(PathBuf::from("."));
+    }
+
+    let today = Local::now().date_naive();
+    let mut findings = Vec::new();
+    for path in &paths {
+        findings.extend(scan_expiry(path, today, warning_days)?);
+    }
+
+    for finding in &findings {
+        let label = match finding.status {
+            ExpiryStatus::Expired => "VENCIDO",
+            _ => "POR VENCER",
+        };
+        println!(
+            "{:<10}  {}  {}  vence {}{}",
+            label,
+            finding.file.display(),
+            finding.key,
+            finding.due,
+            finding
+                .owner
+                .as_ref()
+                .map(|o| format!("  responsable: {}", o))
+                .unwrap_or_default()
+        );
+    }
+
+    let expired = findings
+        .iter()
+        .filter(|f| f.status == ExpiryStatus::Expired)
+        .count();
+    let expiring = findings.len() - expired;
+    println!("{} vencidos, {} por vencer", expired, expiring);
+
+    if expired > 0 || (strict && expiring > 0) {
+        anyhow::bail!("La revisión de vencimientos falló");
+    }
+    Ok(())
+}
@@ -0,0 +1,129 @@
+use anyhow::{Context, Result};
+use std::{
+    fs::{self, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+/// Crea el directorio (y los que falten) accesible solo por el usuario (0700)
+pub fn create_private_dir(dir: &Path) -> Result<()> {
+    let mut builder = fs::DirBuilder::new();
+    builder.recursive(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::DirBuilderExt;
+        builder.mode(0o700);
+    }
+    builder
+        .create(dir)
+        .with_context(|| format!("No se pudo crear {}", dir.display()))
+}
+
+/// Escribe un archivo legible solo por el usuario (0600), también si ya existía
+pub fn write_private(path: &Path, content: &[u8]) -> Result<()> {
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        create_private_dir(parent)?;
+    }
+
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options
+        .open(path)
+        .with_context(|| format!("No se pudo escribir {}", path.display()))?;
+    // `mode` solo aplica al crear: un archivo previo con otros permisos se corrige
+    restrict_permissions(path)?;
+    file.write_all(content)?;
+    Ok(())
+}
+
+/// Agrega al final de un archivo legible solo por el usuario (0600), creándolo si hace falta
+pub fn append_private(path: &Path, content: &[u8]) -> Result<()> {
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        create_private_dir(parent)?;
+    }
+
+    let mut options = OpenOptions::new();
+    options.append(true).create(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options
+        .open(path)
+        .with_context(|| format!("No se pudo escribir {}", path.display()))?;
+    restrict_permissions(path)?;
+    file.write_all(content)?;
+    Ok(())
+}
+
+/// Reemplaza el archivo de forma atómica (temporal 0600 en el mismo directorio + rename),
+/// para no dejar un `keys.txt` a medias si algo falla
+pub fn write_private_atomic(path: &Path, content: &[u8]) -> Result<()> {
+    let file_name = path
+        .file_name()
+        .context("Ruta de archivo inválida")?
+        .to_string_lossy();
+    let tmp = path.with_file_name(format!(".{}.tmp", file_name));
+    write_private(&tmp, content)?;
+    fs::File::open(&tmp)?.sync_all()?;
+    fs::rename(&tmp, path).with_context(|| format!("No se pudo reemplazar {}", path.display()))
+}
+
+/// Reemplaza el archivo de forma atómica (temporal + rename) conservando sus permisos,
+/// para archivos que no son secretos como `.sops.yaml`
+pub fn write_atomic(path: &Path, content: &[u8]) -> Result<()> {
+    let file_name = path
+        .file_name()
+        .context("Ruta de archivo inválida")?
+        .to_string_lossy();
+    let tmp = path.with_file_name(format!(".{}.tmp", file_name));
+    fs::write(&tmp, content).with_context(|| format!("No se pudo escribir {}", tmp.display()))?;
+    if let Ok(metadata) = fs::metadata(path) {
+        fs::set_permissions(&tmp, metadata.permissions())?;
+    }
+    fs::File::open(&tmp)?.sync_all()?;
+    fs::rename(&tmp, path).with_context(|| format!("No se pudo reemplazar {}", path.display()))
+}
+
+/// Permisos del archivo o directorio si el grupo u otros usuarios tienen algún acceso
+#[cfg(unix)]
+pub fn insecure_mode(path: &Path) -> Option<u32> {
+    use std::os::unix::fs::PermissionsExt;
+    let mode = fs::metadata(path).ok()?.permissions().mode() & 0o777;
+    (mode & 0o077 != 0).then_some(mode)
+}
+
+#[cfg(not(unix))]
+pub fn insecure_mode(_path: &Path) -> Option<u32> {
+    None
+}
+
+/// Deja el archivo en 0600 o el directorio en 0700
+pub fn restrict_permissions(path: &Path) -> Result<()> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = if path.is_dir() { 0o700 } else { 0o600 };
+        fs::set_permissions(path, fs::Permissions::from_mode(mode)).with_context(|| {
+            format!("No se pudieron cambiar los permisos de {}", path.display())
+        })?;
+    }
+    #[cfg(not(unix))]
+    let _ = path;
+    Ok(())
+}
+
+/// Archivos existentes de la lista que el grupo u otros usuarios pueden leer o modificar
+pub fn insecure_files(paths: &[PathBuf]) -> Vec<(PathBuf, u32)> {
+    paths
+        .iter()
+        .filter(|p| p.is_file())
+        .filter_map(|p| insecure_mode(p).map(|mode| (p.clone(), mode)))
+        .collect()
+}
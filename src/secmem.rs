@@ -0,0 +1,119 @@
+use std::{ops::Deref, sync::OnceLock};
+use zeroize::{Zeroize, Zeroizing};
+
+/// Valor sensible que se borra de memoria al soltarse
+pub type SecretString = Zeroizing<String>;
+
+/// Pares llave/valor descifrados. Los valores se sobrescriben con ceros al reemplazarse,
+/// eliminarse, limpiarse o al soltar la lista.
+#[derive(Debug, Default, Clone)]
+pub struct SecretList(Vec<(String, String)>);
+
+impl SecretList {
+    pub fn push(&mut self, entry: (String, String)) {
+        lock_memory(entry.1.as_bytes());
+        self.0.push(entry);
+    }
+
+    /// Reemplaza el valor en `idx` borrando el anterior
+    pub fn set_value(&mut self, idx: usize, value: String) {
+        drop(self.replace_value(idx, value));
+    }
+
+    /// Reemplaza el valor en `idx` y devuelve el anterior protegido
+    pub fn replace_value(&mut self, idx: usize, value: String) -> SecretString {
+        lock_memory(value.as_bytes());
+        Zeroizing::new(std::mem::replace(&mut self.0[idx].1, value))
+    }
+
+    /// Renombra la entrada en `idx` sin tocar su valor
+    pub fn set_key(&mut self, idx: usize, key: String) {
+        self.0[idx].0 = key;
+    }
+
+    /// Elimina la entrada en `idx` y devuelve solo la llave
+    pub fn remove(&mut self, idx: usize) -> String {
+        let (key, mut value) = self.0.remove(idx);
+        value.zeroize();
+        key
+    }
+
+    pub fn clear(&mut self) {
+        for (_, value) in self.0.iter_mut() {
+            value.zeroize();
+        }
+        self.0.clear();
+    }
+}
+
+impl From<Vec<(String, String)>> for SecretList {
+    fn from(entries: Vec<(String, String)>) -> Self {
+        for (_, value) in &entries {
+            lock_memory(value.as_bytes());
+        }
+        Self(entries)
+    }
+}
+
+impl Deref for SecretList {
+    type Target = Vec<(String, String)>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl Drop for SecretList {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+/// Evita que las páginas con secretos vayan a swap (mejor esfuerzo).
+/// No se desbloquean: otra variable puede compartir la página, y el límite
+/// RLIMIT_MEMLOCK hace que falle en silencio cuando se agota.
+pub fn lock_memory(bytes: &[u8]) {
+    #[cfg(unix)]
+    if !bytes.is_empty() {
+        // SAFETY: solo se pasa un rango de memoria válido y prestado
+        unsafe {
+            libc::mlock(bytes.as_ptr().cast(), bytes.len());
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = bytes;
+}
+
+/// Límite original de core dumps, para restaurarlo al dejar de haber secretos cargados
+#[cfg(unix)]
+static ORIGINAL_CORE_LIMIT: OnceLock<libc::rlimit> = OnceLock::new();
+#[cfg(not(unix))]
+static ORIGINAL_CORE_LIMIT: OnceLock<()> = OnceLock::new();
+
+/// Desactiva los core dumps (y en Linux el acceso por ptrace) mientras hay secretos en memoria
+pub fn set_core_dumps(enabled: bool) {
+    #[cfg(unix)]
+    // SAFETY: llamadas al sistema con estructuras inicializadas
+    unsafe {
+        let original = ORIGINAL_CORE_LIMIT.get_or_init(|| {
+            let mut limit = libc::rlimit {
+                rlim_cur: 0,
+                rlim_max: 0,
+            };
+            libc::getrlimit(libc::RLIMIT_CORE, &mut limit);
+            limit
+        });
+        let limit = libc::rlimit {
+            rlim_cur: if enabled { original.rlim_cur } else { 0 },
+            rlim_max: original.rlim_max,
+        };
+        libc::setrlimit(libc::RLIMIT_CORE, &limit);
+
+        #[cfg(target_os = "linux")]
+        libc::prctl(libc::PR_SET_DUMPABLE, enabled as libc::c_ulong, 0, 0, 0);
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = (enabled, &ORIGINAL_CORE_LIMIT);
+    }
+}
@@ -0,0 +1,666 @@
+use crate::keystore::tree_files;
+use crate::perms::write_atomic;
+use crate::sops::{plugin_name, recipient_matches};
+use anyhow::{Context, Result};
+use regex::Regex;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_yaml::Value;
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+pub const SOPS_CONFIG_FILE: &str = ".sops.yaml";
+
+/// Campos editables de una regla, en el orden en que se muestran
+pub const RULE_FIELDS: [&str; 4] = ["path_regex", "encrypted_regex", "age", "key_groups"];
+
+/// `.sops.yaml`: las claves que no se editan (stores, destination_rules...) se conservan
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SopsConfig {
+    #[serde(default)]
+    pub creation_rules: Vec<CreationRule>,
+    #[serde(flatten)]
+    pub other: BTreeMap<String, Value>,
+}
+
+/// Regla de creación; SOPS usa la primera cuyo `path_regex` coincide con el archivo
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CreationRule {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path_regex: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub encrypted_regex: Option<String>,
+    #[serde(
+        default,
+        skip_serializing_if = "Vec::is_empty",
+        deserialize_with = "deserialize_recipients",
+        serialize_with = "serialize_recipients"
+    )]
+    pub age: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub key_groups: Vec<KeyGroup>,
+    /// pgp, kms, unencrypted_regex, shamir_threshold...
+    #[serde(flatten)]
+    pub other: BTreeMap<String, Value>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct KeyGroup {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub age: Vec<String>,
+    #[serde(flatten)]
+    pub other: BTreeMap<String, Value>,
+}
+
+/// En las reglas, `age` es una lista separada por comas (a veces en varias líneas con `>`)
+fn deserialize_recipients<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<String>, D::Error> {
+    Ok(match Value::deserialize(deserializer)? {
+        Value::String(s) => split_recipients(&s),
+        Value::Sequence(items) => items
+            .iter()
+            .filter_map(|v| v.as_str())
+            .flat_map(split_recipients)
+            .collect(),
+        _ => Vec::new(),
+    })
+}
+
+fn serialize_recipients<S: Serializer>(
+    recipients: &[String],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&recipients.join(","))
+}
+
+pub fn split_recipients(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|r| !r.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+impl CreationRule {
+    /// Todos los recipients age de la regla, incluidos los de `key_groups`
+    pub fn recipients(&self) -> Vec<String> {
+        let mut recipients = self.age.clone();
+        for group in &self.key_groups {
+            recipients.extend(group.age.iter().cloned());
+        }
+        recipients
+    }
+
+    /// Si la regla aplica al archivo (ruta relativa al directorio del `.sops.yaml`)
+    pub fn matches(&self, relative_path: &str) -> bool {
+        match &self.path_regex {
+            Some(pattern) => Regex::new(pattern).is_ok_and(|re| re.is_match(relative_path)),
+            None => true,
+        }
+    }
+
+    /// Valor de un campo de `RULE_FIELDS` como texto; los grupos se separan con `|`
+    pub fn field_text(&self, field: usize) -> String {
+        match field {
+            0 => self.path_regex.clone().unwrap_or_default(),
+            1 => self.encrypted_regex.clone().unwrap_or_default(),
+            2 => self.age.join(","),
+            _ => self
+                .key_groups
+                .iter()
+                .map(|g| g.age.join(","))
+                .collect::<Vec<_>>()
+                .join(" | "),
+        }
+    }
+
+    /// Asigna un campo desde texto; un texto vacío quita el campo
+    pub fn set_field(&mut self, field: usize, value: &str) {
+        let value = value.trim();
+        let optional = || (!value.is_empty()).then(|| value.to_string());
+        match field {
+            0 => self.path_regex = optional(),
+            1 => self.encrypted_regex = optional(),
+            2 => self.age = split_recipients(value),
+            _ => {
+                let groups: Vec<Vec<String>> = value
+                    .split('|')
+                    .map(split_recipients)
+                    .filter(|g| !g.is_empty())
+                    .collect();
+                // Los grupos existentes conservan sus otras llaves (pgp, kms...)
+                let mut previous = std::mem::take(&mut self.key_groups).into_iter();
+                self.key_groups = groups
+                    .into_iter()
+                    .map(|age| KeyGroup {
+                        age,
+                        other: previous.next().map(|g| g.other).unwrap_or_default(),
+                    })
+                    .collect();
+            }
+        }
+    }
+
+    /// Problemas de la regla: regex inválidas, recipients mal formados o sin llaves
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        for (field, pattern) in [
+            ("path_regex", &self.path_regex),
+            ("encrypted_regex", &self.encrypted_regex),
+        ] {
+            if let Some(Err(e)) = pattern.as_deref().map(Regex::new) {
+                errors.push(format!("{}: {}", field, e));
+            }
+        }
+
+        // SOPS solo admite uno de los selectores de claves a cifrar
+        let mut selectors: Vec<&str> = [
+            "unencrypted_regex",
+            "encrypted_suffix",
+            "unencrypted_suffix",
+        ]
+        .into_iter()
+        .filter(|s| self.other.contains_key(*s))
+        .collect();
+        if self.encrypted_regex.is_some() {
+            selectors.insert(0, "encrypted_regex");
+        }
+        if selectors.len() > 1 {
+            errors.push(format!("Solo se permite uno de: {}", selectors.join(", ")));
+        }
+
+        for recipient in self.recipients() {
+            if !is_valid_recipient(&recipient) {
+                errors.push(format!("Recipient inválido: {}", recipient));
+            }
+        }
+
+        let other_keys = [
+            "pgp",
+            "kms",
+            "gcp_kms",
+            "azure_keyvault",
+            "hc_vault_transit_uri",
+        ];
+        let has_keys = !self.recipients().is_empty()
+            || other_keys.iter().any(|k| self.other.contains_key(*k))
+            || self.key_groups.iter().any(|g| !g.other.is_empty());
+        if !has_keys {
+            errors.push("La regla no tiene recipients".to_string());
+        }
+        errors
+    }
+}
+
+/// Recipient age X25519, SSH o de plugin (`age1yubikey1...`)
+pub fn is_valid_recipient(recipient: &str) -> bool {
+    age::x25519::Recipient::from_str(recipient).is_ok()
+        || age::ssh::Recipient::from_str(recipient).is_ok()
+        || (recipient.starts_with("age1") && plugin_name(recipient).is_some())
+}
+
+impl SopsConfig {
+    pub fn parse(content: &str) -> Result<Self> {
+        if content.trim().is_empty() {
+            return Ok(Self::default());
+        }
+        serde_yaml::from_str(content).context("YAML inválido")
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("No se pudo leer {}", path.display()))?;
+        Self::parse(&content).with_context(|| format!("{} inválido", path.display()))
+    }
+
+    pub fn to_yaml(&self) -> Result<String> {
+        Ok(serde_yaml::to_string(self)?)
+    }
+
+    /// Valida y reescribe el archivo entero: los comentarios se pierden y las claves que no
+    /// se editan quedan ordenadas. No se guarda una configuración con errores.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        self.check()?;
+        let yaml = self.to_yaml()?;
+        // Comprobar que el resultado se vuelve a leer igual
+        if Self::parse(&yaml)? != *self {
+            anyhow::bail!("El YAML generado no es equivalente a la configuración");
+        }
+        write_atomic(path, yaml.as_bytes())
+    }
+
+    /// Guarda cambiando en el texto de `path` solo la regla `idx` (insertada delante de la
+    /// que ocupaba ese lugar, o en su lugar con `replace`), para conservar comentarios y el
+    /// orden del resto. Si el texto no admite ese cambio se reescribe entero, salvo que
+    /// tenga comentarios que se perderían.
+    pub fn save_rule(&self, path: &Path, idx: usize, replace: bool) -> Result<()> {
+        self.check()?;
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("No se pudo leer {}", path.display()))?;
+        let rule = &self.creation_rules[idx];
+        match splice_rule(&content, idx, rule, replace) {
+            Some(yaml) if Self::parse(&yaml).ok().as_ref() == Some(self) => {
+                write_atomic(path, yaml.as_bytes())
+            }
+            _ if has_comments(&content) => anyhow::bail!(
+                "No se puede añadir la regla a {} sin perder sus comentarios",
+                path.display()
+            ),
+            _ => self.save(path),
+        }
+    }
+
+    fn check(&self) -> Result<()> {
+        if let Some((idx, error)) = self.validate().first() {
+            anyhow::bail!("Regla {}: {}", idx + 1, error);
+        }
+        Ok(())
+    }
+
+    /// Errores de todas las reglas, con el índice de cada una
+    pub fn validate(&self) -> Vec<(usize, String)> {
+        self.creation_rules
+            .iter()
+            .enumerate()
+            .flat_map(|(i, rule)| rule.validate().into_iter().map(move |e| (i, e)))
+            .collect()
+    }
+
+    /// Índice de la regla que SOPS usaría para el archivo
+    pub fn rule_for(&self, relative_path: &str) -> Option<usize> {
+        self.creation_rules
+            .iter()
+            .position(|r| r.matches(relative_path))
+    }
+
+    /// Añade una regla para `relative` cifrada a `recipients`, delante de la que lo resolvía
+    /// para que no quede tapada. Devuelve el índice de la regla y si la configuración cambió.
+    pub fn add_file_rule(&mut self, relative: &str, recipients: &[String]) -> (usize, bool) {
+        let rule = CreationRule {
+            path_regex: Some(file_regex(relative)),
+            age: recipients.to_vec(),
+            ..Default::default()
+        };
+        let Some(idx) = self.rule_for(relative) else {
+            self.creation_rules.push(rule);
+            return (self.creation_rules.len() - 1, true);
+        };
+
+        let current = &mut self.creation_rules[idx];
+        let mut wanted = recipients.to_vec();
+        let mut existing = current.recipients();
+        wanted.sort();
+        existing.sort();
+        if wanted == existing {
+            (idx, false)
+        } else if current.path_regex == rule.path_regex && current.key_groups.is_empty() {
+            // Ya hay una regla solo para este archivo: se cambian sus recipients
+            current.age = rule.age;
+            (idx, true)
+        } else {
+            self.creation_rules.insert(idx, rule);
+            (idx, true)
+        }
+    }
+
+    /// Reglas que nunca se aplican, con la regla anterior que las tapa: una anterior sin
+    /// `path_regex` o con la misma, o que ya resuelve todos los archivos del árbol que coinciden
+    pub fn shadowed_rules(&self, dir: &Path) -> Vec<(usize, usize)> {
+        let files: Vec<String> = tree_files(dir)
+            .iter()
+            .filter_map(|path| relative_path(dir, path))
+            .filter(|relative| relative != SOPS_CONFIG_FILE)
+            .collect();
+
+        let mut shadowed = Vec::new();
+        for (i, rule) in self.creation_rules.iter().enumerate() {
+            let earlier = &self.creation_rules[..i];
+            if let Some(j) = earlier
+                .iter()
+                .position(|e| e.path_regex.is_none() || e.path_regex == rule.path_regex)
+            {
+                shadowed.push((i, j));
+                continue;
+            }
+            // Una regla sin path_regex es la de por defecto para archivos nuevos
+            if rule.path_regex.is_none() {
+                continue;
+            }
+
+            let matching: Vec<&String> = files.iter().filter(|f| rule.matches(f)).collect();
+            let owners: Option<Vec<usize>> = matching
+                .iter()
+                .map(|f| earlier.iter().position(|e| e.matches(f)))
+                .collect();
+            if let Some(&j) = owners.as_ref().and_then(|o| o.first()) {
+                shadowed.push((i, j));
+            }
+        }
+        shadowed
+    }
+
+    /// Archivos del árbol de `dir` que resuelve cada regla (la primera que coincide)
+    pub fn preview(&self, dir: &Path) -> Vec<Vec<PathBuf>> {
+        let mut matched = vec![Vec::new(); self.creation_rules.len()];
+        for path in tree_files(dir) {
+            let Some(relative) = relative_path(dir, &path) else {
+                continue;
+            };
+            if relative == SOPS_CONFIG_FILE {
+                continue;
+            }
+            if let Some(idx) = self.rule_for(&relative) {
+                matched[idx].push(path);
+            }
+        }
+        matched
+    }
+}
+
+/// Regla que resuelve un archivo en el `.sops.yaml` que SOPS encontraría para él
+#[derive(Debug, Clone)]
+pub struct Resolution {
+    pub config_path: PathBuf,
+    pub config: SopsConfig,
+    /// Ruta del archivo relativa al directorio del `.sops.yaml`
+    pub relative: String,
+    pub rule: Option<usize>,
+}
+
+impl Resolution {
+    pub fn resolve(file: &Path) -> Result<Option<Self>> {
+        let Some(config_path) = file.parent().and_then(find_config) else {
+            return Ok(None);
+        };
+        let config = SopsConfig::load(&config_path)?;
+        Ok(Some(Self::new(config_path, config, file)))
+    }
+
+    /// Regla de `config` (leído de `config_path`) que resuelve `file`
+    pub fn new(config_path: PathBuf, config: SopsConfig, file: &Path) -> Self {
+        let relative = config_path
+            .parent()
+            .and_then(|dir| relative_path(dir, file))
+            .unwrap_or_default();
+        let rule = config.rule_for(&relative);
+        Self {
+            config_path,
+            config,
+            relative,
+            rule,
+        }
+    }
+
+    pub fn rule(&self) -> Option<&CreationRule> {
+        self.rule.map(|idx| &self.config.creation_rules[idx])
+    }
+
+    /// Diferencia entre los recipients age del archivo y los de su regla, si la hay.
+    /// Las reglas sin recipients age (solo pgp, kms...) no se comparan.
+    pub fn drift(&self, file_recipients: &[String]) -> Option<Drift> {
+        let expected = self.rule()?.recipients();
+        if expected.is_empty() {
+            return None;
+        }
+        let missing_from = |from: &[String], to: &[String]| -> Vec<String> {
+            from.iter()
+                .filter(|r| !to.iter().any(|t| recipient_matches(r, t)))
+                .cloned()
+                .collect()
+        };
+        let drift = Drift {
+            missing: missing_from(&expected, file_recipients),
+            extra: missing_from(file_recipients, &expected),
+        };
+        (!drift.is_empty()).then_some(drift)
+    }
+}
+
+/// Recipients que la regla pide y el archivo no tiene, y los que tiene de más
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Drift {
+    pub missing: Vec<String>,
+    pub extra: Vec<String>,
+}
+
+impl Drift {
+    pub fn is_empty(&self) -> bool {
+        self.missing.is_empty() && self.extra.is_empty()
+    }
+}
+
+/// `.sops.yaml` más cercano subiendo desde `dir`, como lo busca SOPS
+pub fn find_config(dir: &Path) -> Option<PathBuf> {
+    dir.ancestors()
+        .map(|d| d.join(SOPS_CONFIG_FILE))
+        .find(|p| p.is_file())
+}
+
+/// Si el YAML tiene comentarios, que `save` no conserva
+pub fn has_comments(content: &str) -> bool {
+    content
+        .lines()
+        .any(|line| line.trim_start().starts_with('#') || line.contains(" #"))
+}
+
+/// Línea de cada regla de `creation_rules` en el texto, su sangría y la línea donde
+/// termina la lista. `None` si la lista no está en formato de bloque.
+fn rule_lines(lines: &[&str]) -> Option<(Vec<usize>, usize, usize)> {
+    let header = lines.iter().position(|line| {
+        line.strip_prefix("creation_rules:")
+            .map(str::trim)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with('#'))
+    })?;
+
+    let mut items = Vec::new();
+    let mut indent = None;
+    let mut end = header + 1;
+    for (i, line) in lines.iter().enumerate().skip(header + 1) {
+        let trimmed = line.trim_start();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        let level = line.len() - trimmed.len();
+        let is_item = trimmed == "-" || trimmed.starts_with("- ");
+        if level == 0 && !is_item {
+            break;
+        }
+        if is_item && *indent.get_or_insert(level) == level {
+            items.push(i);
+        }
+        end = i + 1;
+    }
+    Some((items, indent.unwrap_or(2), end))
+}
+
+/// Inserta o reemplaza la regla `idx` en el texto del `.sops.yaml`. Los comentarios justo
+/// encima de una regla se quedan con ella.
+fn splice_rule(content: &str, idx: usize, rule: &CreationRule, replace: bool) -> Option<String> {
+    let lines: Vec<&str> = content.lines().collect();
+    let yaml = serde_yaml::to_string(&[rule]).ok()?;
+
+    let Some((items, indent, end)) = rule_lines(&lines) else {
+        // Sin `creation_rules`: la lista se añade al final
+        let existing = content.trim_end();
+        let separator = if existing.is_empty() { "" } else { "\n" };
+        return Some(format!(
+            "{}{}creation_rules:\n{}",
+            existing, separator, yaml
+        ));
+    };
+
+    let with_comments = |item: usize| {
+        let mut start = item;
+        while start > 0 && lines[start - 1].trim_start().starts_with('#') {
+            start -= 1;
+        }
+        start
+    };
+    let (from, to) = if replace {
+        let next = items.get(idx + 1).map_or(end, |&i| with_comments(i));
+        (*items.get(idx)?, next)
+    } else {
+        let at = items.get(idx).map_or(end, |&i| with_comments(i));
+        (at, at)
+    };
+
+    let pad = " ".repeat(indent);
+    let mut out: Vec<String> = lines[..from].iter().map(|l| l.to_string()).collect();
+    out.extend(yaml.lines().map(|line| format!("{}{}", pad, line)));
+    out.extend(lines[to..].iter().map(|l| l.to_string()));
+    Some(out.join("\n") + "\n")
+}
+
+/// Regex que solo coincide con `relative` (en cualquier subdirectorio si es un nombre suelto)
+pub fn file_regex(relative: &str) -> String {
+    format!("(^|/){}$", regex::escape(relative))
+}
+
+/// Ruta con `/` relativa al directorio del `.sops.yaml`, como la compara SOPS
+pub fn relative_path(dir: &Path, path: &Path) -> Option<String> {
+    let relative = path.strip_prefix(dir).ok()?;
+    Some(
+        relative
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/"),
+    )
+}
+
+/// Claves que el template de valores sensibles cifra
+pub const SENSITIVE_KEYS_REGEX: &str = "^(password|passwd|pass|secret|key|token|api[_-]?key|private[_-]?key|access[_-]?key|auth|credential|database[_-]?url|.*[Ss]ecret.*|.*[Kk]ey.*|.*[Tt]oken.*)$";
+
+/// Plantilla para crear un `.sops.yaml`
+#[derive(Debug, Clone, PartialEq)]
+pub enum SopsTemplate {
+    /// Una regla por tipo de archivo
+    ByFormat,
+    /// Una regla por entorno (dev/staging/prod)
+    ByEnvironment,
+    /// Solo las claves sensibles del archivo, según `SENSITIVE_KEYS_REGEX`
+    SensitiveValues,
+    /// Manifiestos de Kubernetes: solo `data` y `stringData`
+    Kubernetes,
+    /// Plantilla del usuario en `~/.config/agesmith/sops-templates/`
+    Custom {
+        name: String,
+        description: String,
+        content: String,
+    },
+}
+
+impl SopsTemplate {
+    pub fn builtin() -> Vec<Self> {
+        vec![
+            Self::ByFormat,
+            Self::ByEnvironment,
+            Self::SensitiveValues,
+            Self::Kubernetes,
+        ]
+    }
+
+    /// Plantillas incorporadas seguidas de las del usuario
+    pub fn all() -> Result<Vec<Self>> {
+        let mut templates = Self::builtin();
+        if let Some(dir) = templates_dir() {
+            templates.extend(load_custom_templates(&dir)?);
+        }
+        Ok(templates)
+    }
+
+    /// Configuración para `file` (relativo al `.sops.yaml`) cifrado a `recipients`.
+    /// Si ninguna regla de la plantilla cubre el archivo, se añade una para él al final.
+    pub fn config(&self, file: &str, recipients: &[String]) -> Result<SopsConfig> {
+        let file_regex = file_regex(file);
+        let rule = |path_regex: &str, encrypted_regex: Option<&str>| CreationRule {
+            path_regex: Some(path_regex.to_string()),
+            encrypted_regex: encrypted_regex.map(str::to_string),
+            age: recipients.to_vec(),
+            ..Default::default()
+        };
+
+        let mut config = match self {
+            Self::ByFormat => SopsConfig {
+                creation_rules: [r"\.env$", r"\.json$", r"\.ya?ml$", r"\.ini$"]
+                    .into_iter()
+                    .map(|r| rule(r, None))
+                    .collect(),
+                ..Default::default()
+            },
+            Self::ByEnvironment => SopsConfig {
+                creation_rules: [
+                    "(^|/)(dev|development)/",
+                    "(^|/)staging/",
+                    "(^|/)(prod|production)/",
+                ]
+                .into_iter()
+                .map(|r| rule(r, None))
+                .collect(),
+                ..Default::default()
+            },
+            Self::SensitiveValues => SopsConfig {
+                creation_rules: vec![rule(&file_regex, Some(SENSITIVE_KEYS_REGEX))],
+                ..Default::default()
+            },
+            Self::Kubernetes => SopsConfig {
+                creation_rules: vec![rule(r"\.ya?ml$", Some("^(data|stringData)$"))],
+                ..Default::default()
+            },
+            Self::Custom { name, content, .. } => {
+                let content = content
+                    .replace("{{recipients}}", &recipients.join(","))
+                    .replace("{{path_regex}}", &file_regex);
+                SopsConfig::parse(&content).with_context(|| format!("Plantilla {}", name))?
+            }
+        };
+
+        if config.rule_for(file).is_none() {
+            config.creation_rules.push(rule(&file_regex, None));
+        }
+        Ok(config)
+    }
+}
+
+/// `~/.config/agesmith/sops-templates/`, si existe
+pub fn templates_dir() -> Option<PathBuf> {
+    let dir = dirs::home_dir()?.join(".config/agesmith/sops-templates");
+    dir.is_dir().then_some(dir)
+}
+
+/// Plantillas `*.yaml` de `dir`: el nombre es el del archivo y la descripción su primer comentario
+pub fn load_custom_templates(dir: &Path) -> Result<Vec<SopsTemplate>> {
+    let mut paths: Vec<PathBuf> = fs::read_dir(dir)
+        .with_context(|| format!("No se pudo leer {}", dir.display()))?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|p| matches!(p.extension().and_then(|e| e.to_str()), Some("yaml" | "yml")))
+        .collect();
+    paths.sort();
+
+    paths
+        .into_iter()
+        .map(|path| {
+            let content = fs::read_to_string(&path)
+                .with_context(|| format!("No se pudo leer {}", path.display()))?;
+            let description = content
+                .lines()
+                .next()
+                .and_then(|l| l.strip_prefix('#'))
+                .map(|l| l.trim().to_string())
+                .unwrap_or_default();
+            Ok(SopsTemplate::Custom {
+                name: path
+                    .file_stem()
+                    .map(|s| s.to_string_lossy().into_owned())
+                    .unwrap_or_default(),
+                description,
+                content,
+            })
+        })
+        .collect()
+}
@@ -1,17 +1,52 @@
+use crate::addressbook::AddressBook;
+use crate::audit::{append_event, load_events, AuditAction, AuditEvent};
 use crate::config::{Config, Theme};
-use crate::generator::TokenFormat;
+use crate::generator::{
+    builtin_policies, public_key_name, GeneratorPolicy, KeyKind, KeyMaterial, PassphrasePolicy,
+    PasswordPolicy, TokenFormat,
+};
+use crate::git::{file_history, HistoryEvent};
 use crate::i18n::I18n;
-use crate::sops::{decrypt_and_parse, get_encrypted_keys, get_sops_recipients, AgeKey};
+use crate::keystore::{
+    append_identities, backup_path, bundle_identity_files, encrypt_to_recipients,
+    encrypt_with_passphrase, files_depending_on, identity_block, is_metadata_comment,
+    new_identities, parse_identities, remove_identity, save_backup_path, set_comment,
+};
+use crate::lock::{hash_passphrase, verify_passphrase, Challenge};
+use crate::metadata::{
+    load_metadata, save_metadata, sidecar_path, ExpiryStatus, FileMetadata, ROTATION_DAYS_RANGE,
+};
+use crate::perms::{restrict_permissions, write_atomic, write_private, write_private_atomic};
+use crate::secmem::{lock_memory, set_core_dumps, SecretList, SecretString};
+use crate::sops::{
+    age_keys_path, decrypt_and_parse, decrypt_identity_file, detect_key_index,
+    encrypted_identity_files, expand_home, get_encrypted_keys, get_sops_recipients,
+    identity_file_paths, insecure_identity_files, is_encrypted_identity_file, load_age_keys,
+    plugin_available, set_sops_identity, AgeKey, KeySource,
+};
+use crate::sopsconfig::{
+    has_comments, CreationRule, Drift, Resolution, SopsConfig, SopsTemplate, RULE_FIELDS,
+    SOPS_CONFIG_FILE,
+};
+use crate::usage::UsageReport;
 use anyhow::{Context, Result};
 use arboard::Clipboard;
+use chrono::{DateTime, Utc};
 use regex::Regex;
 use serde_json::Value;
 use std::{
+    collections::HashMap,
     fs,
     path::{Path, PathBuf},
     process::Command,
+    sync::mpsc::{self, Receiver},
+    thread,
     time::{Duration, Instant},
 };
+use zeroize::Zeroize;
+
+/// Extensiones de los formatos que se pueden crear, en el orden del selector
+pub const FILE_FORMATS: [&str; 4] = ["env", "json", "yaml", "ini"];
 
 #[derive(Debug, PartialEq)]
 pub enum InputMode {
@@ -39,10 +74,68 @@ pub enum InputMode {
     SelectingSopsKeys,
     EditingSopsConfig,
     SelectingSopsTemplate,
+    ViewingHistory,
+    ViewingAudit,
+    EditingMetadata,
+    Locked,
+    SettingLockPassphrase,
+    UnlockingIdentities,
+    ImportingKeys,
+    ExportingKey,
+    EditingKeyComment,
+    BackingUpKeys,
+    EnteringKeyPassphrase,
+    FixingPermissions,
+    ViewingKeyUsage,
+}
+
+/// Operación del gestor de llaves que espera una frase
+pub enum KeyTransfer {
+    /// Archivo de identidades cifrado que se va a importar
+    Import(Vec<u8>),
+    /// Destino de la exportación; siempre se cifra con frase
+    Export(PathBuf),
+    /// Borrado de la última llave: el respaldo se cifra con frase
+    Delete,
+}
+
+/// Opción del selector de recipients al crear `.sops.yaml`: una llave propia, una
+/// persona o un grupo de la libreta
+pub struct RecipientChoice {
+    pub label: String,
+    pub keys: Vec<String>,
+}
+
+/// Valor anterior de un secreto rotado, disponible para deshacer hasta guardar
+pub struct PendingRotation {
+    pub previous_value: SecretString,
+    pub previous_rotated: Option<DateTime<Utc>>,
+}
+
+/// Contenido cifrado previo a una rotación en lote, para poder restaurarlo
+pub struct RotationBackup {
+    pub file: PathBuf,
+    pub content: Vec<u8>,
+    pub sidecar: Option<Vec<u8>>,
+}
+
+/// Entrada `<KEY>_PUBLIC` donde va la parte pública de una llave rotada
+enum PublicSibling {
+    Existing(usize),
+    New(String),
+}
+
+/// La entrada hermana existente o, si `create`, el nombre de la que hay que añadir
+fn public_sibling(secrets: &SecretList, key: &str, create: bool) -> Option<PublicSibling> {
+    let public_name = public_key_name(key);
+    match secrets.iter().position(|(k, _)| *k == public_name) {
+        Some(idx) => Some(PublicSibling::Existing(idx)),
+        None => create.then_some(PublicSibling::New(public_name)),
+    }
 }
 
 pub struct App {
-    pub secrets: Vec<(String, String)>,
+    pub secrets: SecretList,
     pub encrypted_keys: Vec<String>,
     pub table_state: ratatui::widgets::TableState,
     pub age_keys: Vec<AgeKey>,
@@ -58,12 +151,14 @@ pub struct App {
     pub message_timestamp: Option<Instant>,
     pub clipboard_timestamp: Option<Instant>,
     pub file_recipients: Vec<String>,
+    /// `.sops.yaml` y regla que SOPS aplica al archivo abierto
+    pub file_rule: Option<Resolution>,
     pub key_search_query: String,
     pub secret_search_query: String,
     pub clipboard: Option<Clipboard>,
     pub is_modified: bool,
     pub editing_key_buffer: String,
-    pub editing_value_buffer: String,
+    pub editing_value_buffer: SecretString,
     pub cursor_position: usize,
     pub editing_field: usize,
     pub gen_selected_option: usize,
@@ -71,8 +166,18 @@ pub struct App {
     pub gen_use_special: bool,
     pub gen_use_numbers: bool,
     pub gen_token_format: TokenFormat,
+    pub gen_rotating: bool,
+    pub gen_policy_index: usize,
+    pub gen_passphrase: PassphrasePolicy,
+    pub gen_key_kind: KeyKind,
+    /// Guardar la parte pública de los pares de llaves en `<KEY>_PUBLIC`
+    pub gen_public_sibling: bool,
+    /// Parte pública generada que se guardará al confirmar el modal de edición/alta
+    pub pending_public_key: Option<String>,
+    pub pending_rotations: HashMap<String, PendingRotation>,
+    pub batch_rotation_backup: Vec<RotationBackup>,
     pub use_regex: bool,
-    pub viewing_value: Option<String>,
+    pub viewing_value: Option<SecretString>,
     pub viewing_scroll: u16,
     pub config: Config,
     pub favorites: Vec<PathBuf>,
@@ -87,13 +192,57 @@ pub struct App {
     pub loading_message: String,
     pub key_manager_selected: usize,
     pub new_key_comment: String,
+    /// Entrada de importar, exportar, comentar y respaldar llaves
+    pub key_io_buffer: String,
+    pub key_transfer: Option<KeyTransfer>,
+    /// Archivos que solo la llave a eliminar puede descifrar
+    pub key_deletion_dependents: Vec<PathBuf>,
+    pub usage_report: Option<UsageReport>,
+    pub usage_scroll: u16,
     pub folder_name_buffer: String,
     pub rename_buffer: String,
     pub new_file_name_buffer: String,
     pub edit_buffer: String,
+    /// `.sops.yaml` del directorio actual en el editor estructurado
+    pub sops_config: SopsConfig,
+    pub sops_rule_selected: usize,
+    /// Campo de `RULE_FIELDS` seleccionado y si se está editando en `edit_buffer`
+    pub sops_field_selected: usize,
+    pub sops_field_editing: bool,
+    /// Ya se avisó de que guardar desde el editor pierde los comentarios
+    pub sops_comments_confirmed: bool,
+    /// Archivos del árbol que resuelve cada regla
+    pub sops_preview: Vec<Vec<PathBuf>>,
+    /// Reglas que nunca se aplican y la regla anterior que las tapa
+    pub sops_shadowed: Vec<(usize, usize)>,
+    /// `.sops.yaml` existente y la regla que resuelve el archivo nuevo
+    pub new_file_resolution: Option<Resolution>,
+    pub sops_templates: Vec<SopsTemplate>,
     pub selected_sops_template: usize,
     pub selected_format: usize,
     pub selected_sops_keys: Vec<bool>,
+    pub address_book: AddressBook,
+    pub history_events: Vec<HistoryEvent>,
+    pub history_state: ratatui::widgets::TableState,
+    pub history_filter: Option<String>,
+    /// Historial que se está calculando en segundo plano
+    pub history_loading: Option<Receiver<Result<Vec<HistoryEvent>>>>,
+    pub audit_events: Vec<AuditEvent>,
+    pub audit_state: ratatui::widgets::TableState,
+    pub audit_file_filter: Option<PathBuf>,
+    pub audit_action_filter: Option<AuditAction>,
+    pub metadata: FileMetadata,
+    pub meta_buffers: [String; 3],
+    /// Entrada de la pantalla de bloqueo (frase o identidad age) y del cambio de frase
+    pub lock_buffer: SecretString,
+    /// Frase nueva ya escrita una vez, a la espera de repetirla
+    pub lock_passphrase_first: Option<SecretString>,
+    pub lock_challenge: Option<Challenge>,
+    /// Archivos de identidades protegidos con frase; sus llaves solo viven en memoria
+    pub encrypted_identity_files: Vec<PathBuf>,
+    pub unlocked_identity_files: Vec<PathBuf>,
+    /// Archivos de identidades legibles por el grupo u otros, con sus permisos
+    pub insecure_identity_files: Vec<(PathBuf, u32)>,
 }
 
 impl App {
@@ -107,7 +256,7 @@ impl App {
         let i18n = I18n::new(config.get_language());
         let theme = config.get_theme();
         let mut app = Self {
-            secrets: Vec::new(),
+            secrets: SecretList::default(),
             encrypted_keys: Vec::new(),
             table_state: ratatui::widgets::TableState::default(),
             age_keys,
@@ -123,12 +272,13 @@ impl App {
             message_timestamp: None,
             clipboard_timestamp: None,
             file_recipients: Vec::new(),
+            file_rule: None,
             key_search_query: String::new(),
             secret_search_query: String::new(),
             clipboard: Clipboard::new().ok(),
             is_modified: false,
             editing_key_buffer: String::new(),
-            editing_value_buffer: String::new(),
+            editing_value_buffer: SecretString::default(),
             cursor_position: 0,
             editing_field: 0,
             gen_length: 16,
@@ -136,6 +286,14 @@ impl App {
             gen_use_numbers: true,
             gen_token_format: TokenFormat::Hex,
             gen_selected_option: 0,
+            gen_rotating: false,
+            gen_policy_index: 0,
+            gen_passphrase: PassphrasePolicy::default(),
+            gen_key_kind: KeyKind::SshEd25519,
+            gen_public_sibling: true,
+            pending_public_key: None,
+            pending_rotations: HashMap::new(),
+            batch_rotation_backup: Vec::new(),
             use_regex: false,
             viewing_value: None,
             viewing_scroll: 0,
@@ -150,17 +308,53 @@ impl App {
             loading_message: String::new(),
             key_manager_selected: 0,
             new_key_comment: String::new(),
+            key_io_buffer: String::new(),
+            key_transfer: None,
+            key_deletion_dependents: Vec::new(),
+            usage_report: None,
+            usage_scroll: 0,
             folder_name_buffer: String::new(),
             rename_buffer: String::new(),
             new_file_name_buffer: String::new(),
             edit_buffer: String::new(),
+            sops_config: SopsConfig::default(),
+            sops_rule_selected: 0,
+            sops_field_selected: 0,
+            sops_field_editing: false,
+            sops_comments_confirmed: false,
+            sops_preview: Vec::new(),
+            sops_shadowed: Vec::new(),
+            new_file_resolution: None,
+            sops_templates: SopsTemplate::builtin(),
             selected_sops_template: 0,
             selected_format: 0,
             selected_sops_keys: Vec::new(),
+            address_book: AddressBook::default(),
+            history_events: Vec::new(),
+            history_state: ratatui::widgets::TableState::default(),
+            history_filter: None,
+            history_loading: None,
+            audit_events: Vec::new(),
+            audit_state: ratatui::widgets::TableState::default(),
+            audit_file_filter: None,
+            audit_action_filter: None,
+            metadata: FileMetadata::default(),
+            meta_buffers: Default::default(),
+            lock_buffer: SecretString::default(),
+            lock_passphrase_first: None,
+            lock_challenge: None,
+            encrypted_identity_files: Vec::new(),
+            unlocked_identity_files: Vec::new(),
+            insecure_identity_files: Vec::new(),
         };
+        app.encrypted_identity_files = encrypted_identity_files(&app.config.identity_files);
+        app.insecure_identity_files =
+            insecure_identity_files(&app.config.identity_files).unwrap_or_default();
+        app.reload_address_book();
         if !app.files.is_empty() {
             app.file_list_state.select(Some(0));
         }
+        app.input_mode = app.unlocked_mode();
         Ok(app)
     }
 
@@ -209,11 +403,14 @@ impl App {
         if let Some(idx) = self.table_state.selected() {
             let filtered = self.filtered_secrets();
             if let Some(&real_idx) = filtered.get(idx) {
-                if let Some((_, value)) = self.secrets.get(real_idx) {
+                if let Some((key, value)) = self.secrets.get(real_idx) {
+                    let key = key.clone();
                     if let Some(clipboard) = &mut self.clipboard {
-                        if clipboard.set_text(value.clone()).is_ok() {
+                        // Prestado: una copia en el heap no se borraría
+                        if clipboard.set_text(value.as_str()).is_ok() {
                             self.set_temp_message(self.i18n.t("copy_value").to_string());
                             self.clipboard_timestamp = Some(Instant::now());
+                            self.audit(AuditAction::ValueCopied, Some(&key));
                         }
                     }
                 }
@@ -237,39 +434,499 @@ impl App {
         }
     }
 
+    /// Registra un evento en el log de auditoría para el archivo abierto
+    pub fn audit(&self, action: AuditAction, key: Option<&str>) {
+        if let Some(path) = &self.file_path {
+            self.audit_file(action, path, key);
+        }
+    }
+
+    pub fn audit_file(&self, action: AuditAction, path: &Path, key: Option<&str>) {
+        if self.config.audit_log {
+            // El log es best-effort: nunca debe impedir trabajar con los secretos
+            let _ = append_event(&AuditEvent::new(action, path, key));
+        }
+    }
+
+    pub fn toggle_show_values(&mut self) {
+        self.show_values = !self.show_values;
+        if self.show_values {
+            self.audit(AuditAction::ValueRevealed, None);
+        }
+    }
+
+    pub fn open_audit_log(&mut self) {
+        match load_events() {
+            Ok(mut events) => {
+                events.reverse();
+                self.audit_events = events;
+                self.audit_file_filter = None;
+                self.audit_action_filter = None;
+                let first = (!self.audit_events.is_empty()).then_some(0);
+                self.audit_state.select(first);
+                self.input_mode = InputMode::ViewingAudit;
+            }
+            Err(e) => self.set_temp_message(format!("❌ Error: {}", e)),
+        }
+    }
+
+    pub fn filtered_audit_events(&self) -> Vec<&AuditEvent> {
+        self.audit_events
+            .iter()
+            .filter(|e| {
+                self.audit_file_filter
+                    .as_ref()
+                    .map(|f| &e.file == f)
+                    .unwrap_or(true)
+            })
+            .filter(|e| {
+                self.audit_action_filter
+                    .map(|a| e.action == a)
+                    .unwrap_or(true)
+            })
+            .collect()
+    }
+
+    /// Alterna el filtro por archivo: el abierto o, en el explorador, el seleccionado
+    pub fn toggle_audit_file_filter(&mut self) {
+        self.audit_file_filter = if self.audit_file_filter.is_some() {
+            None
+        } else {
+            self.file_path
+                .clone()
+                .or_else(|| {
+                    self.file_list_state
+                        .selected()
+                        .and_then(|i| self.files.get(i))
+                        .filter(|p| p.is_file())
+                        .cloned()
+                })
+                .map(|p| fs::canonicalize(&p).unwrap_or(p))
+        };
+        let first = (!self.filtered_audit_events().is_empty()).then_some(0);
+        self.audit_state.select(first);
+    }
+
+    /// Recorre los filtros de acción: todas → cada acción → todas
+    pub fn cycle_audit_action_filter(&mut self) {
+        let all = AuditAction::ALL;
+        self.audit_action_filter = match self.audit_action_filter {
+            None => Some(all[0]),
+            Some(current) => all
+                .iter()
+                .position(|a| *a == current)
+                .and_then(|i| all.get(i + 1))
+                .copied(),
+        };
+        let first = (!self.filtered_audit_events().is_empty()).then_some(0);
+        self.audit_state.select(first);
+    }
+
     pub fn set_temp_message(&mut self, msg: String) {
         self.error_message = Some(msg);
         self.message_timestamp = Some(Instant::now());
     }
 
+    /// Políticas incluidas más las de `config.toml` (que reemplazan a las del mismo nombre)
+    pub fn password_policies(&self) -> Vec<(String, PasswordPolicy)> {
+        let mut policies: Vec<(String, PasswordPolicy)> = builtin_policies()
+            .into_iter()
+            .filter(|(name, _)| !self.config.policies.contains_key(name))
+            .collect();
+        policies.extend(
+            self.config
+                .policies
+                .iter()
+                .map(|(name, policy)| (name.clone(), policy.clone())),
+        );
+        policies
+    }
+
+    pub fn current_gen_policy(&self) -> GeneratorPolicy {
+        match self.gen_selected_option {
+            0 => GeneratorPolicy::Password {
+                length: self.gen_length,
+                use_special: self.gen_use_special,
+                use_numbers: self.gen_use_numbers,
+            },
+            1 => GeneratorPolicy::Token {
+                format: self.gen_token_format,
+                length: self.gen_length,
+            },
+            2 => {
+                let (name, policy) = self
+                    .password_policies()
+                    .into_iter()
+                    .nth(self.gen_policy_index)
+                    .unwrap_or_else(|| ("strong".to_string(), PasswordPolicy::default()));
+                GeneratorPolicy::Named {
+                    name,
+                    policy: PasswordPolicy {
+                        length: self.gen_length,
+                        ..policy
+                    },
+                }
+            }
+            3 => GeneratorPolicy::Passphrase(self.gen_passphrase.clone()),
+            _ => GeneratorPolicy::Key {
+                algorithm: self.gen_key_kind,
+            },
+        }
+    }
+
+    pub fn cycle_gen_key_kind(&mut self, forward: bool) {
+        let len = KeyKind::ALL.len();
+        let idx = KeyKind::ALL
+            .iter()
+            .position(|k| *k == self.gen_key_kind)
+            .unwrap_or(0);
+        let next = if forward {
+            (idx + 1) % len
+        } else {
+            (idx + len - 1) % len
+        };
+        self.gen_key_kind = KeyKind::ALL[next];
+    }
+
+    /// Alterna el separador de la frase de contraseña entre los más comunes
+    pub fn cycle_gen_separator(&mut self) {
+        const SEPARATORS: [&str; 5] = ["-", " ", ".", "_", ""];
+        let idx = SEPARATORS
+            .iter()
+            .position(|s| *s == self.gen_passphrase.separator)
+            .map_or(0, |i| (i + 1) % SEPARATORS.len());
+        self.gen_passphrase.separator = SEPARATORS[idx].to_string();
+    }
+
+    /// Selecciona la política con nombre siguiente/anterior y adopta su longitud
+    pub fn cycle_gen_policy(&mut self, forward: bool) {
+        let policies = self.password_policies();
+        if policies.is_empty() {
+            return;
+        }
+        self.gen_policy_index = if forward {
+            (self.gen_policy_index + 1) % policies.len()
+        } else {
+            (self.gen_policy_index + policies.len() - 1) % policies.len()
+        };
+        self.gen_length = policies[self.gen_policy_index].1.length;
+    }
+
+    /// Actualiza una política con nombre con la definición actual de la configuración,
+    /// conservando la longitud elegida al generarla
+    fn refresh_policy(&self, policy: GeneratorPolicy) -> GeneratorPolicy {
+        match policy {
+            GeneratorPolicy::Named { name, policy } => {
+                let current = self
+                    .password_policies()
+                    .into_iter()
+                    .find(|(n, _)| *n == name)
+                    .map(|(_, p)| PasswordPolicy {
+                        length: policy.length,
+                        ..p
+                    })
+                    .unwrap_or(policy);
+                GeneratorPolicy::Named {
+                    name,
+                    policy: current,
+                }
+            }
+            other => other,
+        }
+    }
+
+    fn apply_gen_policy(&mut self, policy: &GeneratorPolicy) {
+        match policy {
+            GeneratorPolicy::Password {
+                length,
+                use_special,
+                use_numbers,
+            } => {
+                self.gen_selected_option = 0;
+                self.gen_length = *length;
+                self.gen_use_special = *use_special;
+                self.gen_use_numbers = *use_numbers;
+            }
+            GeneratorPolicy::Token { format, length } => {
+                self.gen_selected_option = 1;
+                self.gen_token_format = *format;
+                self.gen_length = *length;
+            }
+            GeneratorPolicy::Named { name, policy } => {
+                self.gen_selected_option = 2;
+                if let Some(idx) = self.password_policies().iter().position(|(n, _)| n == name) {
+                    self.gen_policy_index = idx;
+                }
+                self.gen_length = policy.length;
+            }
+            GeneratorPolicy::Passphrase(policy) => {
+                self.gen_selected_option = 3;
+                self.gen_passphrase = policy.clone();
+            }
+            GeneratorPolicy::Key { algorithm } => {
+                self.gen_selected_option = 4;
+                self.gen_key_kind = *algorithm;
+            }
+        }
+    }
+
+    /// Abre el generador con la política recordada del secreto seleccionado.
+    /// Con `rotating`, el valor generado reemplaza directamente al secreto.
+    pub fn open_generator(&mut self, rotating: bool) {
+        let policy = self
+            .selected_secret_key()
+            .and_then(|k| self.metadata.get(&k).and_then(|m| m.policy.clone()));
+        if let Some(policy) = policy {
+            self.apply_gen_policy(&policy);
+        }
+        self.gen_rotating = rotating;
+        self.input_mode = InputMode::Generating;
+    }
+
     pub fn generate_and_copy(&mut self) {
-        let result = match self.gen_selected_option {
-            0 => crate::generator::generate_password(
-                self.gen_length,
-                self.gen_use_special,
-                self.gen_use_numbers,
-            ),
-            1 => crate::generator::generate_token(self.gen_token_format, self.gen_length),
-            _ => return,
+        let policy = self.current_gen_policy();
+
+        if self.gen_rotating {
+            self.gen_rotating = false;
+            self.input_mode = InputMode::Secrets;
+            if let Some(key) = self.selected_secret_key() {
+                self.rotate_secret(&key, policy);
+            }
+            return;
+        }
+
+        let (result, public) = match policy.generate_material() {
+            Ok(material) => (SecretString::new(material.private), material.public),
+            Err(e) => {
+                self.set_temp_message(format!("❌ Error: {}", e));
+                return;
+            }
         };
 
-        // Poner el valor generado en el buffer de edición
+        // Editar el secreto seleccionado o agregar uno nuevo con el valor generado
+        if self.selected_secret_key().is_some() {
+            self.edit_secret();
+        } else {
+            self.add_secret();
+        }
+        self.editing_field = 1;
         self.editing_value_buffer = result.clone();
         self.cursor_position = self.editing_value_buffer.len();
+        self.pending_public_key = public.filter(|_| self.gen_public_sibling);
 
         if let Some(clipboard) = &mut self.clipboard {
-            if clipboard.set_text(result.clone()).is_ok() {
-                self.set_temp_message(format!("{}: {}", self.i18n.t("generated"), result));
+            if clipboard.set_text(result.as_str()).is_ok() {
+                self.set_temp_message(format!("{}: {}", self.i18n.t("generated"), *result));
                 self.clipboard_timestamp = Some(Instant::now());
             }
         }
+    }
 
-        // Volver al modo de edición/agregado
-        if self.table_state.selected().is_some() {
-            self.input_mode = InputMode::Editing;
-        } else {
-            self.input_mode = InputMode::AddingSecret;
+    /// Rota el secreto seleccionado con su política recordada, o abre el generador si no tiene
+    pub fn rotate_selected(&mut self) {
+        let Some(key) = self.selected_secret_key() else {
+            return;
+        };
+        match self.metadata.get(&key).and_then(|m| m.policy.clone()) {
+            Some(policy) => self.rotate_secret(&key, policy),
+            None => self.open_generator(true),
+        }
+    }
+
+    /// Reemplaza el valor por uno generado, conservando el anterior hasta guardar
+    pub fn rotate_secret(&mut self, key: &str, policy: GeneratorPolicy) {
+        let Some(idx) = self.secrets.iter().position(|(k, _)| k == key) else {
+            return;
+        };
+        let policy = self.refresh_policy(policy);
+        let material = match policy.generate_material() {
+            Ok(material) => material,
+            Err(e) => {
+                self.set_temp_message(format!("❌ Error: {}", e));
+                return;
+            }
+        };
+
+        self.replace_rotated_value(idx, material.private);
+        if let Some(public) = material.public {
+            match public_sibling(&self.secrets, key, self.gen_public_sibling) {
+                Some(PublicSibling::Existing(public_idx)) => {
+                    self.replace_rotated_value(public_idx, public)
+                }
+                Some(PublicSibling::New(public_name)) => {
+                    self.secrets.push((public_name.clone(), public));
+                    self.metadata.mark_created(&public_name);
+                }
+                None => {}
+            }
+        }
+
+        self.metadata.entry(key).policy = Some(policy);
+        self.is_modified = true;
+        self.audit(AuditAction::KeyRotated, Some(key));
+        self.set_temp_message(format!("✓ {}: {}", self.i18n.t("rotated"), key));
+    }
+
+    /// Cambia el valor en `idx` conservando el anterior para poder deshacer la rotación
+    fn replace_rotated_value(&mut self, idx: usize, value: String) {
+        let key = self.secrets[idx].0.clone();
+        let previous_value = self.secrets.replace_value(idx, value);
+        let previous_rotated = self.metadata.get(&key).and_then(|m| m.rotated);
+        self.pending_rotations
+            .entry(key.clone())
+            .or_insert(PendingRotation {
+                previous_value,
+                previous_rotated,
+            });
+        self.metadata.mark_rotated(&key);
+    }
+
+    /// Guarda la parte pública generada junto al secreto recién editado o agregado
+    pub fn store_pending_public_key(&mut self, key: &str) {
+        let Some(public) = self.pending_public_key.take() else {
+            return;
+        };
+        let public_name = public_key_name(key);
+        match self.secrets.iter().position(|(k, _)| *k == public_name) {
+            Some(idx) => {
+                self.secrets.set_value(idx, public);
+                self.metadata.mark_rotated(&public_name);
+            }
+            None => {
+                self.secrets.push((public_name.clone(), public));
+                self.metadata.mark_created(&public_name);
+            }
+        }
+    }
+
+    pub fn rollback_selected_rotation(&mut self) {
+        let Some(key) = self.selected_secret_key() else {
+            return;
+        };
+        let Some(mut pending) = self.pending_rotations.remove(&key) else {
+            self.set_temp_message(self.i18n.t("no_rotation_to_undo").to_string());
+            return;
+        };
+
+        if let Some(idx) = self.secrets.iter().position(|(k, _)| *k == key) {
+            self.secrets
+                .set_value(idx, std::mem::take(&mut *pending.previous_value));
+        }
+        self.metadata.entry(&key).rotated = pending.previous_rotated;
+
+        // La parte pública se rotó junto con la privada
+        let public_name = public_key_name(&key);
+        if let Some(mut pending) = self.pending_rotations.remove(&public_name) {
+            if let Some(idx) = self.secrets.iter().position(|(k, _)| *k == public_name) {
+                self.secrets
+                    .set_value(idx, std::mem::take(&mut *pending.previous_value));
+            }
+            self.metadata.entry(&public_name).rotated = pending.previous_rotated;
+        }
+        self.set_temp_message(format!("✓ {}: {}", self.i18n.t("rotation_undone"), key));
+    }
+
+    /// Rota la llave seleccionada en todos los archivos marcados que la contienen.
+    /// Los archivos marcados se guardan de inmediato; el abierto queda pendiente de guardar.
+    pub fn rotate_in_marked_files(&mut self) -> Result<()> {
+        let Some(key) = self.selected_secret_key() else {
+            return Ok(());
+        };
+        let policy = self.refresh_policy(
+            self.metadata
+                .get(&key)
+                .and_then(|m| m.policy.clone())
+                .unwrap_or_else(|| self.current_gen_policy()),
+        );
+        // Validar antes de tocar cualquier archivo
+        policy.generate()?;
+
+        let others: Vec<PathBuf> = self
+            .marked_files
+            .iter()
+            .filter(|p| Some(*p) != self.file_path.as_ref())
+            .cloned()
+            .collect();
+
+        let mut backups = Vec::new();
+        for path in others {
+            let recipients = get_sops_recipients(&path).unwrap_or_default();
+            let age_key = detect_key_index(&self.age_keys, &recipients)
+                .and_then(|i| self.age_keys.get(i).map(|k| k.key.clone()));
+            let Ok(secrets) = decrypt_and_parse(&path, age_key.as_deref()) else {
+                continue;
+            };
+            let mut secrets = SecretList::from(secrets);
+            let Some(idx) = secrets.iter().position(|(k, _)| *k == key) else {
+                continue;
+            };
+            let KeyMaterial { private, public } = policy.generate_material()?;
+            secrets.set_value(idx, private);
+            let mut metadata = load_metadata(&path).unwrap_or_default();
+            metadata.entry(&key).policy = Some(policy.clone());
+            metadata.mark_rotated(&key);
+            let sibling = public.zip(public_sibling(&secrets, &key, self.gen_public_sibling));
+            match sibling {
+                Some((public, PublicSibling::Existing(public_idx))) => {
+                    let public_name = secrets[public_idx].0.clone();
+                    secrets.set_value(public_idx, public);
+                    metadata.mark_rotated(&public_name);
+                }
+                Some((public, PublicSibling::New(public_name))) => {
+                    secrets.push((public_name.clone(), public));
+                    metadata.mark_created(&public_name);
+                }
+                None => {}
+            }
+
+            let backup = RotationBackup {
+                file: path.clone(),
+                content: fs::read(&path)?,
+                sidecar: fs::read(sidecar_path(&path)).ok(),
+            };
+            if let Err(e) = self.write_encrypted(&path, &secrets) {
+                // Lo ya rotado sigue pudiéndose deshacer
+                self.batch_rotation_backup = backups;
+                return Err(e);
+            }
+            backups.push(backup);
+            save_metadata(&path, &metadata)?;
+            self.audit_file(AuditAction::KeyRotated, &path, Some(&key));
+        }
+
+        let count = backups.len();
+        if !backups.is_empty() {
+            self.batch_rotation_backup = backups;
         }
+        self.rotate_secret(&key, policy);
+        self.set_temp_message(format!(
+            "✓ {}: {} (+{} {})",
+            self.i18n.t("rotated"),
+            key,
+            count,
+            self.i18n.t("rotated_files")
+        ));
+        Ok(())
+    }
+
+    /// Restaura los archivos modificados por la última rotación en lote
+    pub fn undo_batch_rotation(&mut self) -> Result<()> {
+        if self.batch_rotation_backup.is_empty() {
+            self.set_temp_message(self.i18n.t("no_rotation_to_undo").to_string());
+            return Ok(());
+        }
+
+        for backup in std::mem::take(&mut self.batch_rotation_backup) {
+            write_atomic(&backup.file, &backup.content)?;
+            let sidecar = sidecar_path(&backup.file);
+            match backup.sidecar {
+                Some(content) => write_private(&sidecar, &content)?,
+                None if sidecar.exists() => fs::remove_file(&sidecar)?,
+                None => {}
+            }
+        }
+        self.set_temp_message(format!("✓ {}", self.i18n.t("batch_rotation_undone")));
+        Ok(())
     }
 
     pub fn clear_expired_message(&mut self) {
@@ -300,7 +957,7 @@ impl App {
     }
 
     pub fn check_auto_lock(&self) -> bool {
-        if self.config.auto_lock_minutes == 0 {
+        if self.config.auto_lock_minutes == 0 || self.input_mode == InputMode::Locked {
             return false;
         }
         let timeout = Duration::from_secs(self.config.auto_lock_minutes * 60);
@@ -308,10 +965,204 @@ impl App {
     }
 
     pub fn lock(&mut self) {
-        self.secrets.clear();
+        self.wipe_secrets();
+        self.is_modified = false;
+        self.pending_rotations.clear();
+        self.metadata = FileMetadata::default();
         self.show_values = false;
         self.file_path = None;
-        self.input_mode = InputMode::Explorer;
+
+        // Sin frase ni identidades age no hay con qué autenticar: solo se limpian los secretos
+        self.lock_challenge = match Challenge::new(&self.age_keys) {
+            Ok(challenge) => challenge,
+            Err(e) => {
+                self.set_temp_message(format!("❌ Error: {}", e));
+                None
+            }
+        };
+        self.lock_buffer = SecretString::default();
+        self.forget_identities();
+        self.input_mode =
+            if self.lock_challenge.is_some() || self.config.lock_passphrase_hash.is_some() {
+                InputMode::Locked
+            } else {
+                self.unlocked_mode()
+            };
+    }
+
+    /// Pantalla tras desbloquear: pide la frase de los archivos de identidades si hace falta
+    fn unlocked_mode(&self) -> InputMode {
+        if !self.insecure_identity_files.is_empty() {
+            InputMode::FixingPermissions
+        } else if self.pending_identity_file().is_some() {
+            InputMode::UnlockingIdentities
+        } else {
+            InputMode::Explorer
+        }
+    }
+
+    /// Deja en 0600 los archivos de identidades que otros usuarios podían leer
+    pub fn fix_identity_permissions(&mut self) {
+        let failed: Vec<String> = std::mem::take(&mut self.insecure_identity_files)
+            .into_iter()
+            .filter_map(|(path, _)| restrict_permissions(&path).err())
+            .map(|e| e.to_string())
+            .collect();
+        if failed.is_empty() {
+            self.set_temp_message(self.i18n.t("permissions_fixed").to_string());
+        } else {
+            self.set_temp_message(format!("❌ Error: {}", failed.join("; ")));
+        }
+        self.input_mode = self.unlocked_mode();
+    }
+
+    pub fn ignore_identity_permissions(&mut self) {
+        self.insecure_identity_files.clear();
+        self.set_temp_message(self.i18n.t("permissions_ignored").to_string());
+        self.input_mode = self.unlocked_mode();
+    }
+
+    /// Primer archivo de identidades cifrado que aún no se ha desbloqueado
+    pub fn pending_identity_file(&self) -> Option<&PathBuf> {
+        self.encrypted_identity_files
+            .iter()
+            .find(|path| !self.unlocked_identity_files.contains(path))
+    }
+
+    fn is_from_encrypted_file(&self, key: &AgeKey) -> bool {
+        matches!(&key.source, KeySource::File(path) if self.encrypted_identity_files.contains(path))
+    }
+
+    /// Descifra el archivo de identidades pendiente con la frase escrita y guarda sus
+    /// llaves en memoria
+    pub fn unlock_identities(&mut self) {
+        let passphrase = std::mem::take(&mut *self.lock_buffer);
+        let passphrase = SecretString::from(passphrase);
+        let Some(path) = self.pending_identity_file().cloned() else {
+            self.input_mode = InputMode::Explorer;
+            return;
+        };
+
+        let source = KeySource::File(path.clone());
+        match fs::read(&path)
+            .map_err(anyhow::Error::from)
+            .and_then(|content| decrypt_identity_file(&content, &passphrase, &source))
+        {
+            Ok(keys) => {
+                let count = keys.len();
+                for key in keys {
+                    lock_memory(key.key.as_bytes());
+                    if !self.age_keys.iter().any(|k| k.key == key.key) {
+                        self.age_keys.push(key);
+                    }
+                }
+                self.unlocked_identity_files.push(path);
+                self.input_mode = self.unlocked_mode();
+                self.set_temp_message(format!(
+                    "✓ {} ({})",
+                    self.i18n.t("identities_unlocked"),
+                    count
+                ));
+            }
+            Err(e) => self.set_temp_message(format!("❌ Error: {}", e)),
+        }
+    }
+
+    /// Olvida las identidades descifradas de archivos protegidos con frase
+    pub fn forget_identities(&mut self) {
+        if self.unlocked_identity_files.is_empty() {
+            return;
+        }
+        for mut key in std::mem::take(&mut self.age_keys) {
+            if self.is_from_encrypted_file(&key) {
+                key.key.zeroize();
+            } else {
+                self.age_keys.push(key);
+            }
+        }
+        self.unlocked_identity_files.clear();
+        self.selected_key_index = None;
+    }
+
+    /// Recarga las llaves en texto plano conservando las ya desbloqueadas en memoria
+    pub fn reload_age_keys(&mut self) -> Result<()> {
+        let mut keys = load_age_keys(&self.config.identity_files)?;
+        for key in std::mem::take(&mut self.age_keys) {
+            if self.is_from_encrypted_file(&key) && !keys.iter().any(|k| k.key == key.key) {
+                keys.push(key);
+            }
+        }
+        self.age_keys = keys;
+        Ok(())
+    }
+
+    /// Desbloquea con la frase configurada o con una identidad age que descifre el reto
+    pub fn try_unlock(&mut self) {
+        let input = std::mem::take(&mut *self.lock_buffer);
+        let input = SecretString::from(input);
+
+        let unlocked = if input.trim().starts_with("AGE-SECRET-KEY-") {
+            self.lock_challenge
+                .as_ref()
+                .is_some_and(|challenge| challenge.verify(&input))
+        } else {
+            self.config
+                .lock_passphrase_hash
+                .as_deref()
+                .is_some_and(|hash| verify_passphrase(&input, hash))
+        };
+
+        if unlocked {
+            self.lock_challenge = None;
+            self.input_mode = self.unlocked_mode();
+            self.set_temp_message(format!("✓ {}", self.i18n.t("unlocked")));
+        } else {
+            self.set_temp_message(format!("❌ {}", self.i18n.t("unlock_failed")));
+        }
+    }
+
+    /// Enter al cambiar la frase: la primera vez pide repetirla, la segunda la guarda
+    /// si coincide. Una frase vacía elimina la actual sin confirmación.
+    pub fn submit_lock_passphrase(&mut self) {
+        let passphrase = SecretString::from(std::mem::take(&mut *self.lock_buffer));
+        match self.lock_passphrase_first.take() {
+            None if !passphrase.is_empty() => self.lock_passphrase_first = Some(passphrase),
+            Some(first) if first != passphrase => {
+                self.set_temp_message(format!("❌ {}", self.i18n.t("lock_passphrase_mismatch")));
+            }
+            _ => self.save_lock_passphrase(&passphrase),
+        }
+    }
+
+    /// Guarda el reto de la frase de desbloqueo; una frase vacía la elimina
+    fn save_lock_passphrase(&mut self, passphrase: &str) {
+        let hash = if passphrase.is_empty() {
+            Ok(None)
+        } else {
+            hash_passphrase(passphrase).map(Some)
+        };
+        let saved = hash.and_then(|hash| {
+            self.config.lock_passphrase_hash = hash;
+            self.save_config()
+        });
+
+        let message = match (saved, passphrase.is_empty()) {
+            (Err(e), _) => format!("{}: {}", self.i18n.t("error_save_config"), e),
+            (Ok(()), true) => format!("✓ {}", self.i18n.t("lock_passphrase_cleared")),
+            (Ok(()), false) => format!("✓ {}", self.i18n.t("lock_passphrase_saved")),
+        };
+        self.set_temp_message(message);
+        self.input_mode = InputMode::Settings;
+    }
+
+    /// Borra de memoria todos los valores descifrados y los buffers que los contienen
+    pub fn wipe_secrets(&mut self) {
+        self.secrets.clear();
+        self.editing_value_buffer = SecretString::default();
+        self.viewing_value = None;
+        self.pending_rotations.clear();
+        self.pending_public_key = None;
+        set_core_dumps(true);
     }
 
     pub fn save_config(&self) -> Result<()> {
@@ -340,14 +1191,7 @@ impl App {
     }
 
     pub fn auto_detect_key(&mut self) -> Option<usize> {
-        for (i, age_key) in self.age_keys.iter().enumerate() {
-            if let Some(pub_key) = &age_key.public_key {
-                if self.file_recipients.contains(pub_key) {
-                    return Some(i);
-                }
-            }
-        }
-        None
+        detect_key_index(&self.age_keys, &self.file_recipients)
     }
 
     pub fn filtered_keys(&self) -> Vec<(usize, &AgeKey)> {
@@ -407,15 +1251,170 @@ impl App {
         if let Some(idx) = self.table_state.selected() {
             let filtered = self.filtered_secrets();
             if let Some(&real_idx) = filtered.get(idx) {
-                if let Some((_, value)) = self.secrets.get(real_idx) {
-                    self.viewing_value = Some(value.clone());
+                if let Some((key, value)) = self.secrets.get(real_idx) {
+                    let key = key.clone();
+                    self.viewing_value = Some(value.clone().into());
                     self.viewing_scroll = 0;
                     self.input_mode = InputMode::ViewingValue;
+                    self.audit(AuditAction::ValueRevealed, Some(&key));
                 }
             }
         }
     }
 
+    pub fn open_history(&mut self) {
+        let Some(path) = self.file_path.clone() else {
+            return;
+        };
+
+        let key = self
+            .selected_key_index
+            .and_then(|i| self.age_keys.get(i).map(|k| k.key.clone()));
+
+        // Descifrar cada revisión puede tardar: se hace fuera del hilo de la interfaz
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || {
+            tx.send(file_history(&path, key.as_deref())).ok();
+        });
+        self.history_loading = Some(rx);
+        self.history_events.clear();
+        self.history_filter = None;
+        self.history_state.select(None);
+        self.input_mode = InputMode::ViewingHistory;
+    }
+
+    /// Recoge el historial cuando termina de calcularse
+    pub fn poll_history(&mut self) {
+        let Some(rx) = &self.history_loading else {
+            return;
+        };
+        let result = match rx.try_recv() {
+            Ok(result) => result,
+            Err(mpsc::TryRecvError::Empty) => return,
+            Err(mpsc::TryRecvError::Disconnected) => {
+                Err(anyhow::anyhow!("El cálculo del historial se interrumpió"))
+            }
+        };
+        self.history_loading = None;
+
+        match result {
+            Ok(events) => {
+                self.history_events = events;
+                let first = (!self.history_events.is_empty()).then_some(0);
+                self.history_state.select(first);
+            }
+            Err(e) => {
+                self.input_mode = InputMode::Secrets;
+                self.set_temp_message(format!("{}: {}", self.i18n.t("history_error"), e));
+            }
+        }
+    }
+
+    pub fn filtered_history(&self) -> Vec<&HistoryEvent> {
+        self.history_events
+            .iter()
+            .filter(|e| {
+                self.history_filter
+                    .as_ref()
+                    .map(|k| e.key.is_empty() || &e.key == k)
+                    .unwrap_or(true)
+            })
+            .collect()
+    }
+
+    /// Alterna el filtro del historial entre todas las llaves y el secreto seleccionado
+    pub fn toggle_history_filter(&mut self) {
+        if self.history_filter.is_some() {
+            self.history_filter = None;
+        } else if let Some(idx) = self.table_state.selected() {
+            let filtered = self.filtered_secrets();
+            if let Some(&real_idx) = filtered.get(idx) {
+                self.history_filter = self.secrets.get(real_idx).map(|(k, _)| k.clone());
+            }
+        }
+        let first = (!self.filtered_history().is_empty()).then_some(0);
+        self.history_state.select(first);
+    }
+
+    pub fn expiry_status(&self, key: &str) -> ExpiryStatus {
+        let today = chrono::Local::now().date_naive();
+        self.metadata
+            .get(key)
+            .map(|m| m.status(today, self.config.expiry_warning_days))
+            .unwrap_or(ExpiryStatus::Ok)
+    }
+
+    fn selected_secret_key(&self) -> Option<String> {
+        let idx = self.table_state.selected()?;
+        let real_idx = *self.filtered_secrets().get(idx)?;
+        self.secrets.get(real_idx).map(|(k, _)| k.clone())
+    }
+
+    pub fn open_metadata_editor(&mut self) {
+        let Some(key) = self.selected_secret_key() else {
+            return;
+        };
+        let meta = self.metadata.get(&key).cloned().unwrap_or_default();
+        self.meta_buffers = [
+            meta.owner.unwrap_or_default(),
+            meta.expires.map(|d| d.to_string()).unwrap_or_default(),
+            meta.rotation_days
+                .map(|d| d.to_string())
+                .unwrap_or_default(),
+        ];
+        self.editing_field = 0;
+        self.input_mode = InputMode::EditingMetadata;
+    }
+
+    /// Valida los campos del modal y guarda el sidecar de inmediato (no está cifrado)
+    pub fn save_metadata_editor(&mut self) -> Result<()> {
+        let (Some(key), Some(file_path)) = (self.selected_secret_key(), self.file_path.clone())
+        else {
+            return Ok(());
+        };
+
+        let [owner, expires, rotation_days] = &self.meta_buffers;
+        let owner = Some(owner.trim().to_string()).filter(|o| !o.is_empty());
+        let expires = match expires.trim() {
+            "" => None,
+            date => Some(
+                chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d")
+                    .with_context(|| format!("{}: {}", self.i18n.t("error_meta_date"), date))?,
+            ),
+        };
+        let rotation_days = match rotation_days.trim() {
+            "" => None,
+            days => Some(
+                days.parse::<u32>()
+                    .ok()
+                    .filter(|d| ROTATION_DAYS_RANGE.contains(d))
+                    .with_context(|| {
+                        format!(
+                            "{} ({}-{}): {}",
+                            self.i18n.t("error_meta_days"),
+                            ROTATION_DAYS_RANGE.start(),
+                            ROTATION_DAYS_RANGE.end(),
+                            days
+                        )
+                    })?,
+            ),
+        };
+
+        // En disco solo se actualiza esta llave: las rotaciones pendientes se guardan con el archivo
+        let mut on_disk = load_metadata(&file_path)?;
+        for metadata in [&mut on_disk, &mut self.metadata] {
+            let meta = metadata.entry(&key);
+            meta.owner = owner.clone();
+            meta.expires = expires;
+            meta.rotation_days = rotation_days;
+        }
+        save_metadata(&file_path, &on_disk)?;
+
+        self.input_mode = InputMode::Secrets;
+        self.set_temp_message(format!("✓ {}", self.i18n.t("meta_saved")));
+        Ok(())
+    }
+
     pub fn format_json_value(&self, value: &str) -> String {
         if let Ok(json) = serde_json::from_str::<Value>(value) {
             serde_json::to_string_pretty(&json).unwrap_or_else(|_| value.to_string())
@@ -440,14 +1439,24 @@ impl App {
         let sops_file = self.current_dir.join(".sops.yaml");
 
         if sops_file.exists() {
-            // Abrir con editor externo
-            self.open_file_in_editor(&sops_file)?;
-            self.files = Self::list_files(&self.current_dir)?;
+            match SopsConfig::load(&sops_file) {
+                Ok(config) => self.open_sops_config_editor(config),
+                Err(e) => {
+                    // No se puede editar por campos: abrir con editor externo para corregirlo
+                    self.open_file_in_editor(&sops_file)?;
+                    self.files = Self::list_files(&self.current_dir)?;
+                    self.set_temp_message(format!("❌ Error: {:#}", e));
+                }
+            }
             return Ok(());
         }
 
         if self.age_keys.is_empty() {
-            self.input_mode = InputMode::ConfirmingKeyCreation;
+            self.input_mode = if self.pending_identity_file().is_some() {
+                InputMode::UnlockingIdentities
+            } else {
+                InputMode::ConfirmingKeyCreation
+            };
             return Ok(());
         }
 
@@ -477,57 +1486,326 @@ impl App {
             }
         }
 
-        anyhow::bail!("No se encontró ningún editor (nano, vim, vi)")
-    }
+        anyhow::bail!("No se encontró ningún editor (nano, vim, vi)")
+    }
+
+    /// Carga la libreta de recipients global y la del directorio actual
+    pub fn reload_address_book(&mut self) {
+        match AddressBook::load(&self.current_dir) {
+            Ok(book) => self.address_book = book,
+            Err(e) => self.set_temp_message(format!("❌ Error: {:#}", e)),
+        }
+    }
+
+    /// Nombre de un recipient según la libreta o las llaves propias
+    pub fn recipient_label(&self, recipient: &str) -> String {
+        if let Some(label) = self.address_book.label(recipient) {
+            return label;
+        }
+        let own = self
+            .age_keys
+            .iter()
+            .find(|k| k.is_recipient_of(&[recipient.to_string()]));
+        match own.and_then(|k| k.comment.as_ref()) {
+            Some(comment) => comment.clone(),
+            None if recipient.chars().count() > 20 => {
+                format!("{}…", recipient.chars().take(20).collect::<String>())
+            }
+            None => recipient.to_string(),
+        }
+    }
+
+    /// Llaves propias, personas y grupos de la libreta que se pueden elegir como recipients
+    pub fn sops_recipient_choices(&self) -> Vec<RecipientChoice> {
+        let mut choices: Vec<RecipientChoice> = self
+            .age_keys
+            .iter()
+            .filter_map(|k| {
+                let public_key = k.public_key.clone()?;
+                Some(RecipientChoice {
+                    label: k
+                        .comment
+                        .clone()
+                        .unwrap_or_else(|| self.i18n.t("unnamed").to_string()),
+                    keys: vec![public_key],
+                })
+            })
+            .collect();
+        for (name, contact) in &self.address_book.people {
+            choices.push(RecipientChoice {
+                label: format!("👤 {}", contact.display(name)),
+                keys: contact.keys.clone(),
+            });
+        }
+        for group in self.address_book.groups() {
+            let keys = self.address_book.resolve(&format!("@{}", group));
+            choices.push(RecipientChoice {
+                label: format!("👥 @{} ({})", group, keys.len()),
+                keys,
+            });
+        }
+        choices
+    }
+
+    /// Plantillas incorporadas y las del usuario; si las del usuario fallan se avisa
+    pub fn open_sops_template_selector(&mut self) {
+        self.sops_templates = SopsTemplate::all().unwrap_or_else(|e| {
+            self.set_temp_message(format!("❌ Error: {:#}", e));
+            SopsTemplate::builtin()
+        });
+        self.selected_sops_template = 0;
+        self.input_mode = InputMode::SelectingSopsTemplate;
+    }
+
+    /// Tras nombrar el archivo nuevo: si ya hay un `.sops.yaml` se le añade una regla,
+    /// si no se elige una plantilla para crearlo
+    pub fn choose_new_file_recipients(&mut self) -> Result<()> {
+        let file = self.current_dir.join(self.new_file_name());
+        self.new_file_resolution = Resolution::resolve(&file)?;
+        if self.new_file_resolution.is_some() {
+            self.open_sops_key_selector();
+        } else {
+            self.open_sops_template_selector();
+        }
+        Ok(())
+    }
+
+    pub fn open_sops_key_selector(&mut self) {
+        let choices = self.sops_recipient_choices();
+        self.selected_sops_keys = vec![false; choices.len()];
+
+        // Marcar los recipients de la regla actual si todos están entre las opciones
+        if let Some(rule) = self.new_file_resolution.as_ref().and_then(|r| r.rule()) {
+            let current = rule.recipients();
+            let marked: Vec<bool> = choices
+                .iter()
+                .map(|c| !c.keys.is_empty() && c.keys.iter().all(|k| current.contains(k)))
+                .collect();
+            let covered = current.iter().all(|r| {
+                choices
+                    .iter()
+                    .zip(&marked)
+                    .any(|(c, m)| *m && c.keys.contains(r))
+            });
+            if covered {
+                self.selected_sops_keys = marked;
+            }
+        }
+
+        self.key_list_state.select(Some(0));
+        self.input_mode = InputMode::SelectingSopsKeys;
+    }
+
+    pub fn create_sops_config(&mut self) -> Result<()> {
+        let sops_file = self.current_dir.join(SOPS_CONFIG_FILE);
+
+        let mut public_keys: Vec<String> = Vec::new();
+        for (i, choice) in self.sops_recipient_choices().into_iter().enumerate() {
+            if self.selected_sops_keys.get(i).copied().unwrap_or(false) {
+                for key in choice.keys {
+                    if !public_keys.contains(&key) {
+                        public_keys.push(key);
+                    }
+                }
+            }
+        }
+
+        if public_keys.is_empty() {
+            self.set_temp_message(self.i18n.t("no_keys_selected").to_string());
+            return Ok(());
+        }
+
+        if let Some(resolution) = self.new_file_resolution.take() {
+            return self.add_sops_file_rule(resolution, &public_keys);
+        }
 
-    pub fn create_sops_config(&mut self) -> Result<()> {
-        let sops_file = self.current_dir.join(".sops.yaml");
+        let template = self
+            .sops_templates
+            .get(self.selected_sops_template)
+            .cloned()
+            .unwrap_or(SopsTemplate::SensitiveValues);
+        let config = template.config(&self.new_file_name(), &public_keys)?;
+        config.save(&sops_file)?;
 
-        let public_keys: Vec<String> = self
-            .age_keys
-            .iter()
-            .enumerate()
-            .filter(|(i, _)| self.selected_sops_keys.get(*i).copied().unwrap_or(false))
-            .filter_map(|(_, k)| k.public_key.clone())
-            .collect();
+        self.audit_file(AuditAction::RecipientsChanged, &sops_file, None);
+        self.files = Self::list_files(&self.current_dir)?;
+        self.set_temp_message(self.i18n.t("sops_initialized").to_string());
 
-        if public_keys.is_empty() {
-            self.set_temp_message(self.i18n.t("no_keys_selected").to_string());
-            return Ok(());
+        Ok(())
+    }
+
+    /// Añade al `.sops.yaml` existente una regla para el archivo nuevo; el resto del archivo,
+    /// comentarios incluidos, queda como estaba
+    fn add_sops_file_rule(&mut self, resolution: Resolution, recipients: &[String]) -> Result<()> {
+        let Resolution {
+            config_path,
+            mut config,
+            relative,
+            ..
+        } = resolution;
+        let rule_count = config.creation_rules.len();
+        let (idx, changed) = config.add_file_rule(&relative, recipients);
+        if changed {
+            let replace = config.creation_rules.len() == rule_count;
+            config.save_rule(&config_path, idx, replace)?;
+            self.audit_file(AuditAction::RecipientsChanged, &config_path, None);
+            self.files = Self::list_files(&self.current_dir)?;
+        }
+
+        let action = if changed {
+            "sops_rule_added"
+        } else {
+            "sops_rule_reused"
+        };
+        let mut message = format!(
+            "✓ {} {} — {}",
+            self.i18n.t(action),
+            idx + 1,
+            config_path.display()
+        );
+        let dir = config_path.parent().unwrap_or(&self.current_dir);
+        if let Some((rule, by)) = config.shadowed_rules(dir).first() {
+            message.push_str(&format!(
+                " · ⚠ {} {} {} {}",
+                self.i18n.t("sops_rule"),
+                rule + 1,
+                self.i18n.t("sops_rule_shadowed_by"),
+                by + 1
+            ));
         }
+        self.set_temp_message(message);
+        Ok(())
+    }
 
-        let age_keys = public_keys.join(",\n        ");
+    pub fn open_sops_config_editor(&mut self, config: SopsConfig) {
+        self.sops_config = config;
+        self.sops_rule_selected = 0;
+        self.sops_field_selected = 0;
+        self.sops_field_editing = false;
+        self.sops_comments_confirmed = false;
+        self.refresh_sops_preview();
+        self.input_mode = InputMode::EditingSopsConfig;
+    }
 
-        // Obtener nombre del archivo
-        let formats = ["env", "json", "yaml", "ini"];
-        let ext = formats[self.selected_format];
+    pub fn refresh_sops_preview(&mut self) {
+        self.sops_preview = self.sops_config.preview(&self.current_dir);
+        self.sops_shadowed = self.sops_config.shadowed_rules(&self.current_dir);
+    }
 
-        let filename = if self.new_file_name_buffer.is_empty() {
-            format!("secrets.{}", ext)
-        } else if self.new_file_name_buffer.ends_with(&format!(".{}", ext)) {
-            self.new_file_name_buffer.clone()
+    pub fn selected_sops_rule(&self) -> Option<&CreationRule> {
+        self.sops_config.creation_rules.get(self.sops_rule_selected)
+    }
+
+    pub fn start_sops_field_edit(&mut self) {
+        if let Some(rule) = self.selected_sops_rule() {
+            self.edit_buffer = rule.field_text(self.sops_field_selected);
+            self.sops_field_editing = true;
+        }
+    }
+
+    /// Aplica `edit_buffer` al campo; en los recipients se aceptan nombres y @grupos de la libreta
+    pub fn commit_sops_field_edit(&mut self) {
+        let field = self.sops_field_selected;
+        let value = if RULE_FIELDS[field].ends_with("_regex") {
+            self.edit_buffer.clone()
         } else {
-            format!("{}.{}", self.new_file_name_buffer, ext)
+            self.edit_buffer
+                .split('|')
+                .map(|group| {
+                    group
+                        .split(',')
+                        .flat_map(|r| self.resolve_recipients(r))
+                        .collect::<Vec<_>>()
+                        .join(",")
+                })
+                .collect::<Vec<_>>()
+                .join("|")
         };
+        if let Some(rule) = self
+            .sops_config
+            .creation_rules
+            .get_mut(self.sops_rule_selected)
+        {
+            rule.set_field(field, &value);
+        }
+        self.edit_buffer.clear();
+        self.sops_field_editing = false;
+        self.refresh_sops_preview();
+    }
 
-        // Crear regex que coincida con el archivo en cualquier ubicación
-        let filename_regex = filename.replace(".", "\\.");
+    /// Añade una regla tras la seleccionada, con las llaves propias como recipients
+    pub fn add_sops_rule(&mut self) {
+        let rule = CreationRule {
+            age: self
+                .age_keys
+                .iter()
+                .filter_map(|k| k.public_key.clone())
+                .collect(),
+            ..Default::default()
+        };
+        let idx = if self.sops_config.creation_rules.is_empty() {
+            0
+        } else {
+            self.sops_rule_selected + 1
+        };
+        self.sops_config.creation_rules.insert(idx, rule);
+        self.sops_rule_selected = idx;
+        self.sops_field_selected = 0;
+        self.start_sops_field_edit();
+    }
 
-        let config_content = format!(
-            "# SOPS configuration for {}\ncreation_rules:\n  - path_regex: (^|/){}$\n    encrypted_regex: '^(password|passwd|pass|secret|key|token|api[_-]?key|private[_-]?key|access[_-]?key|auth|credential|database[_-]?url|.*[Ss]ecret.*|.*[Kk]ey.*|.*[Tt]oken.*)$'\n    age: >\n      {}\n",
-            filename, filename_regex, age_keys
-        );
+    pub fn delete_sops_rule(&mut self) {
+        let rules = &mut self.sops_config.creation_rules;
+        if self.sops_rule_selected < rules.len() {
+            rules.remove(self.sops_rule_selected);
+            self.sops_rule_selected = self.sops_rule_selected.min(rules.len().saturating_sub(1));
+            self.refresh_sops_preview();
+        }
+    }
 
-        fs::write(&sops_file, config_content)?;
-        self.files = Self::list_files(&self.current_dir)?;
-        self.set_temp_message(self.i18n.t("sops_initialized").to_string());
+    /// Mueve la regla seleccionada; el orden decide qué regla aplica a cada archivo
+    pub fn move_sops_rule(&mut self, up: bool) {
+        let idx = self.sops_rule_selected;
+        let target = if up {
+            idx.checked_sub(1)
+        } else {
+            Some(idx + 1).filter(|t| *t < self.sops_config.creation_rules.len())
+        };
+        if let Some(target) = target {
+            self.sops_config.creation_rules.swap(idx, target);
+            self.sops_rule_selected = target;
+            self.refresh_sops_preview();
+        }
+    }
 
+    /// Abre `.sops.yaml` en el editor externo y recarga el resultado
+    pub fn edit_sops_config_externally(&mut self) -> Result<()> {
+        let sops_file = self.current_dir.join(SOPS_CONFIG_FILE);
+        if SopsConfig::load(&sops_file).ok().as_ref() != Some(&self.sops_config) {
+            anyhow::bail!("{}", self.i18n.t("sops_unsaved_changes"));
+        }
+        self.open_file_in_editor(&sops_file)?;
+        let config = SopsConfig::load(&sops_file)?;
+        let selected = self.sops_rule_selected;
+        self.open_sops_config_editor(config);
+        self.sops_rule_selected =
+            selected.min(self.sops_config.creation_rules.len().saturating_sub(1));
         Ok(())
     }
 
+    /// Guarda el editor estructurado; si el archivo tiene comentarios, que se pierden al
+    /// reescribirlo, la primera vez solo avisa
     pub fn save_sops_config(&mut self) -> Result<()> {
-        let sops_file = self.current_dir.join(".sops.yaml");
-        fs::write(&sops_file, &self.edit_buffer)?;
+        let sops_file = self.current_dir.join(SOPS_CONFIG_FILE);
+        if !self.sops_comments_confirmed
+            && fs::read_to_string(&sops_file).is_ok_and(|content| has_comments(&content))
+        {
+            self.sops_comments_confirmed = true;
+            self.set_temp_message(format!("⚠ {}", self.i18n.t("sops_comments_lost")));
+            return Ok(());
+        }
+        self.sops_config.save(&sops_file)?;
+        self.audit_file(AuditAction::RecipientsChanged, &sops_file, None);
         self.files = Self::list_files(&self.current_dir)?;
         self.set_temp_message(self.i18n.t("sops_saved").to_string());
         self.input_mode = InputMode::Explorer;
@@ -537,6 +1815,8 @@ impl App {
     pub fn generate_age_key(&mut self) -> Result<()> {
         use std::process::Command;
 
+        let keys_path = self.writable_keys_path()?;
+
         let output = Command::new("age-keygen")
             .output()
             .context("No se pudo ejecutar age-keygen. ¿Está instalado?")?;
@@ -564,78 +1844,337 @@ impl App {
             key_content.trim_end().to_string()
         };
 
-        // Agregar al archivo de llaves
-        let keys_path = dirs::home_dir()
-            .context("No se pudo obtener directorio home")?
-            .join(".config/sops/age/keys.txt");
-
-        if let Some(parent) = keys_path.parent() {
-            fs::create_dir_all(parent)?;
-        }
-
-        use std::fs::OpenOptions;
-        use std::io::Write;
-
-        let mut file = OpenOptions::new()
-            .create(true)
-            .append(true)
-            .open(&keys_path)?;
-
+        // Agregar al archivo de llaves (0600, en un directorio 0700)
+        let mut content =
+            zeroize::Zeroizing::new(fs::read_to_string(&keys_path).unwrap_or_default());
         // Agregar separador si el archivo no está vacío
-        let metadata = file.metadata()?;
-        if metadata.len() > 0 {
-            writeln!(file)?;
+        if !content.is_empty() {
+            content.push('\n');
         }
-        writeln!(file, "{}", final_content)?;
+        content.push_str(&final_content);
+        content.push('\n');
+        write_private_atomic(&keys_path, content.as_bytes())?;
 
         // Recargar llaves
-        self.age_keys = crate::sops::load_age_keys()?;
+        self.reload_age_keys()?;
         self.new_key_comment.clear();
         self.set_temp_message(self.i18n.t("key_generated").to_string());
 
         Ok(())
     }
 
-    pub fn delete_selected_age_key(&mut self) -> Result<()> {
-        if self.age_keys.is_empty() {
-            return Ok(());
-        }
+    /// Informe de qué archivos SOPS del árbol actual descifra cada llave
+    pub fn open_key_usage(&mut self) {
+        self.usage_report = Some(UsageReport::scan(
+            &self.current_dir,
+            &self.age_keys,
+            &self.address_book,
+        ));
+        self.usage_scroll = 0;
+        self.input_mode = InputMode::ViewingKeyUsage;
+    }
+
+    /// Busca los archivos que dejarían de poder descifrarse y pide confirmación
+    pub fn prepare_key_deletion(&mut self) -> Result<()> {
+        let idx = self.key_manager_selected;
+        self.editable_keys_path(idx)?;
+
+        let mut roots = vec![self.current_dir.clone()];
+        roots.extend(self.favorites.iter().cloned());
+        self.key_deletion_dependents =
+            files_depending_on(&self.age_keys[idx], &self.age_keys, &roots);
+        self.input_mode = InputMode::ConfirmingKeyDeletion;
+        Ok(())
+    }
 
+    /// Elimina la llave guardando antes un respaldo cifrado del archivo completo para las
+    /// llaves que quedan; si no queda ninguna, el respaldo se cifra con frase
+    pub fn delete_selected_age_key(&mut self) -> Result<()> {
         let idx = self.key_manager_selected;
         if idx >= self.age_keys.len() {
             return Ok(());
         }
+        let keys_path = self.editable_keys_path(idx)?;
 
-        // Leer el archivo original
-        let keys_path = dirs::home_dir()
-            .context("No se pudo obtener directorio home")?
-            .join(".config/sops/age/keys.txt");
+        let deleted = &self.age_keys[idx].key;
+        let mut recipients: Vec<String> = self
+            .age_keys
+            .iter()
+            .filter(|k| &k.key != deleted && k.plugin().is_none())
+            .filter_map(|k| k.public_key.clone())
+            .collect();
+        recipients.sort();
+        recipients.dedup();
 
-        let original_content = fs::read_to_string(&keys_path)?;
+        if recipients.is_empty() {
+            self.key_transfer = Some(KeyTransfer::Delete);
+            self.input_mode = InputMode::EnteringKeyPassphrase;
+            return Ok(());
+        }
 
-        // Dividir en bloques de llaves (separados por líneas vacías)
-        let blocks: Vec<&str> = original_content.split("\n\n").collect();
+        let original = zeroize::Zeroizing::new(fs::read(&keys_path)?);
+        let backup = encrypt_to_recipients(&original, &recipients)?;
+        self.remove_age_key(idx, &keys_path, &backup)
+    }
 
-        // Filtrar el bloque que contiene la llave a eliminar
-        let key_to_remove = &self.age_keys[idx].key;
-        let filtered_blocks: Vec<&str> = blocks
-            .into_iter()
-            .filter(|block| !block.contains(key_to_remove))
-            .collect();
+    fn remove_age_key(&mut self, idx: usize, keys_path: &Path, backup: &[u8]) -> Result<()> {
+        let original = zeroize::Zeroizing::new(fs::read_to_string(keys_path)?);
+        let updated = zeroize::Zeroizing::new(remove_identity(&original, &self.age_keys[idx].key)?);
 
-        // Reconstruir el archivo
-        let new_content = filtered_blocks.join("\n\n").trim_end().to_string();
-        fs::write(&keys_path, new_content)?;
+        let backup_file = backup_path(keys_path, &Utc::now().format("%Y%m%d-%H%M%S").to_string());
+        write_private(&backup_file, backup)?;
+        write_private_atomic(keys_path, updated.as_bytes())?;
 
         // Actualizar la lista en memoria
         self.age_keys.remove(idx);
+        self.key_deletion_dependents.clear();
 
         if self.key_manager_selected >= self.age_keys.len() && self.key_manager_selected > 0 {
             self.key_manager_selected -= 1;
         }
 
-        self.set_temp_message(self.i18n.t("key_deleted").to_string());
+        self.set_temp_message(format!(
+            "{} · {}: {}",
+            self.i18n.t("key_deleted"),
+            self.i18n.t("backup_saved"),
+            KeySource::File(backup_file).label()
+        ));
+
+        Ok(())
+    }
+
+    /// Archivo de texto plano que contiene la llave; el entorno, SSH y los archivos
+    /// cifrados son de solo lectura
+    fn editable_keys_path(&self, idx: usize) -> Result<PathBuf> {
+        let key = self.age_keys.get(idx).context("Llave no encontrada")?;
+        match &key.source {
+            _ if !key.key.starts_with("AGE-") => {
+                anyhow::bail!("{}", self.i18n.t("ssh_key_read_only"))
+            }
+            KeySource::File(path) if !self.encrypted_identity_files.contains(path) => {
+                Ok(path.clone())
+            }
+            KeySource::File(_) => anyhow::bail!("{}", self.i18n.t("identity_file_read_only")),
+            KeySource::Env => anyhow::bail!("{}", self.i18n.t("key_from_env_read_only")),
+        }
+    }
+
+    /// Archivo de llaves donde se escriben las identidades nuevas
+    fn writable_keys_path(&self) -> Result<PathBuf> {
+        let keys_path = age_keys_path()?;
+        if self.encrypted_identity_files.contains(&keys_path) {
+            anyhow::bail!("{}", self.i18n.t("identity_file_read_only"));
+        }
+        Ok(keys_path)
+    }
+
+    pub fn copy_selected_public_key(&mut self) {
+        let Some(key) = self.age_keys.get(self.key_manager_selected) else {
+            return;
+        };
+        let Some(public_key) = key.public_key.clone() else {
+            self.set_temp_message(format!("❌ {}", self.i18n.t("no_public_key")));
+            return;
+        };
+        // La llave pública no es secreta: no se programa el borrado del portapapeles
+        if let Some(clipboard) = &mut self.clipboard {
+            if clipboard.set_text(public_key).is_ok() {
+                self.set_temp_message(self.i18n.t("public_key_copied").to_string());
+            }
+        }
+    }
+
+    pub fn start_key_export(&mut self) -> Result<()> {
+        let key = self
+            .age_keys
+            .get(self.key_manager_selected)
+            .context("Llave no encontrada")?;
+        if !key.key.starts_with("AGE-") {
+            anyhow::bail!("{}", self.i18n.t("ssh_key_read_only"));
+        }
+        // Junto a las llaves y no en el directorio actual, que suele ser un repositorio
+        let keys_dir = age_keys_path()?
+            .parent()
+            .map(Path::to_path_buf)
+            .unwrap_or_default();
+        self.key_io_buffer = keys_dir.join("identity.age").display().to_string();
+        self.input_mode = InputMode::ExportingKey;
+        Ok(())
+    }
+
+    pub fn start_key_comment_edit(&mut self) -> Result<()> {
+        let idx = self.key_manager_selected;
+        self.editable_keys_path(idx)?;
+        self.key_io_buffer = self.age_keys[idx]
+            .comment
+            .clone()
+            .filter(|c| !is_metadata_comment(c))
+            .unwrap_or_default();
+        self.input_mode = InputMode::EditingKeyComment;
+        Ok(())
+    }
+
+    /// Importa identidades desde una ruta o desde el texto pegado en el modal
+    pub fn import_keys(&mut self) -> Result<()> {
+        let mut input = std::mem::take(&mut self.key_io_buffer);
+        let path = expand_home(input.trim());
+        let result = if !input.trim().contains('\n') && path.is_file() {
+            let content = fs::read(&path)?;
+            if is_encrypted_identity_file(&content) {
+                self.key_transfer = Some(KeyTransfer::Import(content));
+                self.input_mode = InputMode::EnteringKeyPassphrase;
+                return Ok(());
+            }
+            let text = zeroize::Zeroizing::new(String::from_utf8(content)?);
+            self.import_identities(parse_identities(&text))
+        } else {
+            self.import_identities(parse_identities(&input))
+        };
+        input.zeroize();
+        result
+    }
+
+    fn import_identities(&mut self, keys: Vec<AgeKey>) -> Result<()> {
+        let mut keys = new_identities(keys, &self.age_keys);
+        if keys.is_empty() {
+            self.set_temp_message(self.i18n.t("no_keys_imported").to_string());
+            self.input_mode = InputMode::ManagingKeys;
+            return Ok(());
+        }
+
+        let keys_path = self.writable_keys_path()?;
+        let result = append_identities(&keys_path, &keys);
+        let count = keys.len();
+        for key in keys.iter_mut() {
+            key.key.zeroize();
+        }
+        result?;
+
+        self.reload_age_keys()?;
+        self.set_temp_message(format!("✓ {} {}", count, self.i18n.t("keys_imported")));
+        self.input_mode = InputMode::ManagingKeys;
+        Ok(())
+    }
+
+    /// Confirma la ruta de exportación; la frase se pide a continuación. Un archivo que ya
+    /// existe no se sobrescribe: la ruta queda en el campo para cambiarla.
+    pub fn choose_export_path(&mut self) {
+        let path = expand_home(self.key_io_buffer.trim());
+        if path.exists() {
+            self.set_temp_message(format!(
+                "❌ {}: {}",
+                self.i18n.t("export_file_exists"),
+                path.display()
+            ));
+            return;
+        }
+        self.key_io_buffer.clear();
+        self.key_transfer = Some(KeyTransfer::Export(path));
+        self.input_mode = InputMode::EnteringKeyPassphrase;
+    }
+
+    /// Completa la importación cifrada o la exportación con la frase escrita
+    pub fn finish_key_transfer(&mut self) -> Result<()> {
+        let passphrase = std::mem::take(&mut *self.lock_buffer);
+        let passphrase = SecretString::from(passphrase);
+        self.input_mode = InputMode::ManagingKeys;
+
+        match self.key_transfer.take() {
+            Some(KeyTransfer::Import(content)) => {
+                let keys = decrypt_identity_file(&content, &passphrase, &KeySource::Env)?;
+                self.import_identities(keys)
+            }
+            Some(KeyTransfer::Delete) => {
+                if passphrase.is_empty() {
+                    anyhow::bail!("{}", self.i18n.t("backup_passphrase_required"));
+                }
+                let idx = self.key_manager_selected;
+                let keys_path = self.editable_keys_path(idx)?;
+                let original = zeroize::Zeroizing::new(fs::read(&keys_path)?);
+                let backup = encrypt_with_passphrase(&original, &passphrase)?;
+                self.remove_age_key(idx, &keys_path, &backup)
+            }
+            Some(KeyTransfer::Export(path)) => {
+                // Una identidad en texto plano fuera del archivo de llaves acaba en git o en
+                // una copia de seguridad sin que nadie lo note
+                if passphrase.is_empty() {
+                    anyhow::bail!("{}", self.i18n.t("export_passphrase_required"));
+                }
+                let key = self
+                    .age_keys
+                    .get(self.key_manager_selected)
+                    .context("Llave no encontrada")?;
+                if path.exists() {
+                    anyhow::bail!("{}: {}", self.i18n.t("export_file_exists"), path.display());
+                }
+                let block = zeroize::Zeroizing::new(identity_block(key));
+                write_private(
+                    &path,
+                    &encrypt_with_passphrase(block.as_bytes(), &passphrase)?,
+                )?;
+                self.files = Self::list_files(&self.current_dir)?;
+                self.set_temp_message(format!(
+                    "✓ {}: {}",
+                    self.i18n.t("key_exported"),
+                    path.display()
+                ));
+                Ok(())
+            }
+            None => Ok(()),
+        }
+    }
+
+    pub fn save_key_comment(&mut self) -> Result<()> {
+        let idx = self.key_manager_selected;
+        let keys_path = self.editable_keys_path(idx)?;
+        let content = fs::read_to_string(&keys_path)?;
+        let updated = set_comment(&content, &self.age_keys[idx].key, &self.key_io_buffer)?;
+        write_private_atomic(&keys_path, updated.as_bytes())?;
+
+        self.key_io_buffer.clear();
+        self.reload_age_keys()?;
+        self.set_temp_message(self.i18n.t("key_comment_saved").to_string());
+        self.input_mode = InputMode::ManagingKeys;
+        Ok(())
+    }
+
+    /// Recipients escritos a mano: llaves age o SSH, o nombres y `@grupos` de la libreta
+    fn resolve_recipients(&self, input: &str) -> Vec<String> {
+        let input = input.trim();
+        if input.starts_with("ssh-") {
+            return vec![input.to_string()];
+        }
+        input
+            .split([',', ' '])
+            .filter(|s| !s.is_empty())
+            .flat_map(|s| match self.address_book.resolve(s) {
+                keys if keys.is_empty() => vec![s.to_string()],
+                keys => keys,
+            })
+            .collect()
+    }
 
+    /// Respalda el archivo de llaves y los `identity_files` en texto plano, cifrados para
+    /// los recipients indicados, en `backups/` junto al archivo de llaves
+    pub fn backup_age_keys(&mut self) -> Result<()> {
+        let recipients = self.resolve_recipients(&self.key_io_buffer);
+        let paths = identity_file_paths(&self.config.identity_files)?;
+        let content = bundle_identity_files(&paths)?;
+        let encrypted = encrypt_to_recipients(content.as_bytes(), &recipients)?;
+
+        let backup = backup_path(
+            &age_keys_path()?,
+            &Utc::now().format("%Y%m%d-%H%M%S").to_string(),
+        );
+        write_private(&backup, &encrypted)?;
+
+        self.key_io_buffer.clear();
+        self.set_temp_message(format!(
+            "✓ {}: {}",
+            self.i18n.t("keys_backed_up"),
+            backup.display()
+        ));
+        self.input_mode = InputMode::ManagingKeys;
         Ok(())
     }
 
@@ -681,12 +2220,12 @@ impl App {
         Ok(())
     }
 
-    pub fn create_encrypted_file(&mut self) -> Result<()> {
-        let formats = ["env", "json", "yaml", "ini"];
-        let ext = formats[self.selected_format];
+    /// Nombre del archivo nuevo según el buffer y el formato elegido
+    pub fn new_file_name(&self) -> String {
+        let ext = FILE_FORMATS[self.selected_format];
 
         // Si el buffer está vacío, usar "secrets" como nombre por defecto
-        let filename = if self.new_file_name_buffer.is_empty() {
+        if self.new_file_name_buffer.is_empty() {
             format!("secrets.{}", ext)
         } else if self.new_file_name_buffer.starts_with('.') {
             // Si ya empieza con punto, no agregar extensión
@@ -695,7 +2234,12 @@ impl App {
             self.new_file_name_buffer.clone()
         } else {
             format!("{}.{}", self.new_file_name_buffer, ext)
-        };
+        }
+    }
+
+    pub fn create_encrypted_file(&mut self) -> Result<()> {
+        let ext = FILE_FORMATS[self.selected_format];
+        let filename = self.new_file_name();
 
         let file_path = self.current_dir.join(&filename);
 
@@ -747,11 +2291,9 @@ impl App {
         cmd.current_dir(&self.current_dir).arg(&filename); // Usar solo el nombre del archivo, no la ruta completa
 
         // Asegurar que SOPS encuentre las llaves age
-        let age_key_file = std::env::var("SOPS_AGE_KEY_FILE").unwrap_or_else(|_| {
-            let home = std::env::var("HOME").unwrap_or_else(|_| ".".to_string());
-            format!("{}/.config/sops/age/keys.txt", home)
-        });
-        cmd.env("SOPS_AGE_KEY_FILE", age_key_file);
+        if let Ok(age_key_file) = age_keys_path() {
+            cmd.env("SOPS_AGE_KEY_FILE", age_key_file);
+        }
 
         let output = cmd.output()?;
 
@@ -824,19 +2366,32 @@ impl App {
                     self.file_list_state.select(Some(0));
                 } else {
                     self.file_path = Some(path.clone());
-                    self.file_recipients = get_sops_recipients(&path).unwrap_or_default();
+                    self.refresh_file_rule();
+                    self.reload_address_book();
                     self.encrypted_keys = get_encrypted_keys(&path).unwrap_or_default();
 
                     if self.selected_key_index.is_none() {
                         self.selected_key_index = self.auto_detect_key();
                     }
 
+                    // Los valores del archivo anterior no deben quedar en memoria
+                    self.wipe_secrets();
                     let key = self
                         .selected_key_index
                         .and_then(|i| self.age_keys.get(i).map(|k| k.key.as_str()));
                     match decrypt_and_parse(&path, key) {
                         Ok(secrets) => {
-                            self.secrets = secrets;
+                            self.secrets = secrets.into();
+                            set_core_dumps(false);
+                            self.pending_rotations.clear();
+                            self.metadata = match load_metadata(&path) {
+                                Ok(metadata) => metadata,
+                                Err(e) => {
+                                    self.set_temp_message(format!("❌ Error: {}", e));
+                                    FileMetadata::default()
+                                }
+                            };
+                            self.audit(AuditAction::FileOpened, None);
                             self.input_mode = InputMode::Secrets;
                             if !self.secrets.is_empty() {
                                 self.table_state.select(Some(0));
@@ -847,32 +2402,44 @@ impl App {
                                 .age_keys
                                 .iter()
                                 .enumerate()
-                                .filter_map(|(i, k)| {
-                                    if let Some(pub_key) = &k.public_key {
-                                        if self.file_recipients.contains(pub_key) {
-                                            return Some(format!(
-                                                "#{} {}",
-                                                i + 1,
-                                                k.comment
-                                                    .as_ref()
-                                                    .unwrap_or(&self.i18n.t("unnamed").to_string())
-                                            ));
-                                        }
-                                    }
-                                    None
+                                .filter(|(_, k)| k.is_recipient_of(&self.file_recipients))
+                                .map(|(i, k)| {
+                                    format!(
+                                        "#{} {}",
+                                        i + 1,
+                                        k.comment
+                                            .as_ref()
+                                            .unwrap_or(&self.i18n.t("unnamed").to_string())
+                                    )
                                 })
                                 .collect();
 
                             let file_name =
                                 path.file_name().and_then(|n| n.to_str()).unwrap_or("?");
-                            let msg = if matching_keys.is_empty() {
+                            let missing_plugin = self
+                                .selected_key_index
+                                .and_then(|i| self.age_keys.get(i))
+                                .and_then(|k| k.plugin())
+                                .filter(|name| !plugin_available(name));
+                            let msg = if let Some(name) = missing_plugin {
+                                format!(
+                                    "❌ {}: {}: age-plugin-{}",
+                                    file_name,
+                                    self.i18n.t("plugin_missing"),
+                                    name
+                                )
+                            } else if matching_keys.is_empty() {
                                 format!(
                                     "{} {}: {}. {}: {}",
                                     self.i18n.t("error_no_key_match"),
                                     file_name,
                                     self.i18n.t("error_no_key_match"),
                                     self.i18n.t("recipients"),
-                                    self.file_recipients.join(", ")
+                                    self.file_recipients
+                                        .iter()
+                                        .map(|r| self.recipient_label(r))
+                                        .collect::<Vec<_>>()
+                                        .join(", ")
                                 )
                             } else {
                                 format!(
@@ -954,7 +2521,7 @@ impl App {
             if let Some(&real_idx) = filtered.get(idx) {
                 if let Some((key, value)) = self.secrets.get(real_idx) {
                     self.editing_key_buffer = key.clone();
-                    self.editing_value_buffer = value.clone();
+                    self.editing_value_buffer = value.clone().into();
                     self.editing_field = 0;
                     self.cursor_position = self.editing_key_buffer.len();
                     self.input_mode = InputMode::Editing;
@@ -986,7 +2553,9 @@ impl App {
         if let Some(idx) = self.table_state.selected() {
             let filtered = self.filtered_secrets();
             if let Some(&real_idx) = filtered.get(idx) {
-                self.secrets.remove(real_idx);
+                let key = self.secrets.remove(real_idx);
+                self.metadata.remove(&key);
+                self.audit(AuditAction::KeyDeleted, Some(&key));
                 self.is_modified = true;
                 if self.table_state.selected().unwrap_or(0) >= self.secrets.len()
                     && !self.secrets.is_empty()
@@ -1027,242 +2596,310 @@ impl App {
     }
 
     pub fn save_changes(&mut self) -> Result<()> {
-        if let Some(file_path) = &self.file_path {
-            let backup_file = file_path.with_extension("bak");
-            fs::copy(file_path, &backup_file)?;
-
-            let ext = file_path.extension().and_then(|s| s.to_str());
-            let file_name = file_path.file_name().and_then(|s| s.to_str()).unwrap_or("");
-
-            // Detectar formato por nombre de archivo o extensión
-            let format = if file_name == ".env" || ext == Some("env") {
-                "env"
-            } else if file_name == ".ini" || ext == Some("ini") {
-                "ini"
-            } else if ext == Some("yaml") || ext == Some("yml") {
-                "yaml"
-            } else {
-                "json" // default for json and unknown
-            };
+        if let Some(file_path) = self.file_path.clone() {
+            self.write_encrypted(&file_path, &self.secrets)?;
+            let metadata_result = save_metadata(&file_path, &self.metadata);
+
+            self.is_modified = false;
+            self.pending_rotations.clear();
+            match metadata_result {
+                Ok(()) => self.set_temp_message(self.i18n.t("saved").to_string()),
+                Err(e) => self.set_temp_message(format!("❌ Error: {}", e)),
+            }
+            self.audit(AuditAction::FileSaved, None);
 
-            // Para ENV e INI, convertir a JSON para SOPS y luego convertir de vuelta
-            let content = if format == "env" {
-                // Crear JSON para que SOPS lo maneje correctamente
-                let mut json_obj = serde_json::Map::new();
-                for (k, v) in &self.secrets {
-                    // Entrecomillar valores con caracteres especiales
-                    let quoted_value = Self::quote_env_value(v);
-                    json_obj.insert(k.clone(), Value::String(quoted_value));
-                }
-                let json_value = Value::Object(json_obj);
-                serde_json::to_string_pretty(&json_value)?
-            } else if format == "ini" {
-                // Para INI, crear estructura con sección DEFAULT
-                let mut json_obj = serde_json::Map::new();
-                let mut default_section = serde_json::Map::new();
-                for (k, v) in &self.secrets {
-                    let clean_key = k.split('.').next_back().unwrap_or(k);
-                    // Entrecomillar valores con caracteres especiales
-                    let quoted_value = Self::quote_env_value(v);
-                    default_section.insert(clean_key.to_string(), Value::String(quoted_value));
-                }
-                json_obj.insert("DEFAULT".to_string(), Value::Object(default_section));
-                let json_value = Value::Object(json_obj);
-                serde_json::to_string_pretty(&json_value)?
-            } else if format == "yaml" {
-                // Para YAML/JSON, generar JSON estructurado
-                let mut json_obj = serde_json::Map::new();
-
-                for (key, value) in &self.secrets {
-                    let keys: Vec<&str> = key.split('.').collect();
-                    let mut current_map = &mut json_obj;
-                    for (i, k) in keys.iter().enumerate() {
-                        if i == keys.len() - 1 {
-                            // Si el valor está entre comillas, forzar como string
-                            let json_value = if value.len() >= 2
-                                && ((value.starts_with('"') && value.ends_with('"'))
-                                    || (value.starts_with('\'') && value.ends_with('\'')))
-                            {
-                                // Quitar las comillas y guardar como string
-                                let unquoted = &value[1..value.len() - 1];
-                                Value::String(unquoted.to_string())
-                            } else if value == "true" || value == "false" {
-                                Value::Bool(value == "true")
-                            } else if let Ok(num) = value.parse::<i64>() {
-                                Value::Number(num.into())
-                            } else if let Ok(num) = value.parse::<f64>() {
-                                serde_json::Number::from_f64(num)
-                                    .map(Value::Number)
-                                    .unwrap_or_else(|| Value::String(value.clone()))
-                            } else if value == "null" {
-                                Value::Null
-                            } else {
-                                Value::String(value.clone())
-                            };
-                            current_map.insert(k.to_string(), json_value);
+            // Recargar el archivo para actualizar encrypted_keys
+            self.encrypted_keys = get_encrypted_keys(&file_path).unwrap_or_default();
+            // SOPS cifra de nuevo según la regla, así que los recipients pueden cambiar
+            self.refresh_file_rule();
+        }
+        Ok(())
+    }
+
+    /// Recipients del archivo abierto y la regla del `.sops.yaml` que le corresponde
+    pub fn refresh_file_rule(&mut self) {
+        let Some(path) = self.file_path.clone() else {
+            return;
+        };
+        self.file_recipients = get_sops_recipients(&path).unwrap_or_default();
+        self.file_rule = Resolution::resolve(&path).unwrap_or_else(|e| {
+            self.set_temp_message(format!("❌ Error: {:#}", e));
+            None
+        });
+    }
+
+    /// Diferencia entre los recipients del archivo abierto y los de su regla
+    pub fn file_drift(&self) -> Option<Drift> {
+        self.file_rule.as_ref()?.drift(&self.file_recipients)
+    }
+
+    /// Re-cifra la llave de datos del archivo para los recipients de su regla (`sops updatekeys`)
+    pub fn rekey_to_rule(&mut self) -> Result<()> {
+        let (Some(path), Some(resolution)) = (self.file_path.clone(), self.file_rule.clone())
+        else {
+            return Ok(());
+        };
+        if self.file_drift().is_none() {
+            self.set_temp_message(self.i18n.t("no_recipient_drift").to_string());
+            return Ok(());
+        }
+        if self.is_modified {
+            anyhow::bail!("{}", self.i18n.t("save_before_rekey"));
+        }
+
+        let mut cmd = Command::new("sops");
+        cmd.arg("--config")
+            .arg(&resolution.config_path)
+            .arg("updatekeys")
+            .arg("--yes")
+            .arg(&path);
+        self.set_selected_identity(&mut cmd);
+        let output = cmd.output().context("No se pudo ejecutar sops")?;
+        if !output.status.success() {
+            anyhow::bail!(
+                "{}: {}",
+                self.i18n.t("error_rekey"),
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+
+        self.audit_file(AuditAction::RecipientsChanged, &path, None);
+        self.refresh_file_rule();
+        self.set_temp_message(format!(
+            "✓ {} {}",
+            self.i18n.t("rekeyed_to_rule"),
+            resolution.rule.unwrap_or_default() + 1
+        ));
+        Ok(())
+    }
+
+    /// Identidad con la que SOPS descifra: la seleccionada o el archivo de llaves
+    fn set_selected_identity(&self, cmd: &mut Command) {
+        let key = self
+            .selected_key_index
+            .and_then(|i| self.age_keys.get(i).map(|k| k.key.as_str()));
+        if let Some(k) = key {
+            set_sops_identity(cmd, k);
+        } else if let Ok(age_key_file) = age_keys_path() {
+            // Asegurar que SOPS encuentre las llaves age
+            cmd.env("SOPS_AGE_KEY_FILE", age_key_file);
+        }
+    }
+
+    /// Cifra `secrets` en `file_path` con SOPS, restaurando el archivo original si falla
+    fn write_encrypted(&self, file_path: &Path, secrets: &[(String, String)]) -> Result<()> {
+        let backup_file = save_backup_path(file_path);
+        fs::copy(file_path, &backup_file)?;
+
+        let ext = file_path.extension().and_then(|s| s.to_str());
+        let file_name = file_path.file_name().and_then(|s| s.to_str()).unwrap_or("");
+
+        // Detectar formato por nombre de archivo o extensión
+        let format = if file_name == ".env" || ext == Some("env") {
+            "env"
+        } else if file_name == ".ini" || ext == Some("ini") {
+            "ini"
+        } else if ext == Some("yaml") || ext == Some("yml") {
+            "yaml"
+        } else {
+            "json" // default for json and unknown
+        };
+
+        // Para ENV e INI, convertir a JSON para SOPS y luego convertir de vuelta
+        let content = if format == "env" {
+            // Crear JSON para que SOPS lo maneje correctamente
+            let mut json_obj = serde_json::Map::new();
+            for (k, v) in secrets {
+                // Entrecomillar valores con caracteres especiales
+                let quoted_value = Self::quote_env_value(v);
+                json_obj.insert(k.clone(), Value::String(quoted_value));
+            }
+            let json_value = Value::Object(json_obj);
+            serde_json::to_string_pretty(&json_value)?
+        } else if format == "ini" {
+            // Para INI, crear estructura con sección DEFAULT
+            let mut json_obj = serde_json::Map::new();
+            let mut default_section = serde_json::Map::new();
+            for (k, v) in secrets {
+                let clean_key = k.split('.').next_back().unwrap_or(k);
+                // Entrecomillar valores con caracteres especiales
+                let quoted_value = Self::quote_env_value(v);
+                default_section.insert(clean_key.to_string(), Value::String(quoted_value));
+            }
+            json_obj.insert("DEFAULT".to_string(), Value::Object(default_section));
+            let json_value = Value::Object(json_obj);
+            serde_json::to_string_pretty(&json_value)?
+        } else if format == "yaml" {
+            // Para YAML/JSON, generar JSON estructurado
+            let mut json_obj = serde_json::Map::new();
+
+            for (key, value) in secrets {
+                let keys: Vec<&str> = key.split('.').collect();
+                let mut current_map = &mut json_obj;
+                for (i, k) in keys.iter().enumerate() {
+                    if i == keys.len() - 1 {
+                        // Si el valor está entre comillas, forzar como string
+                        let json_value = if value.len() >= 2
+                            && ((value.starts_with('"') && value.ends_with('"'))
+                                || (value.starts_with('\'') && value.ends_with('\'')))
+                        {
+                            // Quitar las comillas y guardar como string
+                            let unquoted = &value[1..value.len() - 1];
+                            Value::String(unquoted.to_string())
+                        } else if value == "true" || value == "false" {
+                            Value::Bool(value == "true")
+                        } else if let Ok(num) = value.parse::<i64>() {
+                            Value::Number(num.into())
+                        } else if let Ok(num) = value.parse::<f64>() {
+                            serde_json::Number::from_f64(num)
+                                .map(Value::Number)
+                                .unwrap_or_else(|| Value::String(value.clone()))
+                        } else if value == "null" {
+                            Value::Null
                         } else {
-                            if !current_map.contains_key(*k) {
-                                current_map
-                                    .insert(k.to_string(), Value::Object(serde_json::Map::new()));
-                            }
-                            let next_map = current_map
-                                .get_mut(*k)
-                                .and_then(|v| v.as_object_mut())
-                                .context("Expected object in nested structure")?;
-                            current_map = next_map;
+                            Value::String(value.clone())
+                        };
+                        current_map.insert(k.to_string(), json_value);
+                    } else {
+                        if !current_map.contains_key(*k) {
+                            current_map
+                                .insert(k.to_string(), Value::Object(serde_json::Map::new()));
                         }
+                        let next_map = current_map
+                            .get_mut(*k)
+                            .and_then(|v| v.as_object_mut())
+                            .context("Expected object in nested structure")?;
+                        current_map = next_map;
                     }
                 }
+            }
 
-                let json_value = Value::Object(json_obj);
-                serde_json::to_string_pretty(&json_value)?
-            } else {
-                // Para JSON, generar JSON estructurado con detección de tipos
-                let mut json_obj = serde_json::Map::new();
-
-                for (key, value) in &self.secrets {
-                    let keys: Vec<&str> = key.split('.').collect();
-                    let mut current_map = &mut json_obj;
-                    for (i, k) in keys.iter().enumerate() {
-                        if i == keys.len() - 1 {
-                            // Si el valor está entre comillas, forzar como string
-                            let json_value = if value.len() >= 2
-                                && ((value.starts_with('"') && value.ends_with('"'))
-                                    || (value.starts_with('\'') && value.ends_with('\'')))
-                            {
-                                // Quitar las comillas y guardar como string
-                                let unquoted = &value[1..value.len() - 1];
-                                Value::String(unquoted.to_string())
-                            } else if value == "true" || value == "false" {
-                                Value::Bool(value == "true")
-                            } else if let Ok(num) = value.parse::<i64>() {
-                                Value::Number(num.into())
-                            } else if let Ok(num) = value.parse::<f64>() {
-                                serde_json::Number::from_f64(num)
-                                    .map(Value::Number)
-                                    .unwrap_or_else(|| Value::String(value.clone()))
-                            } else if value == "null" {
-                                Value::Null
-                            } else {
-                                Value::String(value.clone())
-                            };
-                            current_map.insert(k.to_string(), json_value);
+            let json_value = Value::Object(json_obj);
+            serde_json::to_string_pretty(&json_value)?
+        } else {
+            // Para JSON, generar JSON estructurado con detección de tipos
+            let mut json_obj = serde_json::Map::new();
+
+            for (key, value) in secrets {
+                let keys: Vec<&str> = key.split('.').collect();
+                let mut current_map = &mut json_obj;
+                for (i, k) in keys.iter().enumerate() {
+                    if i == keys.len() - 1 {
+                        // Si el valor está entre comillas, forzar como string
+                        let json_value = if value.len() >= 2
+                            && ((value.starts_with('"') && value.ends_with('"'))
+                                || (value.starts_with('\'') && value.ends_with('\'')))
+                        {
+                            // Quitar las comillas y guardar como string
+                            let unquoted = &value[1..value.len() - 1];
+                            Value::String(unquoted.to_string())
+                        } else if value == "true" || value == "false" {
+                            Value::Bool(value == "true")
+                        } else if let Ok(num) = value.parse::<i64>() {
+                            Value::Number(num.into())
+                        } else if let Ok(num) = value.parse::<f64>() {
+                            serde_json::Number::from_f64(num)
+                                .map(Value::Number)
+                                .unwrap_or_else(|| Value::String(value.clone()))
+                        } else if value == "null" {
+                            Value::Null
                         } else {
-                            if !current_map.contains_key(*k) {
-                                current_map
-                                    .insert(k.to_string(), Value::Object(serde_json::Map::new()));
-                            }
-                            let next_map = current_map
-                                .get_mut(*k)
-                                .and_then(|v| v.as_object_mut())
-                                .context("Expected object in nested structure")?;
-                            current_map = next_map;
+                            Value::String(value.clone())
+                        };
+                        current_map.insert(k.to_string(), json_value);
+                    } else {
+                        if !current_map.contains_key(*k) {
+                            current_map
+                                .insert(k.to_string(), Value::Object(serde_json::Map::new()));
                         }
+                        let next_map = current_map
+                            .get_mut(*k)
+                            .and_then(|v| v.as_object_mut())
+                            .context("Expected object in nested structure")?;
+                        current_map = next_map;
                     }
                 }
+            }
 
-                let json_value = Value::Object(json_obj);
-                serde_json::to_string_pretty(&json_value)?
-            };
+            let json_value = Value::Object(json_obj);
+            serde_json::to_string_pretty(&json_value)?
+        };
 
-            fs::write(file_path, content)?;
+        fs::write(file_path, content)?;
 
-            let key = self
-                .selected_key_index
-                .and_then(|i| self.age_keys.get(i).map(|k| k.key.as_str()));
-            let mut sops_dir = file_path.parent();
-            while let Some(dir) = sops_dir {
-                if dir.join(".sops.yaml").exists() {
-                    break;
-                }
-                sops_dir = dir.parent();
+        let mut sops_dir = file_path.parent();
+        while let Some(dir) = sops_dir {
+            if dir.join(".sops.yaml").exists() {
+                break;
             }
+            sops_dir = dir.parent();
+        }
 
-            let work_dir = sops_dir.unwrap_or_else(|| file_path.parent().unwrap_or(Path::new(".")));
+        let work_dir = sops_dir.unwrap_or_else(|| file_path.parent().unwrap_or(Path::new(".")));
 
-            let mut cmd = Command::new("sops");
-            cmd.arg("--encrypt").arg("-i"); // In-place para mantener recipients
+        let mut cmd = Command::new("sops");
+        cmd.arg("--encrypt").arg("-i"); // In-place para mantener recipients
 
-            // Para ENV e INI, usar JSON como formato intermedio
-            match format {
-                "yaml" => {
-                    cmd.arg("--input-type")
-                        .arg("json")
-                        .arg("--output-type")
-                        .arg("yaml");
-                }
-                "env" => {
-                    cmd.arg("--input-type")
-                        .arg("json")
-                        .arg("--output-type")
-                        .arg("dotenv");
-                }
-                "ini" => {
-                    cmd.arg("--input-type")
-                        .arg("json")
-                        .arg("--output-type")
-                        .arg("ini");
-                }
-                _ => {
-                    cmd.arg("--input-type")
-                        .arg("json")
-                        .arg("--output-type")
-                        .arg("json");
-                }
-            };
+        // Para ENV e INI, usar JSON como formato intermedio
+        match format {
+            "yaml" => {
+                cmd.arg("--input-type")
+                    .arg("json")
+                    .arg("--output-type")
+                    .arg("yaml");
+            }
+            "env" => {
+                cmd.arg("--input-type")
+                    .arg("json")
+                    .arg("--output-type")
+                    .arg("dotenv");
+            }
+            "ini" => {
+                cmd.arg("--input-type")
+                    .arg("json")
+                    .arg("--output-type")
+                    .arg("ini");
+            }
+            _ => {
+                cmd.arg("--input-type")
+                    .arg("json")
+                    .arg("--output-type")
+                    .arg("json");
+            }
+        };
 
-            cmd.arg(file_path).current_dir(work_dir);
+        cmd.arg(file_path).current_dir(work_dir);
+        self.set_selected_identity(&mut cmd);
 
-            if let Some(k) = key {
-                cmd.env("SOPS_AGE_KEY", k);
-            } else {
-                // Asegurar que SOPS encuentre las llaves age
-                let age_key_file = std::env::var("SOPS_AGE_KEY_FILE").unwrap_or_else(|_| {
-                    let home = std::env::var("HOME").unwrap_or_else(|_| ".".to_string());
-                    format!("{}/.config/sops/age/keys.txt", home)
-                });
-                cmd.env("SOPS_AGE_KEY_FILE", age_key_file);
-            }
-
-            let output = cmd.output()?;
-
-            if output.status.success() {
-                // Con -i, SOPS modifica el archivo directamente
-                fs::remove_file(&backup_file).ok();
-
-                if let Some(parent) = file_path.parent() {
-                    if let Ok(entries) = fs::read_dir(parent) {
-                        for entry in entries.flatten() {
-                            let path = entry.path();
-                            if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
-                                if name.contains(".tmp")
-                                    && name
-                                        .contains(file_path.file_name().unwrap().to_str().unwrap())
-                                {
-                                    fs::remove_file(&path).ok();
-                                }
+        let output = cmd.output()?;
+
+        if output.status.success() {
+            // Con -i, SOPS modifica el archivo directamente
+            fs::remove_file(&backup_file).ok();
+
+            if let Some(parent) = file_path.parent() {
+                if let Ok(entries) = fs::read_dir(parent) {
+                    for entry in entries.flatten() {
+                        let path = entry.path();
+                        if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
+                            if name.contains(".tmp")
+                                && name.contains(file_path.file_name().unwrap().to_str().unwrap())
+                            {
+                                fs::remove_file(&path).ok();
                             }
                         }
                     }
                 }
-
-                self.is_modified = false;
-                self.set_temp_message(self.i18n.t("saved").to_string());
-
-                // Recargar el archivo para actualizar encrypted_keys
-                if let Some(file_path) = &self.file_path.clone() {
-                    self.encrypted_keys = get_encrypted_keys(file_path).unwrap_or_default();
-                }
-            } else {
-                fs::copy(&backup_file, file_path)?;
-                fs::remove_file(&backup_file).ok();
-                anyhow::bail!(
-                    "{}: {}",
-                    self.i18n.t("error_encrypt"),
-                    String::from_utf8_lossy(&output.stderr)
-                );
             }
+            Ok(())
+        } else {
+            fs::copy(&backup_file, file_path)?;
+            fs::remove_file(&backup_file).ok();
+            anyhow::bail!(
+                "{}: {}",
+                self.i18n.t("error_encrypt"),
+                String::from_utf8_lossy(&output.stderr)
+            );
         }
-        Ok(())
     }
 }
@@ -1,11 +1,17 @@
 use crate::config::Theme;
+use crate::generator::{estimate_entropy, Strength, TokenFormat};
+use crate::git::KeyChange;
 use crate::help::show_help;
-use crate::state::{App, InputMode};
+use crate::keystore::{display_relative, parse_identities};
+use crate::metadata::ExpiryStatus;
+use crate::sops::{plugin_available, KeySource};
+use crate::sopsconfig::{SopsTemplate, RULE_FIELDS};
+use crate::state::{App, InputMode, KeyTransfer, FILE_FORMATS};
 use ratatui::{
     layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, Clear, List, ListItem, Paragraph, Row, Table},
+    widgets::{Block, Borders, Cell, Clear, List, ListItem, Paragraph, Row, Table, Wrap},
     Frame,
 };
 
@@ -87,7 +93,14 @@ pub fn ui(f: &mut Frame, app: &mut App) {
 
     let footer_text = get_footer_text(app);
 
-    if app.input_mode == InputMode::Settings {
+    // Bloqueada: no se dibuja nada del contenido (ni siquiera los nombres de archivo)
+    if app.input_mode == InputMode::Locked {
+        render_lock_screen(f, app, main_layout[1]);
+        render_footer(f, app, main_layout[2], &footer_text);
+        return;
+    }
+
+    if app.input_mode == InputMode::Settings || app.input_mode == InputMode::SettingLockPassphrase {
         render_settings_modal(f, app, main_layout[1]);
     } else {
         let content_chunks = Layout::default()
@@ -168,6 +181,45 @@ pub fn ui(f: &mut Frame, app: &mut App) {
     if app.input_mode == InputMode::SelectingSopsTemplate {
         render_selecting_sops_template_modal(f, app);
     }
+
+    if app.input_mode == InputMode::ViewingHistory {
+        render_history_modal(f, app);
+    }
+
+    if app.input_mode == InputMode::ViewingAudit {
+        render_audit_modal(f, app);
+    }
+
+    if app.input_mode == InputMode::EditingMetadata {
+        render_metadata_modal(f, app);
+    }
+
+    if app.input_mode == InputMode::Generating {
+        render_generator_modal(f, app);
+    }
+
+    if app.input_mode == InputMode::UnlockingIdentities {
+        render_unlocking_identities_modal(f, app);
+    }
+
+    if app.input_mode == InputMode::FixingPermissions {
+        render_fixing_permissions_modal(f, app);
+    }
+
+    if app.input_mode == InputMode::ViewingKeyUsage {
+        render_key_usage_modal(f, app);
+    }
+
+    if matches!(
+        app.input_mode,
+        InputMode::ImportingKeys
+            | InputMode::ExportingKey
+            | InputMode::EditingKeyComment
+            | InputMode::BackingUpKeys
+            | InputMode::EnteringKeyPassphrase
+    ) {
+        render_key_io_modal(f, app);
+    }
 }
 
 fn render_settings_modal(f: &mut Frame, app: &App, area: Rect) {
@@ -183,6 +235,7 @@ fn render_settings_modal(f: &mut Frame, app: &App, area: Rect) {
             Constraint::Length(3),
             Constraint::Length(3),
             Constraint::Length(3),
+            Constraint::Length(3),
             Constraint::Min(0),
         ])
         .split(area);
@@ -336,6 +389,50 @@ fn render_settings_modal(f: &mut Frame, app: &App, area: Rect) {
     let msg = Paragraph::new(msg_text).style(msg_style);
     f.render_widget(msg, chunks[3]);
 
+    // Lock passphrase
+    let passphrase_style = if app.settings_selected == 4 {
+        Style::default()
+            .fg(Color::Rgb(
+                theme.warning.0,
+                theme.warning.1,
+                theme.warning.2,
+            ))
+            .add_modifier(Modifier::BOLD)
+    } else {
+        Style::default().fg(Color::Rgb(theme.fg.0, theme.fg.1, theme.fg.2))
+    };
+    let passphrase_value = if app.input_mode == InputMode::SettingLockPassphrase {
+        format!("{}█", "•".repeat(app.lock_buffer.chars().count()))
+    } else if app.config.lock_passphrase_hash.is_some() {
+        format!(
+            "{} {}",
+            app.i18n.t("settings_configured"),
+            app.i18n.t("settings_enter_change")
+        )
+    } else {
+        format!(
+            "{} {}",
+            app.i18n.t("settings_not_configured"),
+            app.i18n.t("settings_enter_change")
+        )
+    };
+    let passphrase_label = if app.lock_passphrase_first.is_some() {
+        app.i18n.t("settings_lock_passphrase_repeat")
+    } else {
+        app.i18n.t("settings_lock_passphrase")
+    };
+    let passphrase_text = vec![
+        Line::from(format!("{}: {}", passphrase_label, passphrase_value)),
+        Line::from(Span::styled(
+            format!("  {}", app.i18n.t("settings_lock_passphrase_help")),
+            Style::default()
+                .fg(Color::Rgb(theme.fg.0 / 2, theme.fg.1 / 2, theme.fg.2 / 2))
+                .add_modifier(Modifier::ITALIC),
+        )),
+    ];
+    let passphrase = Paragraph::new(passphrase_text).style(passphrase_style);
+    f.render_widget(passphrase, chunks[4]);
+
     // Help text
     let help_text = vec![
         Line::from(""),
@@ -360,10 +457,7 @@ fn render_settings_modal(f: &mut Frame, app: &App, area: Rect) {
                     theme.success.2,
                 )),
             ),
-            Span::raw(format!(
-                "{}  ",
-                app.i18n.t("change")
-            )),
+            Span::raw(format!("{}  ", app.i18n.t("change"))),
             Span::styled(
                 "[s] ",
                 Style::default().fg(Color::Rgb(
@@ -387,7 +481,68 @@ fn render_settings_modal(f: &mut Frame, app: &App, area: Rect) {
             (theme.fg.2 as u16 * 3 / 5) as u8,
         )))
         .alignment(Alignment::Center);
-    f.render_widget(help, chunks[4]);
+    f.render_widget(help, chunks[5]);
+}
+
+/// Pantalla de bloqueo: reemplaza todo el contenido hasta volver a autenticarse
+fn render_lock_screen(f: &mut Frame, app: &App, area: Rect) {
+    let theme = &app.theme;
+    let area = centered_rect(60, 40, area);
+    f.render_widget(Clear, area);
+
+    let mut methods = Vec::new();
+    if app.config.lock_passphrase_hash.is_some() {
+        methods.push(app.i18n.t("lock_with_passphrase"));
+    }
+    if app.lock_challenge.is_some() {
+        methods.push(app.i18n.t("lock_with_identity"));
+    }
+
+    let text = vec![
+        Line::from(""),
+        Line::from(Span::styled(
+            app.i18n.t("lock_prompt"),
+            Style::default().fg(Color::Rgb(theme.fg.0, theme.fg.1, theme.fg.2)),
+        )),
+        Line::from(Span::styled(
+            methods.join(" / "),
+            Style::default()
+                .fg(Color::Rgb(theme.fg.0 / 2, theme.fg.1 / 2, theme.fg.2 / 2))
+                .add_modifier(Modifier::ITALIC),
+        )),
+        Line::from(""),
+        Line::from(Span::styled(
+            format!("> {}█", "•".repeat(app.lock_buffer.chars().count().min(40))),
+            Style::default()
+                .fg(Color::Rgb(
+                    theme.warning.0,
+                    theme.warning.1,
+                    theme.warning.2,
+                ))
+                .add_modifier(Modifier::BOLD),
+        )),
+    ];
+
+    let paragraph = Paragraph::new(text)
+        .alignment(Alignment::Center)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(app.i18n.t("lock_title"))
+                .title_style(
+                    Style::default()
+                        .fg(Color::Rgb(theme.error.0, theme.error.1, theme.error.2))
+                        .add_modifier(Modifier::BOLD),
+                )
+                .border_style(Style::default().fg(Color::Rgb(
+                    theme.error.0,
+                    theme.error.1,
+                    theme.error.2,
+                ))),
+        )
+        .style(Style::default().bg(Color::Rgb(theme.bg.0, theme.bg.1, theme.bg.2)));
+
+    f.render_widget(paragraph, area);
 }
 
 fn render_file_explorer(f: &mut Frame, app: &mut App, area: Rect) {
@@ -536,7 +691,7 @@ fn render_secrets_panel(f: &mut Frame, app: &mut App, area: Rect) {
         ));
     }
 
-    let header = Row::new(vec!["🔑 Key", "🔐 Value"]).style(
+    let header = Row::new(vec!["🔑 Key", "🔐 Value", "⏳ Due"]).style(
         Style::default()
             .fg(Color::Rgb(171, 71, 188))
             .add_modifier(Modifier::BOLD),
@@ -556,7 +711,31 @@ fn render_secrets_panel(f: &mut Frame, app: &mut App, area: Rect) {
             } else {
                 v.clone()
             };
-            Row::new(vec![k.clone(), display_value])
+            let due = app
+                .metadata
+                .get(k)
+                .and_then(|m| m.due_date())
+                .map(|d| d.to_string())
+                .unwrap_or_default();
+            // Las rotaciones sin guardar se marcan para poder deshacerlas con [u]
+            let key_label = if app.pending_rotations.contains_key(k) {
+                format!("↺ {}", k)
+            } else {
+                k.clone()
+            };
+            let row = Row::new(vec![key_label, display_value, due]);
+            // Resaltar secretos vencidos o próximos a vencer
+            match app.expiry_status(k) {
+                ExpiryStatus::Expired => {
+                    let c = app.theme.error;
+                    row.style(Style::default().fg(Color::Rgb(c.0, c.1, c.2)))
+                }
+                ExpiryStatus::ExpiringSoon => {
+                    let c = app.theme.warning;
+                    row.style(Style::default().fg(Color::Rgb(c.0, c.1, c.2)))
+                }
+                ExpiryStatus::Ok => row,
+            }
         })
         .collect();
 
@@ -584,13 +763,19 @@ fn render_secrets_panel(f: &mut Frame, app: &mut App, area: Rect) {
 
     let table = Table::new(
         rows,
-        [Constraint::Percentage(40), Constraint::Percentage(60)],
+        [
+            Constraint::Percentage(35),
+            Constraint::Percentage(50),
+            Constraint::Percentage(15),
+        ],
     )
     .header(header)
     .block(
         Block::default()
             .borders(Borders::ALL)
             .title(title)
+            .title_bottom(recipients_title(app))
+            .title_bottom(rule_title(app))
             .title_style(
                 Style::default()
                     .fg(Color::Rgb(129, 212, 250))
@@ -617,6 +802,59 @@ fn render_secrets_panel(f: &mut Frame, app: &mut App, area: Rect) {
     f.render_stateful_widget(table, chunks[1], &mut app.table_state);
 }
 
+/// Recipients del archivo abierto, con nombre si están en la libreta
+fn recipients_title(app: &App) -> String {
+    if app.file_path.is_none() || app.file_recipients.is_empty() {
+        return String::new();
+    }
+    let labels: Vec<String> = app
+        .file_recipients
+        .iter()
+        .map(|r| app.recipient_label(r))
+        .collect();
+    format!(" 👥 {} ", labels.join(", "))
+}
+
+/// `.sops.yaml` y regla del archivo abierto; avisa si sus recipients no coinciden con la regla
+fn rule_title(app: &App) -> Line<'static> {
+    let Some(resolution) = app.file_path.as_ref().and(app.file_rule.as_ref()) else {
+        return Line::default();
+    };
+    let config = resolution
+        .config_path
+        .strip_prefix(&app.current_dir)
+        .unwrap_or(&resolution.config_path)
+        .display();
+    let rule = match (resolution.rule, resolution.rule()) {
+        (Some(idx), Some(rule)) => format!(
+            "{} {} ({})",
+            app.i18n.t("sops_rule"),
+            idx + 1,
+            rule.path_regex
+                .as_deref()
+                .unwrap_or(app.i18n.t("sops_any_file"))
+        ),
+        _ => app.i18n.t("sops_no_rule").to_string(),
+    };
+
+    let mut spans = vec![Span::raw(format!(" 📄 {} · {} ", config, rule))];
+    if let Some(drift) = app.file_drift() {
+        spans.push(Span::styled(
+            format!(
+                "⚠ {}: +{} −{} [y] {} ",
+                app.i18n.t("recipient_drift"),
+                drift.missing.len(),
+                drift.extra.len(),
+                app.i18n.t("cmd_rekey")
+            ),
+            Style::default()
+                .fg(Color::Rgb(255, 167, 38))
+                .add_modifier(Modifier::BOLD),
+        ));
+    }
+    Line::from(spans).right_aligned()
+}
+
 fn render_footer(f: &mut Frame, app: &App, area: Rect, footer_text: &str) {
     let footer_style = if app.error_message.is_some() {
         if app.error_message.as_ref().unwrap().starts_with("✓") {
@@ -718,10 +956,8 @@ fn render_key_selector_modal(f: &mut Frame, app: &mut App) {
                 prefix.push_str(&format!("{} ", app.i18n.t("active")));
             }
 
-            if let Some(pub_key) = &key.public_key {
-                if app.file_recipients.contains(pub_key) {
-                    prefix.push_str("✓ ");
-                }
+            if key.is_recipient_of(&app.file_recipients) {
+                prefix.push_str("✓ ");
             }
 
             ListItem::new(format!("{}{} | {}", prefix, label, pub_key_short))
@@ -750,6 +986,7 @@ fn render_key_selector_modal(f: &mut Frame, app: &mut App) {
             Block::default()
                 .borders(Borders::ALL)
                 .title(title)
+                .title_bottom(recipients_title(app))
                 .title_style(
                     Style::default()
                         .fg(Color::Rgb(171, 71, 188))
@@ -798,6 +1035,7 @@ fn render_value_viewer_modal(f: &mut Frame, app: &mut App) {
                         lines.len(),
                         app.i18n.t("value_help")
                     ))
+                    .title_bottom(strength_line(app, estimate_entropy(value)))
                     .title_style(
                         Style::default()
                             .fg(Color::Rgb(129, 212, 250))
@@ -887,6 +1125,11 @@ fn render_edit_modal(f: &mut Frame, app: &mut App) {
             Block::default()
                 .borders(Borders::ALL)
                 .title(value_title)
+                .title_bottom(if app.editing_value_buffer.is_empty() {
+                    Line::default()
+                } else {
+                    strength_line(app, estimate_entropy(&app.editing_value_buffer))
+                })
                 .title_style(
                     Style::default()
                         .fg(Color::Rgb(129, 212, 250))
@@ -939,10 +1182,7 @@ fn render_confirm_modal(f: &mut Frame, app: &App) {
                     .fg(Color::Rgb(239, 83, 80))
                     .add_modifier(Modifier::BOLD),
             ),
-            Span::raw(format!(
-                "{}     ",
-                app.i18n.t("confirm")
-            )),
+            Span::raw(format!("{}     ", app.i18n.t("confirm"))),
             Span::styled(
                 "[n] ",
                 Style::default()
@@ -1129,24 +1369,27 @@ fn get_footer_text(app: &App) -> String {
                     ""
                 };
                 format!(
-                    "{}: {} | {}: [v] {} | {} | {}{}\n{}: {} | {} | {}{}\n{}: {} | {} | {} | {} | {}{}",
+                    "{}: {} | {}: [v] {} | {} | {} | {}{}\n{}: {} | {} | {} | {}{}\n{}: {} | {} | {} | {} | {} | {}{}",
                     app.i18n.t("cat_navigation"),
                     app.i18n.t("cmd_navigate"),
                     app.i18n.t("cat_view"),
                     if app.show_values { app.i18n.t("cmd_hide") } else { app.i18n.t("cmd_show") },
                     app.i18n.t("cmd_zoom"),
+                    app.i18n.t("cmd_history"),
                     app.i18n.t("cmd_favorite"),
                     fav_indicator,
                     app.i18n.t("cat_editing"),
                     edit_cmd,
                     app.i18n.t("cmd_new"),
                     delete_cmd,
+                    app.i18n.t("cmd_metadata"),
                     edit_cmds,
                     app.i18n.t("cat_tools"),
                     app.i18n.t("cmd_copy"),
                     app.i18n.t("cmd_copy_key"),
                     app.i18n.t("cmd_search"),
                     app.i18n.t("cmd_generate"),
+                    app.i18n.t("cmd_rotate"),
                     app.i18n.t("cmd_key_selector"),
                     key_info
                 )
@@ -1196,7 +1439,7 @@ fn get_footer_text(app: &App) -> String {
             InputMode::Confirming => {
                 format!("{} | {}", app.i18n.t("confirm_y"), app.i18n.t("cancel_n"))
             }
-            InputMode::Generating => app.i18n.t("generating").to_string(),
+            InputMode::Generating => app.i18n.t("footer_generator").to_string(),
             InputMode::Help => format!(
                 "{} | [?] {}",
                 app.i18n.t("close_help"),
@@ -1223,13 +1466,19 @@ fn get_footer_text(app: &App) -> String {
                 let delete_cmd = if selected_key.is_empty() {
                     String::new()
                 } else {
-                    format!(" | {}{}", app.i18n.t("delete_key_cmd"), selected_key)
+                    format!(
+                        " | {} | {}{}",
+                        app.i18n.t("key_actions_cmd"),
+                        app.i18n.t("delete_key_cmd"),
+                        selected_key
+                    )
                 };
 
                 format!(
-                    "{}{} | {}",
+                    "{}{} | {} | {}",
                     app.i18n.t("footer_key_manager"),
                     delete_cmd,
+                    app.i18n.t("key_transfer_cmd"),
                     app.i18n.t("footer_close")
                 )
             }
@@ -1241,8 +1490,24 @@ fn get_footer_text(app: &App) -> String {
             InputMode::SelectingFileFormat => app.i18n.t("footer_select_format").to_string(),
             InputMode::CreatingSecretFile => app.i18n.t("footer_create_file").to_string(),
             InputMode::SelectingSopsKeys => app.i18n.t("footer_select_sops_keys").to_string(),
+            InputMode::EditingSopsConfig if app.sops_field_editing => {
+                app.i18n.t("footer_edit_sops_field").to_string()
+            }
             InputMode::EditingSopsConfig => app.i18n.t("footer_edit_sops").to_string(),
             InputMode::SelectingSopsTemplate => app.i18n.t("footer_select_template").to_string(),
+            InputMode::ViewingHistory => app.i18n.t("footer_history").to_string(),
+            InputMode::ViewingAudit => app.i18n.t("footer_audit").to_string(),
+            InputMode::EditingMetadata => app.i18n.t("footer_metadata").to_string(),
+            InputMode::Locked => app.i18n.t("footer_locked").to_string(),
+            InputMode::SettingLockPassphrase => app.i18n.t("footer_lock_passphrase").to_string(),
+            InputMode::UnlockingIdentities => app.i18n.t("footer_unlock_identities").to_string(),
+            InputMode::ImportingKeys
+            | InputMode::ExportingKey
+            | InputMode::EditingKeyComment
+            | InputMode::BackingUpKeys
+            | InputMode::EnteringKeyPassphrase => app.i18n.t("footer_key_io").to_string(),
+            InputMode::FixingPermissions => app.i18n.t("footer_fix_permissions").to_string(),
+            InputMode::ViewingKeyUsage => app.i18n.t("footer_key_usage").to_string(),
         }
     }
 }
@@ -1278,7 +1543,17 @@ fn render_key_manager_modal(f: &mut Frame, app: &App) {
         .iter()
         .enumerate()
         .map(|(i, key)| {
-            let name = key.comment.as_deref().unwrap_or(app.i18n.t("unnamed"));
+            let mut name = key
+                .comment
+                .as_deref()
+                .unwrap_or(app.i18n.t("unnamed"))
+                .to_string();
+            if let Some(plugin) = key.plugin() {
+                name = format!("🔌 {} {}", plugin, name);
+                if !plugin_available(&plugin) {
+                    name.push_str(&format!(" ⚠ {}", app.i18n.t("plugin_missing")));
+                }
+            }
             let pub_key = key.public_key.as_deref().unwrap_or(app.i18n.t("na"));
             let pub_key_short = if pub_key.len() > 20 {
                 format!("{}...", &pub_key[..20])
@@ -1298,7 +1573,13 @@ fn render_key_manager_modal(f: &mut Frame, app: &App) {
                 Style::default().fg(Color::Rgb(app.theme.fg.0, app.theme.fg.1, app.theme.fg.2))
             };
 
-            ListItem::new(format!("  {} | {}", name, pub_key_short)).style(style)
+            ListItem::new(format!(
+                "  {} | {} | {}",
+                name,
+                pub_key_short,
+                key.source.label()
+            ))
+            .style(style)
         })
         .collect();
 
@@ -1328,7 +1609,9 @@ fn render_key_manager_modal(f: &mut Frame, app: &App) {
 }
 
 fn render_confirm_key_deletion_modal(f: &mut Frame, app: &App) {
-    let area = centered_rect(50, 25, f.area());
+    let dependents = &app.key_deletion_dependents;
+    let height = if dependents.is_empty() { 30 } else { 60 };
+    let area = centered_rect(60, height, f.area());
     f.render_widget(Clear, area);
 
     let key_name = if app.key_manager_selected < app.age_keys.len() {
@@ -1340,7 +1623,7 @@ fn render_confirm_key_deletion_modal(f: &mut Frame, app: &App) {
         app.i18n.t("unknown")
     };
 
-    let text = vec![
+    let mut text = vec![
         Line::from(""),
         Line::from(Span::styled(
             format!("{} '{}'?", app.i18n.t("delete_key_question"), key_name),
@@ -1349,12 +1632,44 @@ fn render_confirm_key_deletion_modal(f: &mut Frame, app: &App) {
                 .add_modifier(Modifier::BOLD),
         )),
         Line::from(""),
+    ];
+
+    // Archivos que solo esta llave puede descifrar (árbol actual y favoritos)
+    if dependents.is_empty() {
+        text.push(Line::from(Span::styled(
+            app.i18n.t("no_dependent_files"),
+            Style::default().fg(Color::Rgb(102, 187, 106)),
+        )));
+    } else {
+        text.push(Line::from(Span::styled(
+            format!("⚠ {} {}", dependents.len(), app.i18n.t("dependent_files")),
+            Style::default()
+                .fg(Color::Rgb(239, 83, 80))
+                .add_modifier(Modifier::BOLD),
+        )));
+        for path in dependents.iter().take(8) {
+            let shown = path.strip_prefix(&app.current_dir).unwrap_or(path);
+            text.push(Line::from(Span::styled(
+                shown.display().to_string(),
+                Style::default().fg(Color::Rgb(255, 167, 38)),
+            )));
+        }
+        if dependents.len() > 8 {
+            text.push(Line::from(format!(
+                "… {} {}",
+                dependents.len() - 8,
+                app.i18n.t("more_files")
+            )));
+        }
+    }
+
+    text.extend([
+        Line::from(""),
         Line::from(Span::styled(
-            app.i18n.t("action_irreversible"),
-            Style::default().fg(Color::Rgb(239, 83, 80)),
+            app.i18n.t("key_backup_notice"),
+            Style::default().fg(Color::Rgb(176, 190, 197)),
         )),
         Line::from(""),
-        Line::from(""),
         Line::from(vec![
             Span::styled(
                 "[y] ",
@@ -1378,7 +1693,7 @@ fn render_confirm_key_deletion_modal(f: &mut Frame, app: &App) {
             ),
             Span::raw(app.i18n.t("cancel")),
         ]),
-    ];
+    ]);
 
     let confirm = Paragraph::new(text)
         .style(
@@ -1492,14 +1807,212 @@ fn render_creating_folder_modal(f: &mut Frame, app: &App) {
     f.render_widget(input, area);
 }
 
-fn render_renaming_file_modal(f: &mut Frame, app: &App) {
-    let area = centered_rect(50, 15, f.area());
+fn render_key_usage_modal(f: &mut Frame, app: &App) {
+    let area = centered_rect(80, 80, f.area());
+    f.render_widget(Clear, area);
+
+    let Some(report) = &app.usage_report else {
+        return;
+    };
+    let header = |text: String| {
+        Line::from(Span::styled(
+            text,
+            Style::default()
+                .fg(Color::Rgb(
+                    app.theme.primary.0,
+                    app.theme.primary.1,
+                    app.theme.primary.2,
+                ))
+                .add_modifier(Modifier::BOLD),
+        ))
+    };
+    let short = |recipient: &str| {
+        if recipient.is_empty() {
+            app.i18n.t("na").to_string()
+        } else if recipient.len() > 24 {
+            format!("{}...", &recipient[..24])
+        } else {
+            recipient.to_string()
+        }
+    };
+
+    let mut text = vec![
+        Line::from(format!(
+            "{} — {} {}",
+            report.root.display(),
+            report.total_files,
+            app.i18n.t("sops_files_found")
+        )),
+        Line::from(""),
+    ];
+
+    let sections = [
+        (
+            "🔑",
+            app.i18n.t("usage_local_identities"),
+            &report.identities,
+        ),
+        (
+            "👤",
+            app.i18n.t("usage_known_recipients"),
+            &report.recipients,
+        ),
+    ];
+    for (icon, title, usages) in sections {
+        if usages.is_empty() {
+            continue;
+        }
+        text.push(header(title.to_string()));
+        for usage in usages {
+            let name = usage.name.as_deref().unwrap_or(app.i18n.t("unnamed"));
+            let count = if usage.files.is_empty() {
+                Span::styled(
+                    app.i18n.t("usage_unused"),
+                    Style::default().fg(Color::Rgb(
+                        app.theme.warning.0,
+                        app.theme.warning.1,
+                        app.theme.warning.2,
+                    )),
+                )
+            } else {
+                Span::raw(format!("{} {}", usage.files.len(), app.i18n.t("files")))
+            };
+            text.push(Line::from(vec![
+                Span::styled(
+                    format!("{} {} · {} — ", icon, name, short(&usage.recipient)),
+                    Style::default().add_modifier(Modifier::BOLD),
+                ),
+                count,
+            ]));
+            for path in &usage.files {
+                text.push(Line::from(format!(
+                    "    {}",
+                    display_relative(&report.root, path)
+                )));
+            }
+        }
+        text.push(Line::from(""));
+    }
+
+    if report.orphaned.is_empty() {
+        text.push(Line::from(Span::styled(
+            app.i18n.t("usage_no_orphans"),
+            Style::default().fg(Color::Rgb(
+                app.theme.success.0,
+                app.theme.success.1,
+                app.theme.success.2,
+            )),
+        )));
+    } else {
+        text.push(Line::from(Span::styled(
+            format!(
+                "⚠ {} ({})",
+                app.i18n.t("usage_orphaned"),
+                report.orphaned.len()
+            ),
+            Style::default()
+                .fg(Color::Rgb(239, 83, 80))
+                .add_modifier(Modifier::BOLD),
+        )));
+        for path in &report.orphaned {
+            text.push(Line::from(format!(
+                "    {}",
+                display_relative(&report.root, path)
+            )));
+        }
+    }
+
+    let modal = Paragraph::new(text)
+        .style(
+            Style::default()
+                .fg(Color::Rgb(255, 255, 255))
+                .bg(Color::Rgb(38, 50, 56)),
+        )
+        .scroll((app.usage_scroll, 0))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(app.i18n.t("key_usage_title"))
+                .title_style(
+                    Style::default()
+                        .fg(Color::Rgb(
+                            app.theme.primary.0,
+                            app.theme.primary.1,
+                            app.theme.primary.2,
+                        ))
+                        .add_modifier(Modifier::BOLD),
+                )
+                .border_style(Style::default().fg(Color::Rgb(
+                    app.theme.success.0,
+                    app.theme.success.1,
+                    app.theme.success.2,
+                ))),
+        );
+
+    f.render_widget(modal, area);
+}
+
+fn render_fixing_permissions_modal(f: &mut Frame, app: &App) {
+    let area = centered_rect(60, 35, f.area());
+    f.render_widget(Clear, area);
+
+    let mut text = vec![
+        Line::from(""),
+        Line::from(Span::styled(
+            app.i18n.t("insecure_identity_files"),
+            Style::default()
+                .fg(Color::Rgb(255, 255, 255))
+                .add_modifier(Modifier::BOLD),
+        )),
+        Line::from(""),
+    ];
+    for (path, mode) in &app.insecure_identity_files {
+        text.push(Line::from(Span::styled(
+            format!("{} ({:o})", KeySource::File(path.clone()).label(), mode),
+            Style::default().fg(Color::Rgb(255, 167, 38)),
+        )));
+    }
+    text.extend([
+        Line::from(""),
+        Line::from(app.i18n.t("fix_permissions_question")),
+    ]);
+
+    let modal = Paragraph::new(text)
+        .style(
+            Style::default()
+                .fg(Color::Rgb(255, 255, 255))
+                .bg(Color::Rgb(38, 50, 56)),
+        )
+        .alignment(Alignment::Center)
+        .wrap(Wrap { trim: false })
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(app.i18n.t("insecure_permissions_title"))
+                .title_style(
+                    Style::default()
+                        .fg(Color::Rgb(239, 83, 80))
+                        .add_modifier(Modifier::BOLD),
+                )
+                .border_style(Style::default().fg(Color::Rgb(239, 83, 80))),
+        );
+
+    f.render_widget(modal, area);
+}
+
+fn render_unlocking_identities_modal(f: &mut Frame, app: &App) {
+    let area = centered_rect(60, 20, f.area());
     f.render_widget(Clear, area);
 
+    let path = app
+        .pending_identity_file()
+        .map(|p| KeySource::File(p.clone()).label())
+        .unwrap_or_default();
     let input = Paragraph::new(format!(
-        "{}\n\n{}_",
-        app.i18n.t("enter_new_name"),
-        app.rename_buffer
+        "{}\n{}\n\n> {}_",
+        app.i18n.t("enter_identity_passphrase"),
+        path,
+        "•".repeat(app.lock_buffer.chars().count().min(40))
     ))
     .style(
         Style::default()
@@ -1510,7 +2023,7 @@ fn render_renaming_file_modal(f: &mut Frame, app: &App) {
     .block(
         Block::default()
             .borders(Borders::ALL)
-            .title(app.i18n.t("rename_title_help"))
+            .title(app.i18n.t("unlock_identities_title"))
             .title_style(
                 Style::default()
                     .fg(Color::Rgb(
@@ -1530,13 +2043,130 @@ fn render_renaming_file_modal(f: &mut Frame, app: &App) {
     f.render_widget(input, area);
 }
 
-fn render_confirm_file_deletion_modal(f: &mut Frame, app: &App) {
-    let area = centered_rect(50, 20, f.area());
+/// Modal de texto del gestor de llaves: importar, exportar, comentar, respaldar y frase
+fn render_key_io_modal(f: &mut Frame, app: &App) {
+    let area = centered_rect(60, 25, f.area());
     f.render_widget(Clear, area);
 
-    let file_name = if let Some(idx) = app.file_list_state.selected() {
-        app.files
-            .get(idx)
+    let pasted = app.key_io_buffer.contains('\n') || app.key_io_buffer.contains("AGE-");
+    let (title, prompt, value) = match app.input_mode {
+        // Las identidades pegadas no se muestran en pantalla
+        InputMode::ImportingKeys if pasted => (
+            "import_keys_title",
+            "import_keys_prompt",
+            format!(
+                "{} {}",
+                parse_identities(&app.key_io_buffer).len(),
+                app.i18n.t("identities_pasted")
+            ),
+        ),
+        InputMode::ImportingKeys => (
+            "import_keys_title",
+            "import_keys_prompt",
+            app.key_io_buffer.clone(),
+        ),
+        InputMode::ExportingKey => (
+            "export_key_title",
+            "export_key_prompt",
+            app.key_io_buffer.clone(),
+        ),
+        InputMode::EditingKeyComment => (
+            "key_comment_title",
+            "key_comment_prompt",
+            app.key_io_buffer.clone(),
+        ),
+        InputMode::BackingUpKeys => (
+            "backup_keys_title",
+            "backup_keys_prompt",
+            app.key_io_buffer.clone(),
+        ),
+        _ => (
+            "key_passphrase_title",
+            match app.key_transfer {
+                Some(KeyTransfer::Export(_)) => "export_passphrase_prompt",
+                Some(KeyTransfer::Delete) => "backup_passphrase_prompt",
+                _ => "enter_identity_passphrase",
+            },
+            "•".repeat(app.lock_buffer.chars().count().min(40)),
+        ),
+    };
+
+    let input = Paragraph::new(format!("{}\n\n> {}_", app.i18n.t(prompt), value))
+        .style(
+            Style::default()
+                .fg(Color::Rgb(255, 255, 255))
+                .bg(Color::Rgb(38, 50, 56)),
+        )
+        .alignment(Alignment::Center)
+        .wrap(Wrap { trim: false })
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(app.i18n.t(title))
+                .title_style(
+                    Style::default()
+                        .fg(Color::Rgb(
+                            app.theme.primary.0,
+                            app.theme.primary.1,
+                            app.theme.primary.2,
+                        ))
+                        .add_modifier(Modifier::BOLD),
+                )
+                .border_style(Style::default().fg(Color::Rgb(
+                    app.theme.warning.0,
+                    app.theme.warning.1,
+                    app.theme.warning.2,
+                ))),
+        );
+
+    f.render_widget(input, area);
+}
+
+fn render_renaming_file_modal(f: &mut Frame, app: &App) {
+    let area = centered_rect(50, 15, f.area());
+    f.render_widget(Clear, area);
+
+    let input = Paragraph::new(format!(
+        "{}\n\n{}_",
+        app.i18n.t("enter_new_name"),
+        app.rename_buffer
+    ))
+    .style(
+        Style::default()
+            .fg(Color::Rgb(255, 255, 255))
+            .bg(Color::Rgb(38, 50, 56)),
+    )
+    .alignment(Alignment::Center)
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(app.i18n.t("rename_title_help"))
+            .title_style(
+                Style::default()
+                    .fg(Color::Rgb(
+                        app.theme.primary.0,
+                        app.theme.primary.1,
+                        app.theme.primary.2,
+                    ))
+                    .add_modifier(Modifier::BOLD),
+            )
+            .border_style(Style::default().fg(Color::Rgb(
+                app.theme.warning.0,
+                app.theme.warning.1,
+                app.theme.warning.2,
+            ))),
+    );
+
+    f.render_widget(input, area);
+}
+
+fn render_confirm_file_deletion_modal(f: &mut Frame, app: &App) {
+    let area = centered_rect(50, 20, f.area());
+    f.render_widget(Clear, area);
+
+    let file_name = if let Some(idx) = app.file_list_state.selected() {
+        app.files
+            .get(idx)
             .and_then(|p| p.file_name())
             .and_then(|n| n.to_str())
             .unwrap_or("?")
@@ -1609,18 +2239,8 @@ fn render_creating_secret_file_modal(f: &mut Frame, app: &App) {
     let area = centered_rect(60, 25, f.area());
     f.render_widget(Clear, area);
 
-    let formats = ["yaml", "json", "env", "ini"];
-    let ext = formats[app.selected_format];
-
-    let display_text = if app.new_file_name_buffer.is_empty() {
-        format!("secrets.{}", ext)
-    } else if app.new_file_name_buffer.starts_with('.')
-        || app.new_file_name_buffer.ends_with(&format!(".{}", ext))
-    {
-        app.new_file_name_buffer.clone()
-    } else {
-        format!("{}.{}", app.new_file_name_buffer, ext)
-    };
+    let ext = FILE_FORMATS[app.selected_format];
+    let display_text = app.new_file_name();
 
     let chunks = Layout::default()
         .direction(Direction::Vertical)
@@ -1669,7 +2289,10 @@ fn render_selecting_file_format_modal(f: &mut Frame, app: &App) {
     f.render_widget(Clear, area);
 
     let formats = [
-        (app.i18n.t("format_env"), app.i18n.t("environment_variables")),
+        (
+            app.i18n.t("format_env"),
+            app.i18n.t("environment_variables"),
+        ),
         (app.i18n.t("format_json"), app.i18n.t("json_config")),
         (app.i18n.t("format_yaml"), app.i18n.t("yaml_config")),
         (app.i18n.t("format_ini"), app.i18n.t("ini_config")),
@@ -1731,14 +2354,18 @@ fn render_selecting_sops_keys_modal(f: &mut Frame, app: &App) {
     f.render_widget(Clear, area);
 
     let items: Vec<ListItem> = app
-        .age_keys
+        .sops_recipient_choices()
         .iter()
         .enumerate()
-        .map(|(i, key)| {
+        .map(|(i, choice)| {
             let checked = app.selected_sops_keys.get(i).copied().unwrap_or(false);
-            let radio = if checked { "(•) " } else { "( ) " };
-            let name = key.comment.as_deref().unwrap_or(app.i18n.t("unnamed"));
-            let pub_key = key.public_key.as_ref().map(|k| &k[..16]).unwrap_or("???");
+            let radio = if checked { "[x] " } else { "[ ] " };
+            let name = &choice.label;
+            let pub_key: String = choice
+                .keys
+                .first()
+                .map(|k| k.chars().take(16).collect())
+                .unwrap_or_else(|| "???".to_string());
 
             let style = if Some(i) == app.key_list_state.selected() {
                 Style::default()
@@ -1767,7 +2394,50 @@ fn render_selecting_sops_keys_modal(f: &mut Frame, app: &App) {
         .style(Style::default().bg(Color::Rgb(38, 50, 56)))
         .highlight_style(Style::default().add_modifier(Modifier::BOLD));
 
-    f.render_stateful_widget(list, area, &mut app.key_list_state.clone());
+    let Some(resolution) = &app.new_file_resolution else {
+        f.render_stateful_widget(list, area, &mut app.key_list_state.clone());
+        return;
+    };
+
+    // Regla del .sops.yaml existente que ya resuelve el archivo nuevo
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(4), Constraint::Min(3)])
+        .split(area);
+    let info = match resolution.rule() {
+        Some(rule) => vec![
+            Line::from(format!(
+                "{} {} ({}) — {}",
+                app.i18n.t("sops_resolves_to"),
+                resolution.rule.unwrap_or_default() + 1,
+                rule.path_regex
+                    .as_deref()
+                    .unwrap_or(app.i18n.t("sops_any_file")),
+                resolution.config_path.display()
+            )),
+            Line::from(Span::styled(
+                app.i18n.t("sops_new_rule_hint"),
+                Style::default().fg(Color::Rgb(150, 150, 150)),
+            )),
+        ],
+        None => vec![Line::from(format!(
+            "{} {}",
+            app.i18n.t("sops_no_matching_rule"),
+            resolution.config_path.display()
+        ))],
+    };
+    f.render_widget(
+        Paragraph::new(info)
+            .wrap(Wrap { trim: true })
+            .block(Block::default().borders(Borders::ALL))
+            .style(
+                Style::default()
+                    .bg(Color::Rgb(38, 50, 56))
+                    .fg(Color::Rgb(189, 189, 189)),
+            ),
+        chunks[0],
+    );
+    f.render_stateful_widget(list, chunks[1], &mut app.key_list_state.clone());
 }
 
 pub fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
@@ -1791,20 +2461,232 @@ pub fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
 }
 
 fn render_editing_sops_config_modal(f: &mut Frame, app: &App) {
-    let area = centered_rect(80, 80, f.area());
+    let area = centered_rect(85, 80, f.area());
+    f.render_widget(Clear, area);
+
+    let green = Color::Rgb(102, 187, 106);
+    let red = Color::Rgb(239, 83, 80);
+    let yellow = Color::Rgb(255, 167, 38);
+    let modal_style = Style::default()
+        .bg(Color::Rgb(38, 50, 56))
+        .fg(Color::Rgb(189, 189, 189));
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(format!(" {} .sops.yaml ", app.i18n.t("editing_sops")))
+        .title_style(Style::default().fg(green).add_modifier(Modifier::BOLD))
+        .border_style(Style::default().fg(green))
+        .style(modal_style);
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+
+    let columns = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(35), Constraint::Percentage(65)])
+        .split(inner);
+
+    // Reglas en orden: SOPS usa la primera que coincide
+    let errors = app.sops_config.validate();
+    let items: Vec<ListItem> = app
+        .sops_config
+        .creation_rules
+        .iter()
+        .enumerate()
+        .map(|(i, rule)| {
+            let invalid = errors.iter().any(|(idx, _)| *idx == i);
+            let shadowed = app.sops_shadowed.iter().any(|(idx, _)| *idx == i);
+            let pattern = rule
+                .path_regex
+                .clone()
+                .unwrap_or_else(|| app.i18n.t("sops_any_file").to_string());
+            let style = match (i == app.sops_rule_selected, invalid, shadowed) {
+                (true, _, _) => Style::default().fg(green).add_modifier(Modifier::BOLD),
+                (false, true, _) => Style::default().fg(red),
+                (false, false, true) => Style::default().fg(yellow),
+                (false, false, false) => Style::default(),
+            };
+            ListItem::new(format!(
+                "{}{}. {}",
+                if invalid || shadowed { "⚠ " } else { "" },
+                i + 1,
+                pattern
+            ))
+            .style(style)
+        })
+        .collect();
+    let rules = if items.is_empty() {
+        List::new(vec![ListItem::new(app.i18n.t("sops_no_rules"))])
+    } else {
+        List::new(items)
+    };
+    f.render_widget(
+        rules.block(
+            Block::default()
+                .borders(Borders::RIGHT)
+                .title(app.i18n.t("sops_rules_title")),
+        ),
+        columns[0],
+    );
+
+    let Some(rule) = app.selected_sops_rule() else {
+        return;
+    };
+
+    let mut text = Vec::new();
+    for (field, name) in RULE_FIELDS.iter().enumerate() {
+        let selected = field == app.sops_field_selected;
+        let value = if selected && app.sops_field_editing {
+            format!("{}_", app.edit_buffer)
+        } else if name.ends_with("_regex") {
+            rule.field_text(field)
+        } else {
+            // Recipients con el nombre de la libreta o de la llave propia
+            let groups: Vec<&[String]> = if field == 2 {
+                vec![rule.age.as_slice()]
+            } else {
+                rule.key_groups.iter().map(|g| g.age.as_slice()).collect()
+            };
+            groups
+                .iter()
+                .map(|keys| {
+                    keys.iter()
+                        .map(|k| app.recipient_label(k))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                })
+                .collect::<Vec<_>>()
+                .join(" | ")
+        };
+        let style = if selected {
+            Style::default().fg(green).add_modifier(Modifier::BOLD)
+        } else {
+            Style::default()
+        };
+        text.push(Line::from(vec![
+            Span::styled(
+                format!("{} {:<16}", if selected { "▶" } else { " " }, name),
+                style,
+            ),
+            Span::raw(value),
+        ]));
+    }
+    if !rule.other.is_empty() {
+        let other: Vec<&str> = rule.other.keys().map(String::as_str).collect();
+        text.push(Line::from(Span::styled(
+            format!(
+                "  {}: {}",
+                app.i18n.t("sops_other_fields"),
+                other.join(", ")
+            ),
+            Style::default().fg(Color::Rgb(150, 150, 150)),
+        )));
+    }
+
+    text.push(Line::from(""));
+    for (_, error) in errors.iter().filter(|(i, _)| *i == app.sops_rule_selected) {
+        text.push(Line::from(Span::styled(
+            format!("❌ {}", error),
+            Style::default().fg(red),
+        )));
+    }
+    for (_, by) in app
+        .sops_shadowed
+        .iter()
+        .filter(|(i, _)| *i == app.sops_rule_selected)
+    {
+        text.push(Line::from(Span::styled(
+            format!("⚠ {} {}", app.i18n.t("sops_rule_shadowed_by"), by + 1),
+            Style::default().fg(yellow),
+        )));
+    }
+
+    let matched = app
+        .sops_preview
+        .get(app.sops_rule_selected)
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+    text.push(Line::from(Span::styled(
+        format!("{} ({})", app.i18n.t("sops_rule_matches"), matched.len()),
+        Style::default().add_modifier(Modifier::BOLD),
+    )));
+    for path in matched {
+        let shown = path.strip_prefix(&app.current_dir).unwrap_or(path);
+        text.push(Line::from(format!("  {}", shown.display())));
+    }
+
+    f.render_widget(
+        Paragraph::new(text).wrap(Wrap { trim: false }),
+        columns[1].inner(ratatui::layout::Margin::new(1, 0)),
+    );
+}
+
+fn render_selecting_sops_template_modal(f: &mut Frame, app: &App) {
+    let area = centered_rect(70, 50, f.area());
     f.render_widget(Clear, area);
 
-    let lines: Vec<Line> = app
-        .edit_buffer
-        .lines()
-        .map(|line| Line::from(line.to_string()))
+    let templates: Vec<(&str, &str)> = app
+        .sops_templates
+        .iter()
+        .map(|template| match template {
+            SopsTemplate::ByFormat => (
+                app.i18n.t("template_simple"),
+                app.i18n.t("template_simple_desc"),
+            ),
+            SopsTemplate::ByEnvironment => (
+                app.i18n.t("template_by_type"),
+                app.i18n.t("template_by_type_desc"),
+            ),
+            SopsTemplate::SensitiveValues => (
+                app.i18n.t("template_regex"),
+                app.i18n.t("template_regex_desc"),
+            ),
+            SopsTemplate::Kubernetes => {
+                (app.i18n.t("template_k8s"), app.i18n.t("template_k8s_desc"))
+            }
+            SopsTemplate::Custom {
+                name, description, ..
+            } => (name.as_str(), description.as_str()),
+        })
         .collect();
 
-    let paragraph = Paragraph::new(lines)
+    let items: Vec<ListItem> = templates
+        .iter()
+        .enumerate()
+        .map(|(i, (name, desc))| {
+            let style = if i == app.selected_sops_template {
+                Style::default()
+                    .fg(Color::Rgb(102, 187, 106))
+                    .add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(Color::Rgb(189, 189, 189))
+            };
+
+            let content = vec![
+                Line::from(vec![
+                    Span::styled(
+                        if i == app.selected_sops_template {
+                            "▶ "
+                        } else {
+                            "  "
+                        },
+                        style,
+                    ),
+                    Span::styled(*name, style),
+                ]),
+                Line::from(vec![
+                    Span::styled("    ", style),
+                    Span::styled(*desc, Style::default().fg(Color::Rgb(150, 150, 150))),
+                ]),
+            ];
+
+            ListItem::new(content).style(style)
+        })
+        .collect();
+
+    let list = List::new(items)
         .block(
             Block::default()
                 .borders(Borders::ALL)
-                .title(format!(" {} .sops.yaml ", app.i18n.t("editing_sops")))
+                .title(app.i18n.t("select_template_title"))
                 .title_style(
                     Style::default()
                         .fg(Color::Rgb(102, 187, 106))
@@ -1812,40 +2694,352 @@ fn render_editing_sops_config_modal(f: &mut Frame, app: &App) {
                 )
                 .border_style(Style::default().fg(Color::Rgb(102, 187, 106))),
         )
-        .style(
+        .style(Style::default().bg(Color::Rgb(38, 50, 56)));
+
+    f.render_widget(list, area);
+}
+
+fn render_history_modal(f: &mut Frame, app: &mut App) {
+    let area = centered_rect(85, 80, f.area());
+    f.render_widget(Clear, area);
+
+    let rows: Vec<Row> = app
+        .filtered_history()
+        .iter()
+        .map(|event| {
+            let (label, color) = match event.change {
+                KeyChange::Added => (app.i18n.t("history_added"), app.theme.success),
+                KeyChange::Removed => (app.i18n.t("history_removed"), app.theme.error),
+                KeyChange::Changed => (app.i18n.t("history_changed"), app.theme.warning),
+                KeyChange::Unreadable => (app.i18n.t("history_unreadable"), app.theme.warning),
+                KeyChange::FileDeleted => (app.i18n.t("history_file_deleted"), app.theme.error),
+            };
+            Row::new(vec![
+                Cell::from(event.date.clone()),
+                Cell::from(event.commit.clone()),
+                Cell::from(event.author.clone()),
+                Cell::from(event.key.clone()),
+                Cell::from(label.to_string())
+                    .style(Style::default().fg(Color::Rgb(color.0, color.1, color.2))),
+            ])
+        })
+        .collect();
+
+    let file_name = app
+        .file_path
+        .as_ref()
+        .and_then(|p| p.file_name())
+        .and_then(|n| n.to_str())
+        .unwrap_or("?");
+    let mut title = format!(
+        "{} {} ({})",
+        app.i18n.t("history_title"),
+        file_name,
+        rows.len()
+    );
+    if let Some(key) = &app.history_filter {
+        title.push_str(&format!(" - {} {}", app.i18n.t("history_filtered"), key));
+    }
+    if app.history_loading.is_some() {
+        title.push_str(&format!(" - {}", app.i18n.t("history_loading")));
+    } else if rows.is_empty() {
+        title.push_str(&format!(" - {}", app.i18n.t("history_empty")));
+    }
+
+    let header = Row::new(vec![
+        app.i18n.t("history_date"),
+        app.i18n.t("history_commit"),
+        app.i18n.t("history_author"),
+        app.i18n.t("key"),
+        "",
+    ])
+    .style(
+        Style::default()
+            .fg(Color::Rgb(171, 71, 188))
+            .add_modifier(Modifier::BOLD),
+    );
+
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(12),
+            Constraint::Length(10),
+            Constraint::Percentage(25),
+            Constraint::Percentage(40),
+            Constraint::Length(12),
+        ],
+    )
+    .header(header)
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(title)
+            .title_style(
+                Style::default()
+                    .fg(Color::Rgb(129, 212, 250))
+                    .add_modifier(Modifier::BOLD),
+            )
+            .border_style(Style::default().fg(Color::Rgb(102, 187, 106)))
+            .style(Style::default().bg(Color::Rgb(38, 50, 56))),
+    )
+    .row_highlight_style(
+        Style::default()
+            .bg(Color::Rgb(66, 66, 66))
+            .fg(Color::Rgb(255, 213, 79))
+            .add_modifier(Modifier::BOLD),
+    )
+    .highlight_symbol("▶ ");
+
+    f.render_stateful_widget(table, area, &mut app.history_state);
+}
+
+fn render_audit_modal(f: &mut Frame, app: &mut App) {
+    let area = centered_rect(85, 80, f.area());
+    f.render_widget(Clear, area);
+
+    let rows: Vec<Row> = app
+        .filtered_audit_events()
+        .iter()
+        .map(|event| {
+            let file_name = event
+                .file
+                .file_name()
+                .and_then(|n| n.to_str())
+                .unwrap_or("?")
+                .to_string();
+            Row::new(vec![
+                event
+                    .timestamp
+                    .with_timezone(&chrono::Local)
+                    .format("%Y-%m-%d %H:%M:%S")
+                    .to_string(),
+                event.action.as_str().to_string(),
+                file_name,
+                event.key.clone().unwrap_or_default(),
+            ])
+        })
+        .collect();
+
+    let mut title = format!("{} ({})", app.i18n.t("audit_title"), rows.len());
+    if let Some(file) = &app.audit_file_filter {
+        title.push_str(&format!(
+            " - {}: {}",
+            app.i18n.t("audit_file"),
+            file.file_name().and_then(|n| n.to_str()).unwrap_or("?")
+        ));
+    }
+    title.push_str(&format!(
+        " - {}: {}",
+        app.i18n.t("audit_action"),
+        app.audit_action_filter
+            .map(|a| a.as_str())
+            .unwrap_or(app.i18n.t("audit_all_actions"))
+    ));
+    if rows.is_empty() {
+        title.push_str(&format!(" - {}", app.i18n.t("audit_empty")));
+    }
+
+    let header = Row::new(vec![
+        app.i18n.t("audit_header_time"),
+        app.i18n.t("audit_header_action"),
+        app.i18n.t("audit_header_file"),
+        app.i18n.t("key"),
+    ])
+    .style(
+        Style::default()
+            .fg(Color::Rgb(171, 71, 188))
+            .add_modifier(Modifier::BOLD),
+    );
+
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(20),
+            Constraint::Length(20),
+            Constraint::Percentage(30),
+            Constraint::Percentage(40),
+        ],
+    )
+    .header(header)
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(title)
+            .title_style(
+                Style::default()
+                    .fg(Color::Rgb(129, 212, 250))
+                    .add_modifier(Modifier::BOLD),
+            )
+            .border_style(Style::default().fg(Color::Rgb(102, 187, 106)))
+            .style(Style::default().bg(Color::Rgb(38, 50, 56))),
+    )
+    .row_highlight_style(
+        Style::default()
+            .bg(Color::Rgb(66, 66, 66))
+            .fg(Color::Rgb(255, 213, 79))
+            .add_modifier(Modifier::BOLD),
+    )
+    .highlight_symbol("▶ ");
+
+    f.render_stateful_widget(table, area, &mut app.audit_state);
+}
+
+fn render_metadata_modal(f: &mut Frame, app: &App) {
+    let area = centered_rect(60, 40, f.area());
+    f.render_widget(Clear, area);
+
+    let key = app
+        .table_state
+        .selected()
+        .and_then(|idx| app.filtered_secrets().get(idx).copied())
+        .and_then(|real_idx| app.secrets.get(real_idx))
+        .map(|(k, _)| k.clone())
+        .unwrap_or_default();
+    let meta = app.metadata.get(&key).cloned().unwrap_or_default();
+    let format_time = |t: Option<chrono::DateTime<chrono::Utc>>| {
+        t.map(|t| {
+            t.with_timezone(&chrono::Local)
+                .format("%Y-%m-%d %H:%M")
+                .to_string()
+        })
+        .unwrap_or_else(|| "-".to_string())
+    };
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(format!("{} {}", app.i18n.t("meta_title"), key))
+        .title_style(
             Style::default()
-                .bg(Color::Rgb(38, 50, 56))
-                .fg(Color::Rgb(189, 189, 189)),
-        );
+                .fg(Color::Rgb(255, 167, 38))
+                .add_modifier(Modifier::BOLD),
+        )
+        .border_style(Style::default().fg(Color::Rgb(102, 187, 106)))
+        .style(Style::default().bg(Color::Rgb(38, 50, 56)));
+    let inner = block.inner(area);
+    f.render_widget(block, area);
 
-    f.render_widget(paragraph, area);
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(2),
+            Constraint::Length(3),
+            Constraint::Length(3),
+            Constraint::Length(3),
+            Constraint::Min(0),
+        ])
+        .split(inner);
+
+    let info = Paragraph::new(format!(
+        "{}: {} | {}: {}",
+        app.i18n.t("meta_created"),
+        format_time(meta.created),
+        app.i18n.t("meta_rotated"),
+        format_time(meta.rotated)
+    ))
+    .style(Style::default().fg(Color::Rgb(129, 212, 250)));
+    f.render_widget(info, chunks[0]);
+
+    let labels = ["meta_owner", "meta_expires", "meta_rotation_days"];
+    for (i, label) in labels.iter().enumerate() {
+        let input = Paragraph::new(app.meta_buffers[i].as_str())
+            .style(Style::default().fg(Color::Rgb(255, 255, 255)))
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(app.i18n.t(label))
+                    .border_style(if app.editing_field == i {
+                        Style::default().fg(Color::Rgb(102, 187, 106))
+                    } else {
+                        Style::default().fg(Color::Rgb(66, 66, 66))
+                    }),
+            );
+        f.render_widget(input, chunks[i + 1]);
+    }
+
+    let field = chunks[app.editing_field + 1];
+    f.set_cursor_position((
+        field.x + app.meta_buffers[app.editing_field].len() as u16 + 1,
+        field.y + 1,
+    ));
 }
 
-fn render_selecting_sops_template_modal(f: &mut Frame, app: &App) {
-    let area = centered_rect(70, 50, f.area());
+fn render_generator_modal(f: &mut Frame, app: &App) {
+    let area = centered_rect(60, 45, f.area());
     f.render_widget(Clear, area);
 
-    let templates = [
+    let check = |enabled: bool| if enabled { "✓" } else { "✗" };
+    let token_format = match app.gen_token_format {
+        TokenFormat::Hex => "Hex",
+        TokenFormat::Base64 => "Base64",
+        TokenFormat::Base64Url => "Base64url",
+        TokenFormat::Base32 => "Base32",
+        TokenFormat::Uuid => "UUID",
+    };
+    let options = [
         (
-            app.i18n.t("template_simple"),
-            app.i18n.t("template_simple_desc"),
+            app.i18n.t("gen_password"),
+            format!(
+                "{}: {} | [s] {}: {} | [n] {}: {}",
+                app.i18n.t("gen_length"),
+                app.gen_length,
+                app.i18n.t("gen_special"),
+                check(app.gen_use_special),
+                app.i18n.t("gen_numbers"),
+                check(app.gen_use_numbers)
+            ),
         ),
         (
-            app.i18n.t("template_by_type"),
-            app.i18n.t("template_by_type_desc"),
+            app.i18n.t("gen_token"),
+            format!(
+                "{}: {} | {}: {}",
+                app.i18n.t("gen_format"),
+                token_format,
+                app.i18n.t("gen_bytes"),
+                app.gen_length
+            ),
         ),
         (
-            app.i18n.t("template_regex"),
-            app.i18n.t("template_regex_desc"),
+            app.i18n.t("gen_policy"),
+            format!(
+                "◀ {} ▶ | {}: {}",
+                app.password_policies()
+                    .get(app.gen_policy_index)
+                    .map(|(name, _)| name.as_str())
+                    .unwrap_or("-"),
+                app.i18n.t("gen_length"),
+                app.gen_length
+            ),
+        ),
+        (
+            app.i18n.t("gen_passphrase"),
+            format!(
+                "{}: {} | [s] {}: \"{}\" | [c] {}: {} | [n] {}: {}",
+                app.i18n.t("gen_words"),
+                app.gen_passphrase.words,
+                app.i18n.t("gen_separator"),
+                app.gen_passphrase.separator,
+                app.i18n.t("gen_capitalize"),
+                check(app.gen_passphrase.capitalize),
+                app.i18n.t("gen_numbers"),
+                check(app.gen_passphrase.digit)
+            ),
+        ),
+        (
+            app.i18n.t("gen_key"),
+            format!(
+                "◀ {} ▶ | [p] {}: {}",
+                app.gen_key_kind.label(),
+                app.i18n.t("gen_public_sibling"),
+                check(app.gen_public_sibling)
+            ),
         ),
-        (app.i18n.t("template_k8s"), app.i18n.t("template_k8s_desc")),
     ];
 
-    let items: Vec<ListItem> = templates
+    let items: Vec<ListItem> = options
         .iter()
         .enumerate()
         .map(|(i, (name, desc))| {
-            let style = if i == app.selected_sops_template {
+            let style = if i == app.gen_selected_option {
                 Style::default()
                     .fg(Color::Rgb(102, 187, 106))
                     .add_modifier(Modifier::BOLD)
@@ -1856,7 +3050,7 @@ fn render_selecting_sops_template_modal(f: &mut Frame, app: &App) {
             let content = vec![
                 Line::from(vec![
                     Span::styled(
-                        if i == app.selected_sops_template {
+                        if i == app.gen_selected_option {
                             "▶ "
                         } else {
                             "  "
@@ -1867,7 +3061,7 @@ fn render_selecting_sops_template_modal(f: &mut Frame, app: &App) {
                 ]),
                 Line::from(vec![
                     Span::styled("    ", style),
-                    Span::styled(*desc, Style::default().fg(Color::Rgb(150, 150, 150))),
+                    Span::styled(desc.clone(), Style::default().fg(Color::Rgb(150, 150, 150))),
                 ]),
             ];
 
@@ -1875,11 +3069,25 @@ fn render_selecting_sops_template_modal(f: &mut Frame, app: &App) {
         })
         .collect();
 
+    let title = if app.gen_rotating {
+        let key = app
+            .table_state
+            .selected()
+            .and_then(|idx| app.filtered_secrets().get(idx).copied())
+            .and_then(|real_idx| app.secrets.get(real_idx))
+            .map(|(k, _)| k.as_str())
+            .unwrap_or("");
+        format!("{} {}", app.i18n.t("gen_rotate_title"), key)
+    } else {
+        app.i18n.t("gen_title").to_string()
+    };
+
     let list = List::new(items)
         .block(
             Block::default()
                 .borders(Borders::ALL)
-                .title(app.i18n.t("select_template_title"))
+                .title(title)
+                .title_bottom(strength_line(app, app.current_gen_policy().entropy_bits()))
                 .title_style(
                     Style::default()
                         .fg(Color::Rgb(102, 187, 106))
@@ -1891,3 +3099,22 @@ fn render_selecting_sops_template_modal(f: &mut Frame, app: &App) {
 
     f.render_widget(list, area);
 }
+
+/// Indicador de fortaleza con la entropía estimada, coloreado según el nivel
+fn strength_line(app: &App, bits: f64) -> Line<'static> {
+    let strength = Strength::from_bits(bits);
+    let color = match strength {
+        Strength::VeryWeak | Strength::Weak => app.theme.error,
+        Strength::Fair => app.theme.warning,
+        Strength::Strong | Strength::VeryStrong => app.theme.success,
+    };
+    Line::from(Span::styled(
+        format!(
+            " {}: {} (~{:.0} bits) ",
+            app.i18n.t("strength"),
+            app.i18n.t(strength.label_key()),
+            bits
+        ),
+        Style::default().fg(Color::Rgb(color.0, color.1, color.2)),
+    ))
+}
@@ -0,0 +1,84 @@
+use crate::addressbook::AddressBook;
+use crate::keystore::sops_files;
+use crate::sops::{recipient_matches, AgeKey};
+use std::path::{Path, PathBuf};
+
+/// Archivos que una identidad propia o un recipient conocido puede descifrar
+pub struct KeyUsage {
+    /// Comentario de la llave o nombre en la libreta, si lo hay
+    pub name: Option<String>,
+    pub recipient: String,
+    pub files: Vec<PathBuf>,
+}
+
+/// Informe de uso de llaves sobre los archivos SOPS de un árbol
+pub struct UsageReport {
+    pub root: PathBuf,
+    pub total_files: usize,
+    pub identities: Vec<KeyUsage>,
+    /// Recipients de los archivos que no corresponden a ninguna llave local
+    pub recipients: Vec<KeyUsage>,
+    /// Archivos que ninguna llave local puede descifrar
+    pub orphaned: Vec<PathBuf>,
+}
+
+impl UsageReport {
+    pub fn scan(root: &Path, keys: &[AgeKey], book: &AddressBook) -> Self {
+        Self::build(root, &sops_files(&[root.to_path_buf()]), keys, book)
+    }
+
+    pub fn build(
+        root: &Path,
+        files: &[(PathBuf, Vec<String>)],
+        keys: &[AgeKey],
+        book: &AddressBook,
+    ) -> Self {
+        let files_of = |matches: &dyn Fn(&[String]) -> bool| -> Vec<PathBuf> {
+            files
+                .iter()
+                .filter(|(_, recipients)| matches(recipients))
+                .map(|(path, _)| path.clone())
+                .collect()
+        };
+
+        let identities = keys
+            .iter()
+            .map(|key| KeyUsage {
+                name: key.comment.clone(),
+                recipient: key.public_key.clone().unwrap_or_default(),
+                files: files_of(&|recipients| key.is_recipient_of(recipients)),
+            })
+            .collect();
+
+        let mut foreign: Vec<&String> = files
+            .iter()
+            .flat_map(|(_, recipients)| recipients)
+            .filter(|r| {
+                !keys
+                    .iter()
+                    .any(|k| k.is_recipient_of(std::slice::from_ref(*r)))
+            })
+            .collect();
+        foreign.sort();
+        foreign.dedup_by(|a, b| recipient_matches(a, b));
+
+        let mut recipients: Vec<KeyUsage> = foreign
+            .into_iter()
+            .map(|recipient| KeyUsage {
+                name: book.label(recipient),
+                recipient: recipient.clone(),
+                files: files_of(&|rs| rs.iter().any(|r| recipient_matches(recipient, r))),
+            })
+            .collect();
+        // Primero los que tienen nombre en la libreta
+        recipients.sort_by(|a, b| (a.name.is_none(), &a.name).cmp(&(b.name.is_none(), &b.name)));
+
+        Self {
+            root: root.to_path_buf(),
+            total_files: files.len(),
+            identities,
+            recipients,
+            orphaned: files_of(&|recipients| !keys.iter().any(|k| k.is_recipient_of(recipients))),
+        }
+    }
+}
@@ -0,0 +1,102 @@
+/// Tests para la libreta de recipients del equipo
+#[allow(dead_code)]
+#[path = "../src/addressbook.rs"]
+mod addressbook;
+#[allow(dead_code)]
+#[path = "../src/perms.rs"]
+mod perms;
+#[allow(dead_code)]
+#[path = "../src/sops.rs"]
+mod sops;
+
+#[allow(dead_code)]
+mod helpers;
+
+use addressbook::{local_path, AddressBook, LOCAL_ADDRESS_BOOK};
+use helpers::temp_dir;
+use std::fs;
+
+const ALICE: &str = "age1ql3z7hjy54pw3hyww5ayyfg7zqgvc7w3j2elw8zmrj2kg5sfn9aqmcac8p";
+const BOB: &str = "age1lggyhqrw2nlhcxprm67z43rta597azn8gknawjehu9d9dl0jq3yqqvfafg";
+const BOB_SSH: &str = "ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIBobBobBob bob@laptop";
+
+fn book() -> AddressBook {
+    AddressBook::parse(&format!(
+        r#"
+        [people.alice]
+        email = "alice@example.com"
+        keys = ["{}"]
+        groups = ["backend", "ops"]
+
+        [people.bob]
+        keys = ["{}", "{}"]
+        groups = ["backend"]
+        "#,
+        ALICE, BOB, BOB_SSH
+    ))
+    .unwrap()
+}
+
+#[test]
+fn test_labels_known_recipients() {
+    let book = book();
+    assert_eq!(
+        book.label(ALICE).as_deref(),
+        Some("alice <alice@example.com>")
+    );
+    assert_eq!(book.label(BOB).as_deref(), Some("bob"));
+    // Las llaves SSH se reconocen aunque el recipient no lleve comentario
+    assert_eq!(
+        book.label("ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIBobBobBob")
+            .as_deref(),
+        Some("bob")
+    );
+    assert_eq!(book.label("age1unknown"), None);
+}
+
+#[test]
+fn test_resolves_people_and_groups() {
+    let book = book();
+    assert_eq!(book.groups(), vec!["backend", "ops"]);
+    assert_eq!(book.resolve("alice"), vec![ALICE]);
+    assert_eq!(book.resolve("alice@example.com"), vec![ALICE]);
+    assert_eq!(book.resolve("@ops"), vec![ALICE]);
+
+    let mut backend = vec![ALICE, BOB, BOB_SSH];
+    backend.sort();
+    assert_eq!(book.resolve("@backend"), backend);
+    assert!(book.resolve("@nobody").is_empty());
+    assert!(book.resolve("carol").is_empty());
+}
+
+#[test]
+fn test_minimal_and_invalid_books() {
+    let book = AddressBook::parse("[people.carol]\nkeys = [\"age1carol\"]\n").unwrap();
+    assert!(book.people["carol"].groups.is_empty());
+    assert_eq!(book.people["carol"].email, None);
+
+    assert!(AddressBook::parse("").unwrap().people.is_empty());
+    assert!(AddressBook::parse("[people.dave]\nkeys = \"age1\"\n").is_err());
+}
+
+#[test]
+fn test_local_book_is_found_from_subdirectories() {
+    let root = temp_dir("book");
+    let nested = root.join("services/api");
+    fs::create_dir_all(&nested).unwrap();
+    assert_eq!(local_path(&nested), None);
+
+    let book_path = root.join(LOCAL_ADDRESS_BOOK);
+    fs::create_dir_all(book_path.parent().unwrap()).unwrap();
+    fs::write(
+        &book_path,
+        format!("[people.alice]\nkeys = [\"{}\"]\n", ALICE),
+    )
+    .unwrap();
+
+    assert_eq!(local_path(&nested), Some(book_path));
+    let book = AddressBook::load(&nested).unwrap();
+    assert_eq!(book.label(ALICE).as_deref(), Some("alice"));
+
+    fs::remove_dir_all(&root).ok();
+}
@@ -0,0 +1,60 @@
+/// Tests para el formato del log de auditoría
+#[allow(dead_code)]
+#[path = "../src/audit.rs"]
+mod audit;
+#[allow(dead_code)]
+#[path = "../src/perms.rs"]
+mod perms;
+
+use audit::{parse_events, AuditAction, AuditEvent};
+use std::path::Path;
+
+#[test]
+fn test_event_serializes_without_values() {
+    let event = AuditEvent::new(
+        AuditAction::ValueCopied,
+        Path::new("/tmp/secrets.yaml"),
+        Some("DB_PASSWORD"),
+    );
+    let json = serde_json::to_string(&event).unwrap();
+
+    assert!(json.contains("\"action\":\"value_copied\""));
+    assert!(json.contains("\"key\":\"DB_PASSWORD\""));
+    assert!(!json.contains("value\":"), "Values must never be logged");
+}
+
+#[test]
+fn test_event_without_key_omits_field() {
+    let event = AuditEvent::new(AuditAction::FileSaved, Path::new("/tmp/a.env"), None);
+    let json = serde_json::to_string(&event).unwrap();
+    assert!(!json.contains("\"key\""));
+}
+
+#[test]
+fn test_parse_events_skips_malformed_lines() {
+    let first = AuditEvent::new(AuditAction::FileOpened, Path::new("/tmp/a.env"), None);
+    let second = AuditEvent::new(
+        AuditAction::KeyDeleted,
+        Path::new("/tmp/a.env"),
+        Some("OLD"),
+    );
+    let content = format!(
+        "{}\n\nnot json\n{}\n",
+        serde_json::to_string(&first).unwrap(),
+        serde_json::to_string(&second).unwrap()
+    );
+
+    let events = parse_events(&content);
+    assert_eq!(events.len(), 2);
+    assert_eq!(events[0].action, AuditAction::FileOpened);
+    assert_eq!(events[1].action, AuditAction::KeyDeleted);
+    assert_eq!(events[1].key.as_deref(), Some("OLD"));
+}
+
+#[test]
+fn test_action_names_match_serde() {
+    for action in AuditAction::ALL {
+        let json = serde_json::to_string(&action).unwrap();
+        assert_eq!(json, format!("\"{}\"", action.as_str()));
+    }
+}
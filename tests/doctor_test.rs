@@ -0,0 +1,205 @@
+/// Tests para `agesmith doctor`: reglas, drift, valores en claro y restos de guardados
+#[allow(dead_code)]
+#[path = "../src/doctor.rs"]
+mod doctor;
+#[allow(dead_code)]
+#[path = "../src/keystore.rs"]
+mod keystore;
+#[allow(dead_code)]
+#[path = "../src/perms.rs"]
+mod perms;
+#[allow(dead_code)]
+#[path = "../src/sops.rs"]
+mod sops;
+#[allow(dead_code)]
+#[path = "../src/sopsconfig.rs"]
+mod sopsconfig;
+
+#[allow(dead_code)]
+mod helpers;
+
+use doctor::{plaintext_keys, Check, DoctorReport};
+use helpers::temp_dir;
+use keystore::display_relative;
+use regex::Regex;
+use std::{fs, path::Path, process::Command};
+
+const ALICE: &str = "age1ql3z7hjy54pw3hyww5ayyfg7zqgvc7w3j2elw8zmrj2kg5sfn9aqmcac8p";
+const BOB: &str = "age1lggyhqrw2nlhcxprm67z43rta597azn8gknawjehu9d9dl0jq3yqqvfafg";
+
+fn write(dir: &Path, file: &str, content: &str) {
+    let path = dir.join(file);
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, content).unwrap();
+}
+
+/// Hallazgos del árbol, sin los de identidades (dependen del HOME de quien ejecuta)
+fn tree_findings(report: &DoctorReport) -> Vec<(Check, String)> {
+    report
+        .findings
+        .iter()
+        .filter(|f| f.check != Check::ReadableIdentity)
+        .map(|f| (f.check, display_relative(&report.root, &f.path).to_string()))
+        .collect()
+}
+
+#[test]
+fn test_doctor_reports_each_problem() {
+    let dir = temp_dir("doctor");
+    write(
+        &dir,
+        ".sops.yaml",
+        &format!(
+            "creation_rules:\n  - path_regex: ^prod/\n    encrypted_regex: ^(password|data)$\n    age: {}\n",
+            ALICE
+        ),
+    );
+    write(
+        &dir,
+        "prod/ok.yaml",
+        &format!(
+            "password: ENC[x]\nuser: app\nsops:\n    age:\n        - recipient: {}\n",
+            ALICE
+        ),
+    );
+    write(
+        &dir,
+        "prod/db.yaml",
+        &format!(
+            "password: ENC[x]\ndata:\n    token: plain\nsops:\n    age:\n        - recipient: {}\n",
+            BOB
+        ),
+    );
+    write(
+        &dir,
+        "other.json",
+        &format!(
+            r#"{{"a": "ENC[x]", "sops": {{"age": [{{"recipient": "{}"}}]}}}}"#,
+            ALICE
+        ),
+    );
+    write(&dir, "prod/db.yaml.bak", "password=hunter2\n");
+    write(
+        &dir,
+        "prod/app.env",
+        &format!(
+            "password=ENC[x]\nsops_age__list_0__map_recipient={}\n",
+            ALICE
+        ),
+    );
+    write(&dir, "prod/.app.env.tmp", "password=hunter2\n");
+    // Sin archivo SOPS al lado no son restos de un guardado
+    write(&dir, "prod/notes.bak", "password=hunter2\n");
+    write(&dir, "prod/db.json.bak", "password=hunter2\n");
+    write(&dir, "build.tmp", "password=hunter2\n");
+    write(
+        &dir,
+        "prod/app.env.tmp",
+        &format!(
+            "password=ENC[x]\nsops_age__list_0__map_recipient={}\n",
+            ALICE
+        ),
+    );
+
+    let report = DoctorReport::scan(&dir, &[]).unwrap();
+    assert_eq!(report.sops_files, 4);
+    let mut findings = tree_findings(&report);
+    findings[3..].sort_by(|a, b| a.1.cmp(&b.1));
+    assert_eq!(
+        findings,
+        vec![
+            (Check::NoMatchingRule, "other.json".to_string()),
+            (Check::RecipientDrift, "prod/db.yaml".to_string()),
+            (Check::UnencryptedValue, "prod/db.yaml".to_string()),
+            (Check::PlaintextLeftover, "prod/.app.env.tmp".to_string()),
+            (Check::PlaintextLeftover, "prod/db.yaml.bak".to_string()),
+        ]
+    );
+    let unencrypted = &report.findings[2];
+    assert!(unencrypted.detail.contains("data.token"));
+
+    let json = serde_json::to_value(&report).unwrap();
+    assert_eq!(json["findings"][0]["check"], "no_matching_rule");
+
+    // Un .sops.yaml ilegible se informa una vez
+    write(&dir, ".sops.yaml", "creation_rules: [");
+    let report = DoctorReport::scan(&dir, &[]).unwrap();
+    let findings = tree_findings(&report);
+    assert_eq!(
+        findings[0],
+        (Check::InvalidConfig, ".sops.yaml".to_string())
+    );
+    assert_eq!(
+        findings
+            .iter()
+            .filter(|(c, _)| *c == Check::InvalidConfig)
+            .count(),
+        1
+    );
+
+    fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn test_plaintext_keys_by_format() {
+    let re = Regex::new("^(password|data|db)$").unwrap();
+
+    let env = "password=hunter2\nuser=app\ndb=ENC[x]\n# password=old\nsops_mac=ENC[x]\n";
+    assert_eq!(
+        plaintext_keys(Path::new("app.env"), env, &re),
+        vec!["password"]
+    );
+
+    let ini = "[db]\nhost=localhost\n[app]\npassword=ENC[x]\n[sops]\nmac=plain\n";
+    assert_eq!(
+        plaintext_keys(Path::new("app.ini"), ini, &re),
+        vec!["db.host"]
+    );
+
+    let yaml = "data:\n    list:\n        - ENC[x]\n        - plain\n    port: 5432\nother: value\npassword: null\n";
+    assert_eq!(
+        plaintext_keys(Path::new("k8s.yaml"), yaml, &re),
+        vec!["data.list[1]", "data.port"]
+    );
+
+    let json = r#"{"password": "ENC[x]", "nested": {"db": {"user": "root"}}, "sops": {"data": 1}}"#;
+    assert_eq!(
+        plaintext_keys(Path::new("app.json"), json, &re),
+        vec!["nested.db.user"]
+    );
+}
+
+#[test]
+fn test_doctor_finds_config_above_the_working_directory() {
+    let dir = temp_dir("doctor-cwd");
+    write(
+        &dir,
+        ".sops.yaml",
+        &format!("creation_rules:\n  - age: {}\n", ALICE),
+    );
+    write(
+        &dir,
+        "api/app.env",
+        &format!(
+            "password=ENC[x]\nsops_age__list_0__map_recipient={}\n",
+            ALICE
+        ),
+    );
+
+    // `agesmith doctor` sin argumentos desde el subdirectorio: la raíz es `.`
+    let output = Command::new(env!("CARGO_BIN_EXE_agesmith"))
+        .arg("doctor")
+        .arg("--json")
+        .current_dir(dir.join("api"))
+        .env("HOME", &dir)
+        .env_remove("XDG_CONFIG_HOME")
+        .env_remove("SOPS_AGE_KEY_FILE")
+        .output()
+        .unwrap();
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["sops_files"], 1);
+    assert_eq!(report["findings"], serde_json::json!([]));
+    assert!(output.status.success());
+
+    fs::remove_dir_all(&dir).ok();
+}
@@ -1,70 +1,64 @@
 /// Tests para el generador de secretos
+#[allow(dead_code)]
+#[path = "../src/generator.rs"]
+mod generator;
+#[allow(dead_code)]
+#[path = "../src/perms.rs"]
+mod perms;
+
+use base64::{engine::general_purpose, Engine as _};
+use generator::{generate_password, generate_token, random_bytes, PasswordPolicy, TokenFormat};
+use regex::Regex;
+use std::collections::{HashMap, HashSet};
+
+/// Estadístico chi-cuadrado de los conteos observados contra una distribución uniforme
+fn chi_square(counts: &[usize]) -> f64 {
+    let total: usize = counts.iter().sum();
+    let expected = total as f64 / counts.len() as f64;
+    counts
+        .iter()
+        .map(|&c| (c as f64 - expected).powi(2) / expected)
+        .sum()
+}
 
 #[test]
 fn test_password_generation() {
     println!("\n=== Testing password generation ===\n");
-    
-    use rand::Rng;
-    let mut rng = rand::thread_rng();
-    
-    let lengths = vec![8, 16, 32, 64];
-    let chars = "abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789";
-    
-    for length in lengths {
-        let password: String = (0..length)
-            .map(|_| {
-                let idx = rng.gen_range(0..chars.len());
-                chars.chars().nth(idx).unwrap()
-            })
-            .collect();
-            
+
+    for length in [8, 16, 32, 64] {
+        let password = generate_password(length, false, true);
         println!("Length {}: {}", length, password);
-        
+
         assert_eq!(password.len(), length, "Password length mismatch");
-        assert!(password.chars().all(|c| c.is_alphanumeric()), "Should only contain alphanumeric");
+        assert!(
+            password.chars().all(|c| c.is_ascii_alphanumeric()),
+            "Should only contain alphanumeric"
+        );
     }
 }
 
 #[test]
 fn test_password_with_special_chars() {
     println!("\n=== Testing password with special characters ===\n");
-    
-    use rand::Rng;
-    let mut rng = rand::thread_rng();
-    let chars = "abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789!@#$%^&*()";
-    
+
     for _ in 0..10 {
-        let password: String = (0..32)
-            .map(|_| {
-                let idx = rng.gen_range(0..chars.len());
-                chars.chars().nth(idx).unwrap()
-            })
-            .collect();
-            
+        let password = generate_password(32, true, true);
         println!("Generated: {}", password);
         assert_eq!(password.len(), 32);
+        assert!(password.chars().any(|c| !c.is_ascii_alphanumeric()));
     }
 }
 
 #[test]
 fn test_hex_generation() {
     println!("\n=== Testing hex generation ===\n");
-    
-    use rand::Rng;
-    use regex::Regex;
-    let mut rng = rand::thread_rng();
+
     let hex_regex = Regex::new(r"^[0-9a-f]+$").unwrap();
-    
-    let lengths = vec![16, 32, 64];
-    
-    for length in lengths {
-        let hex: String = (0..length)
-            .map(|_| format!("{:x}", rng.gen_range(0..16)))
-            .collect();
-            
-        println!("Length {}: {}", length, hex);
-        
-        assert_eq!(hex.len(), length);
+    for bytes in [16, 32, 64] {
+        let hex = generate_token(TokenFormat::Hex, bytes);
+        println!("Bytes {}: {}", bytes, hex);
+
+        assert_eq!(hex.len(), bytes * 2);
         assert!(hex_regex.is_match(&hex), "Should be valid hex");
     }
 }
@@ -72,93 +66,161 @@ fn test_hex_generation() {
 #[test]
 fn test_base64_generation() {
     println!("\n=== Testing base64 generation ===\n");
-    
-    use base64::{Engine as _, engine::general_purpose};
-    use rand::Rng;
-    use regex::Regex;
-    
-    let mut rng = rand::thread_rng();
-    let lengths = vec![16, 32, 64];
+
     let base64_regex = Regex::new(r"^[A-Za-z0-9+/]+=*$").unwrap();
-    
-    for length in lengths {
-        let bytes: Vec<u8> = (0..length).map(|_| rng.gen()).collect();
-        let b64 = general_purpose::STANDARD.encode(&bytes);
-        
-        println!("Length {}: {}", length, b64);
+    for bytes in [16, 32, 64] {
+        let b64 = generate_token(TokenFormat::Base64, bytes);
+        println!("Bytes {}: {}", bytes, b64);
+
         assert!(base64_regex.is_match(&b64), "Should be valid base64");
+        assert_eq!(general_purpose::STANDARD.decode(&b64).unwrap().len(), bytes);
     }
 }
 
 #[test]
 fn test_uuid_generation() {
     println!("\n=== Testing UUID generation ===\n");
-    
-    use regex::Regex;
-    let uuid_regex = Regex::new(r"^[0-9a-f]{8}-[0-9a-f]{4}-4[0-9a-f]{3}-[89ab][0-9a-f]{3}-[0-9a-f]{12}$").unwrap();
-    
-    for i in 0..5 {
-        let uuid = uuid::Uuid::new_v4().to_string();
-        println!("UUID {}: {}", i + 1, uuid);
-        
-        assert!(uuid_regex.is_match(&uuid), "Should be valid UUIDv4");
+
+    let uuid_regex =
+        Regex::new(r"^[0-9a-f]{8}-[0-9a-f]{4}-4[0-9a-f]{3}-[89ab][0-9a-f]{3}-[0-9a-f]{12}$")
+            .unwrap();
+    for i in 0..100 {
+        let uuid = generate_token(TokenFormat::Uuid, 0);
+        if i < 5 {
+            println!("UUID {}: {}", i + 1, uuid);
+        }
+        assert!(
+            uuid_regex.is_match(&uuid),
+            "Should be valid UUIDv4: {}",
+            uuid
+        );
     }
 }
 
 #[test]
 fn test_uniqueness() {
     println!("\n=== Testing uniqueness ===\n");
-    
-    use rand::Rng;
-    let mut rng = rand::thread_rng();
-    let chars = "abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789!@#$%^&*()";
-    
-    let mut passwords = std::collections::HashSet::new();
-    let mut uuids = std::collections::HashSet::new();
-    
+
+    let mut passwords = HashSet::new();
+    let mut uuids = HashSet::new();
+
     // Generar 100 de cada y verificar que son únicos
     for _ in 0..100 {
-        let password: String = (0..32)
-            .map(|_| {
-                let idx = rng.gen_range(0..chars.len());
-                chars.chars().nth(idx).unwrap()
-            })
-            .collect();
-        passwords.insert(password);
-        uuids.insert(uuid::Uuid::new_v4().to_string());
+        passwords.insert(generate_password(32, true, true));
+        uuids.insert(generate_token(TokenFormat::Uuid, 0));
     }
-    
+
     assert_eq!(passwords.len(), 100, "All passwords should be unique");
     assert_eq!(uuids.len(), 100, "All UUIDs should be unique");
-    
-    println!("✅ Generated 100 unique passwords");
-    println!("✅ Generated 100 unique UUIDs");
 }
 
 #[test]
 fn test_password_strength() {
     println!("\n=== Testing password strength ===\n");
-    
-    use rand::Rng;
-    let mut rng = rand::thread_rng();
-    let chars = "abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789!@#$%^&*()";
-    
-    for _ in 0..10 {
-        let password: String = (0..16)
-            .map(|_| {
-                let idx = rng.gen_range(0..chars.len());
-                chars.chars().nth(idx).unwrap()
-            })
-            .collect();
-        
-        let has_lower = password.chars().any(|c| c.is_lowercase());
-        let has_upper = password.chars().any(|c| c.is_uppercase());
-        let has_digit = password.chars().any(|c| c.is_numeric());
-        
-        println!("Password: {} (lower:{} upper:{} digit:{})", 
-                 password, has_lower, has_upper, has_digit);
-        
-        // Con 16 caracteres, probabilísticamente debería tener variedad
-        assert!(has_lower || has_upper || has_digit, "Should have character variety");
+
+    for _ in 0..100 {
+        let password = generate_password(16, true, true);
+        let has_lower = password.chars().any(|c| c.is_ascii_lowercase());
+        let has_upper = password.chars().any(|c| c.is_ascii_uppercase());
+        let has_digit = password.chars().any(|c| c.is_ascii_digit());
+
+        // Las clases habilitadas están garantizadas, no solo probables
+        assert!(
+            has_lower && has_upper && has_digit,
+            "Missing class in {}",
+            password
+        );
+    }
+}
+
+#[test]
+fn test_characters_are_uniformly_distributed() {
+    // Sin mínimos por clase cada carácter del alfabeto debe ser igual de probable
+    let policy = PasswordPolicy {
+        length: 62 * 1000,
+        symbols: false,
+        min_lowercase: 0,
+        min_uppercase: 0,
+        min_digits: 0,
+        min_symbols: 0,
+        ..Default::default()
+    };
+    let alphabet = policy.alphabet();
+    assert_eq!(alphabet.len(), 62);
+
+    let mut counts: HashMap<char, usize> = alphabet.iter().map(|c| (*c, 0)).collect();
+    for c in policy.generate().unwrap().chars() {
+        *counts.get_mut(&c).expect("Character outside alphabet") += 1;
+    }
+    let counts: Vec<usize> = counts.into_values().collect();
+
+    // 61 grados de libertad: el valor crítico para p = 1e-6 ronda 120
+    let chi = chi_square(&counts);
+    assert!(
+        chi < 130.0,
+        "Character distribution is biased (χ² = {:.1})",
+        chi
+    );
+}
+
+#[test]
+fn test_random_bytes_are_uniform() {
+    let mut counts = [0usize; 256];
+    for byte in random_bytes(256 * 400) {
+        counts[byte as usize] += 1;
+    }
+
+    // 255 grados de libertad: el valor crítico para p = 1e-6 ronda 370
+    let chi = chi_square(&counts);
+    assert!(chi < 400.0, "Byte distribution is biased (χ² = {:.1})", chi);
+}
+
+#[test]
+fn test_required_characters_are_shuffled() {
+    // Un único dígito obligatorio debe poder aparecer en cualquier posición
+    let policy = PasswordPolicy {
+        length: 8,
+        uppercase: false,
+        digits: true,
+        symbols: false,
+        min_lowercase: 0,
+        min_digits: 1,
+        alphabet: None,
+        ..Default::default()
+    };
+
+    let mut positions = [0usize; 8];
+    for _ in 0..8000 {
+        let password = policy.generate().unwrap();
+        for (i, c) in password.chars().enumerate() {
+            if c.is_ascii_digit() {
+                positions[i] += 1;
+            }
+        }
+    }
+
+    // 7 grados de libertad: el valor crítico para p = 1e-6 ronda 38
+    let chi = chi_square(&positions);
+    assert!(chi < 45.0, "Digit positions are biased: {:?}", positions);
+}
+
+#[test]
+fn test_class_minimums_hold_for_every_sample() {
+    let policy = PasswordPolicy {
+        length: 12,
+        min_lowercase: 2,
+        min_uppercase: 2,
+        min_digits: 3,
+        min_symbols: 3,
+        ..Default::default()
+    };
+
+    for _ in 0..2000 {
+        let password = policy.generate().unwrap();
+        let count = |f: fn(&char) -> bool| password.chars().filter(f).count();
+        assert!(count(char::is_ascii_lowercase) >= 2, "{}", password);
+        assert!(count(char::is_ascii_uppercase) >= 2, "{}", password);
+        assert!(count(char::is_ascii_digit) >= 3, "{}", password);
+        assert!(count(char::is_ascii_punctuation) >= 3, "{}", password);
     }
 }
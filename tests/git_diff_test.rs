@@ -0,0 +1,65 @@
+/// Tests para el diff de líneas usado por `agesmith diff-driver`
+#[allow(dead_code)]
+#[path = "../src/diff.rs"]
+mod diff;
+
+use diff::{diff_lines, unified_diff, DiffLine};
+
+fn lines(text: &str) -> Vec<String> {
+    text.lines().map(String::from).collect()
+}
+
+#[test]
+fn test_identical_inputs_produce_no_diff() {
+    let old = lines("API_KEY: abc\nDB_PASSWORD: secret");
+    assert_eq!(unified_diff("a/x", "b/x", &old, &old, 3), "");
+}
+
+#[test]
+fn test_changed_value_is_removed_and_added() {
+    let old = lines("API_KEY: abc\nDB_PASSWORD: old\nPORT: 5432");
+    let new = lines("API_KEY: abc\nDB_PASSWORD: new\nPORT: 5432");
+
+    let ops = diff_lines(&old, &new);
+    assert_eq!(
+        ops,
+        vec![
+            DiffLine::Same("API_KEY: abc".to_string()),
+            DiffLine::Removed("DB_PASSWORD: old".to_string()),
+            DiffLine::Added("DB_PASSWORD: new".to_string()),
+            DiffLine::Same("PORT: 5432".to_string()),
+        ]
+    );
+
+    let out = unified_diff("a/secrets.yaml", "b/secrets.yaml", &old, &new, 3);
+    println!("{}", out);
+    assert!(out.starts_with("--- a/secrets.yaml\n+++ b/secrets.yaml\n"));
+    assert!(out.contains("@@ -1,3 +1,3 @@"));
+    assert!(out.contains("-DB_PASSWORD: old\n"));
+    assert!(out.contains("+DB_PASSWORD: new\n"));
+}
+
+#[test]
+fn test_distant_changes_produce_separate_hunks() {
+    let old: Vec<String> = (0..20).map(|i| format!("KEY_{}: v", i)).collect();
+    let mut new = old.clone();
+    new[1] = "KEY_1: changed".to_string();
+    new[18] = "KEY_18: changed".to_string();
+
+    let out = unified_diff("a/f", "b/f", &old, &new, 2);
+    assert_eq!(
+        out.matches("@@ ").count(),
+        2,
+        "Expected two hunks:\n{}",
+        out
+    );
+    assert!(!out.contains("KEY_10"), "Unrelated lines must not be shown");
+}
+
+#[test]
+fn test_added_file_diff_against_empty() {
+    let new = lines("A: 1\nB: 2");
+    let out = unified_diff("a/f", "b/f", &[], &new, 3);
+    assert!(out.contains("@@ -0,0 +1,2 @@"));
+    assert!(out.contains("+A: 1\n+B: 2\n"));
+}
@@ -0,0 +1,83 @@
+/// Tests para el historial git por llave
+#[allow(dead_code)]
+#[path = "../src/config.rs"]
+mod config;
+#[allow(dead_code)]
+#[path = "../src/diff.rs"]
+mod diff;
+#[allow(dead_code)]
+#[path = "../src/generator.rs"]
+mod generator;
+#[allow(dead_code)]
+#[path = "../src/git.rs"]
+mod git;
+#[allow(dead_code)]
+#[path = "../src/perms.rs"]
+mod perms;
+#[allow(dead_code)]
+#[path = "../src/sops.rs"]
+mod sops;
+
+#[allow(dead_code)]
+mod helpers;
+
+use git::{file_history, parse_log, KeyChange};
+use helpers::temp_dir;
+use std::{fs, path::Path, process::Command};
+
+fn git(dir: &Path, args: &[&str]) {
+    let status = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(["-c", "user.name=Test", "-c", "user.email=test@example.com"])
+        .args(args)
+        .output()
+        .unwrap()
+        .status;
+    assert!(status.success(), "git {:?}", args);
+}
+
+#[test]
+fn test_parse_log_handles_renames_and_deletions() {
+    let log = "\x1ec3\x1fAna\x1f2026-03-01\n\nD\tprod/app.env\n\
+               \x1eb2\x1fAna\x1f2026-02-01\n\nR100\tapp.env\tprod/app.env\n\
+               \x1ea1\x1fLuis\x1f2026-01-01\n\nA\tapp.env\n";
+    let revisions = parse_log(log);
+
+    let summary: Vec<(&str, &str, bool)> = revisions
+        .iter()
+        .map(|r| (r.commit.as_str(), r.path.as_str(), r.deleted))
+        .collect();
+    assert_eq!(
+        summary,
+        vec![
+            ("a1", "app.env", false),
+            ("b2", "prod/app.env", false),
+            ("c3", "prod/app.env", true),
+        ]
+    );
+    assert_eq!(revisions[0].author, "Luis");
+}
+
+#[test]
+fn test_history_keeps_unreadable_and_deleted_revisions() {
+    let dir = temp_dir("history");
+    git(&dir, &["init", "-q"]);
+
+    // Un archivo que no es SOPS no se puede descifrar con ninguna llave
+    fs::write(dir.join("app.env"), "API_KEY=plain\n").unwrap();
+    git(&dir, &["add", "app.env"]);
+    git(&dir, &["commit", "-qm", "add"]);
+    git(&dir, &["rm", "-q", "app.env"]);
+    git(&dir, &["commit", "-qm", "remove"]);
+
+    let events = file_history(&dir.join("app.env"), None).unwrap();
+    let changes: Vec<(KeyChange, &str)> =
+        events.iter().map(|e| (e.change, e.key.as_str())).collect();
+    assert_eq!(
+        changes,
+        vec![(KeyChange::FileDeleted, ""), (KeyChange::Unreadable, "")]
+    );
+
+    fs::remove_dir_all(&dir).ok();
+}
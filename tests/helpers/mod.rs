@@ -1,6 +1,14 @@
 /// Test helpers and utilities
 use serde_json::Value;
-use std::collections::HashMap;
+use std::{collections::HashMap, fs, path::PathBuf};
+
+/// Directorio temporal vacío para un test: `agesmith-<name>-<pid>`
+pub fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("agesmith-{}-{}", name, std::process::id()));
+    fs::remove_dir_all(&dir).ok();
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
 
 /// Helper para crear secretos de prueba
 pub fn create_test_secrets() -> HashMap<String, String> {
@@ -0,0 +1,163 @@
+/// Tests para archivos de identidades age protegidos con frase
+#[allow(dead_code)]
+#[path = "../src/perms.rs"]
+mod perms;
+#[allow(dead_code)]
+#[path = "../src/sops.rs"]
+mod sops;
+
+#[allow(dead_code)]
+mod helpers;
+
+use age::secrecy::ExposeSecret;
+use helpers::temp_dir;
+use sops::{decrypt_identity_file, is_encrypted_identity_file, parse_age_keys, KeyEnv, KeySource};
+use std::{fs, io::Write, path::PathBuf};
+
+fn identity_file() -> (String, String) {
+    let identity = age::x25519::Identity::generate();
+    let secret = identity.to_string().expose_secret().to_string();
+    let content = format!(
+        "# created: 2026-01-01T00:00:00Z\n# public key: {}\n{}\n",
+        identity.to_public(),
+        secret
+    );
+    (secret, content)
+}
+
+/// Cifra como `age -p`, con un factor de trabajo bajo para que el test sea rápido
+fn encrypt_with_passphrase(plaintext: &str, passphrase: &str, armor: bool) -> Vec<u8> {
+    let mut recipient = age::scrypt::Recipient::new(passphrase.to_string().into());
+    recipient.set_work_factor(10);
+    let encryptor =
+        age::Encryptor::with_recipients(std::iter::once(&recipient as &dyn age::Recipient))
+            .unwrap();
+
+    let mut output = Vec::new();
+    let format = if armor {
+        age::armor::Format::AsciiArmor
+    } else {
+        age::armor::Format::Binary
+    };
+    let armored = age::armor::ArmoredWriter::wrap_output(&mut output, format).unwrap();
+    let mut writer = encryptor.wrap_output(armored).unwrap();
+    writer.write_all(plaintext.as_bytes()).unwrap();
+    writer.finish().unwrap().finish().unwrap();
+    output
+}
+
+#[test]
+fn test_parse_plaintext_identities() {
+    let (secret, content) = identity_file();
+    let content = format!("{}\n# work laptop\n{}\n", content, secret.to_lowercase());
+
+    let keys = parse_age_keys(&content, &KeySource::Env);
+    assert_eq!(keys.len(), 1, "Lowercase lines are not identities");
+    assert_eq!(keys[0].key, secret);
+    assert!(keys[0]
+        .comment
+        .as_deref()
+        .unwrap()
+        .starts_with("public key:"));
+}
+
+#[test]
+fn test_detects_encrypted_identity_files() {
+    let (_, content) = identity_file();
+    assert!(!is_encrypted_identity_file(content.as_bytes()));
+    assert!(is_encrypted_identity_file(&encrypt_with_passphrase(
+        &content, "pw", false
+    )));
+    assert!(is_encrypted_identity_file(&encrypt_with_passphrase(
+        &content, "pw", true
+    )));
+}
+
+#[test]
+fn test_decrypts_binary_and_armored_files() {
+    let (secret, content) = identity_file();
+
+    for armor in [false, true] {
+        let encrypted = encrypt_with_passphrase(&content, "correct horse", armor);
+        let keys = decrypt_identity_file(&encrypted, "correct horse", &KeySource::Env).unwrap();
+        assert_eq!(keys.len(), 1);
+        assert_eq!(keys[0].key, secret);
+    }
+}
+
+#[test]
+fn test_wrong_passphrase_is_rejected() {
+    let (_, content) = identity_file();
+    let encrypted = encrypt_with_passphrase(&content, "correct horse", true);
+    assert!(decrypt_identity_file(&encrypted, "wrong horse", &KeySource::Env).is_err());
+    assert!(decrypt_identity_file(&encrypted, "", &KeySource::Env).is_err());
+}
+
+#[test]
+fn test_files_encrypted_to_recipients_are_rejected() {
+    let identity = age::x25519::Identity::generate();
+    let recipient = identity.to_public();
+    let encryptor =
+        age::Encryptor::with_recipients(std::iter::once(&recipient as &dyn age::Recipient))
+            .unwrap();
+    let mut encrypted = Vec::new();
+    let mut writer = encryptor.wrap_output(&mut encrypted).unwrap();
+    writer.write_all(b"AGE-SECRET-KEY-1X").unwrap();
+    writer.finish().unwrap();
+
+    assert!(is_encrypted_identity_file(&encrypted));
+    assert!(decrypt_identity_file(&encrypted, "anything", &KeySource::Env).is_err());
+    assert!(decrypt_identity_file(b"not an age file", "anything", &KeySource::Env).is_err());
+}
+
+#[test]
+fn test_keys_are_loaded_from_every_source() {
+    let dir = temp_dir("identities");
+
+    let (env_secret, env_content) = identity_file();
+    let (main_secret, main_content) = identity_file();
+    let (extra_secret, extra_content) = identity_file();
+    let (_, locked_content) = identity_file();
+
+    let main_file = dir.join("keys.txt");
+    let extra_file = dir.join("extra.txt");
+    let locked_file = dir.join("locked.age");
+    fs::write(&main_file, format!("{}{}", main_content, env_secret)).unwrap();
+    fs::write(&extra_file, &extra_content).unwrap();
+    fs::write(
+        &locked_file,
+        encrypt_with_passphrase(&locked_content, "pw", true),
+    )
+    .unwrap();
+
+    // HOME apunta al directorio temporal para no cargar las llaves SSH del usuario
+    let env = KeyEnv {
+        home: Some(dir.clone()),
+        sops_age_key: Some(env_content.clone()),
+        sops_age_key_file: Some(main_file.clone()),
+        ..KeyEnv::default()
+    };
+    let extra = vec![
+        extra_file.display().to_string(),
+        locked_file.display().to_string(),
+        dir.join("missing.txt").display().to_string(),
+        main_file.display().to_string(),
+    ];
+    let keys = env.load_age_keys(&extra).unwrap();
+    let encrypted = env.encrypted_identity_files(&extra);
+
+    let sources: Vec<(&str, &KeySource)> =
+        keys.iter().map(|k| (k.key.as_str(), &k.source)).collect();
+    assert_eq!(
+        sources,
+        vec![
+            (env_secret.as_str(), &KeySource::Env),
+            (main_secret.as_str(), &KeySource::File(main_file.clone())),
+            (extra_secret.as_str(), &KeySource::File(extra_file.clone())),
+        ],
+        "Duplicates keep their first source and encrypted files are skipped"
+    );
+    assert_eq!(encrypted, vec![PathBuf::from(&locked_file)]);
+
+    fs::remove_dir_all(&dir).ok();
+}
@@ -0,0 +1,161 @@
+/// Tests para el borrado seguro de llaves: archivos dependientes, respaldo y escritura atómica
+#[allow(dead_code)]
+#[path = "../src/keystore.rs"]
+mod keystore;
+#[allow(dead_code)]
+#[path = "../src/perms.rs"]
+mod perms;
+#[allow(dead_code)]
+#[path = "../src/sops.rs"]
+mod sops;
+
+#[allow(dead_code)]
+mod helpers;
+
+use helpers::temp_dir;
+use keystore::{backup_path, files_depending_on, remove_identity};
+use perms::write_private_atomic;
+use sops::{parse_sops_recipients, AgeKey, KeySource};
+use std::{fs, path::Path};
+
+const ALICE: &str = "age1ql3z7hjy54pw3hyww5ayyfg7zqgvc7w3j2elw8zmrj2kg5sfn9aqmcac8p";
+const BOB: &str = "age1lggyhqrw2nlhcxprm67z43rta597azn8gknawjehu9d9dl0jq3yqqvfafg";
+
+fn key(secret: &str, public_key: &str) -> AgeKey {
+    AgeKey {
+        key: secret.to_string(),
+        comment: None,
+        public_key: Some(public_key.to_string()),
+        source: KeySource::Env,
+    }
+}
+
+fn sops_yaml(recipients: &[&str]) -> String {
+    let mut content = "password: ENC[AES256_GCM,data:abc]\nsops:\n    age:\n".to_string();
+    for r in recipients {
+        content.push_str(&format!(
+            "        - recipient: {}\n          enc: |\n            -----BEGIN AGE ENCRYPTED FILE-----\n",
+            r
+        ));
+    }
+    content
+}
+
+fn write(path: &Path, content: &str) {
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, content).unwrap();
+}
+
+#[test]
+fn test_parse_sops_recipients_in_every_format() {
+    let json = format!(
+        r#"{{"a": "ENC[x]", "sops": {{"age": [{{"recipient": "{}"}}]}}}}"#,
+        ALICE
+    );
+    assert_eq!(parse_sops_recipients(&json), vec![ALICE]);
+    assert_eq!(
+        parse_sops_recipients(&sops_yaml(&[ALICE, BOB])),
+        vec![ALICE, BOB]
+    );
+
+    let dotenv = format!(
+        "API=ENC[x]\nsops_age__list_0__map_recipient={}\nsops_age__list_1__map_recipient=\"{}\"\n",
+        ALICE, BOB
+    );
+    assert_eq!(parse_sops_recipients(&dotenv), vec![ALICE, BOB]);
+
+    let ini = format!("[sops]\nage__list_0__map_recipient = {}\n", ALICE);
+    assert_eq!(parse_sops_recipients(&ini), vec![ALICE]);
+
+    // Un secreto llamado "recipient" no es un recipient de SOPS
+    assert!(parse_sops_recipients("recipient: ENC[AES256_GCM,data:abc]\n").is_empty());
+}
+
+#[test]
+fn test_remove_identity_only_drops_its_block() {
+    let content = "# created: 2026-01-01\n# public key: age1a\nAGE-SECRET-KEY-1A\n\n\
+                   # created: 2026-01-02\n# public key: age1b\n# work\nAGE-SECRET-KEY-1B\n\n\
+                   # public key: age1c\nAGE-SECRET-KEY-1C\n";
+
+    let without_b = remove_identity(content, "AGE-SECRET-KEY-1B").unwrap();
+    assert_eq!(
+        without_b,
+        "# created: 2026-01-01\n# public key: age1a\nAGE-SECRET-KEY-1A\n\n\
+         # public key: age1c\nAGE-SECRET-KEY-1C\n"
+    );
+
+    let without_a = remove_identity(&without_b, "AGE-SECRET-KEY-1A").unwrap();
+    assert_eq!(without_a, "# public key: age1c\nAGE-SECRET-KEY-1C\n");
+    assert_eq!(
+        remove_identity(&without_a, "AGE-SECRET-KEY-1C").unwrap(),
+        ""
+    );
+    assert!(remove_identity(content, "AGE-SECRET-KEY-1Z").is_err());
+}
+
+#[test]
+fn test_files_depending_only_on_the_key() {
+    let dir = temp_dir("delete-scan");
+    let favorite = temp_dir("delete-favorite").join("prod.yaml");
+    write(&dir.join("alice-only.yaml"), &sops_yaml(&[ALICE]));
+    write(&dir.join("shared.yaml"), &sops_yaml(&[ALICE, BOB]));
+    write(&dir.join("nested/bob.yaml"), &sops_yaml(&[BOB]));
+    write(
+        &dir.join(".env"),
+        &format!("sops_age__list_0__map_recipient={}\n", ALICE),
+    );
+    write(&dir.join(".git/config.yaml"), &sops_yaml(&[ALICE]));
+    write(&dir.join("plain.txt"), "nothing to see\n");
+    write(&favorite, &sops_yaml(&[ALICE]));
+    // Un enlace a un directorio ancestro no se recorre
+    std::os::unix::fs::symlink(&dir, dir.join("nested/loop")).unwrap();
+
+    let alice = key("AGE-SECRET-KEY-1ALICE", ALICE);
+    let bob = key("AGE-SECRET-KEY-1BOB", BOB);
+    let roots = vec![dir.clone(), favorite.clone(), dir.join("alice-only.yaml")];
+
+    let dependents = files_depending_on(&alice, &[alice.clone(), bob.clone()], &roots);
+    assert_eq!(
+        dependents,
+        vec![
+            favorite.clone(),
+            dir.join(".env"),
+            dir.join("alice-only.yaml")
+        ]
+    );
+
+    // Sin otras llaves propias, también depende de ella el archivo compartido
+    let dependents = files_depending_on(
+        &alice,
+        std::slice::from_ref(&alice),
+        std::slice::from_ref(&dir),
+    );
+    assert!(dependents.contains(&dir.join("shared.yaml")));
+
+    fs::remove_dir_all(&dir).ok();
+    fs::remove_dir_all(favorite.parent().unwrap()).ok();
+}
+
+#[test]
+fn test_atomic_write_and_backup_path() {
+    let dir = temp_dir("delete-atomic");
+    let keys = dir.join("keys.txt");
+    fs::write(&keys, "old").unwrap();
+
+    write_private_atomic(&keys, b"new").unwrap();
+    assert_eq!(fs::read_to_string(&keys).unwrap(), "new");
+    assert!(!dir.join(".keys.txt.tmp").exists());
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = fs::metadata(&keys).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600, "The replaced file is private");
+    }
+
+    assert_eq!(
+        backup_path(&keys, "20260101-120000"),
+        dir.join("backups/keys-20260101-120000.age")
+    );
+
+    fs::remove_dir_all(&dir).ok();
+}
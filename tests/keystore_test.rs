@@ -0,0 +1,210 @@
+/// Tests para importar, exportar y respaldar identidades desde el gestor de llaves
+#[allow(dead_code)]
+#[path = "../src/keystore.rs"]
+mod keystore;
+#[allow(dead_code)]
+#[path = "../src/perms.rs"]
+mod perms;
+#[allow(dead_code)]
+#[path = "../src/sops.rs"]
+mod sops;
+
+#[allow(dead_code)]
+mod helpers;
+
+use age::secrecy::ExposeSecret;
+use helpers::temp_dir;
+use keystore::{
+    append_identities, bundle_identity_files, encrypt_to_recipients, encrypt_with_passphrase,
+    identity_block, new_identities, parse_identities, set_comment,
+};
+use perms::write_private;
+use sops::{decrypt_identity_file, parse_age_keys, KeySource};
+use std::{fs, io::Read, process::Command};
+
+fn keygen_output(comment: Option<&str>) -> (age::x25519::Identity, String) {
+    let identity = age::x25519::Identity::generate();
+    let comment = comment.map(|c| format!("# {}\n", c)).unwrap_or_default();
+    let content = format!(
+        "# created: 2026-01-01T00:00:00Z\n# public key: {}\n{}{}\n",
+        identity.to_public(),
+        comment,
+        identity.to_string().expose_secret()
+    );
+    (identity, content)
+}
+
+fn decrypt_with(identity: &dyn age::Identity, armored: &[u8]) -> Vec<u8> {
+    let decryptor = age::Decryptor::new(age::armor::ArmoredReader::new(armored)).unwrap();
+    let mut reader = decryptor.decrypt(std::iter::once(identity)).unwrap();
+    let mut plaintext = Vec::new();
+    reader.read_to_end(&mut plaintext).unwrap();
+    plaintext
+}
+
+#[test]
+fn test_identity_block_round_trip() {
+    let (_, content) = keygen_output(None);
+    let mut key = parse_identities(&content).remove(0);
+    key.comment = Some("deploy".to_string());
+
+    let parsed = parse_age_keys(&identity_block(&key), &KeySource::Env);
+    assert_eq!(parsed.len(), 1);
+    assert_eq!(parsed[0].key, key.key);
+    assert_eq!(parsed[0].comment.as_deref(), Some("deploy"));
+    assert_eq!(parsed[0].public_key, key.public_key);
+}
+
+#[test]
+fn test_new_identities_skips_known_and_invalid() {
+    let (_, known) = keygen_output(Some("known"));
+    let (_, fresh) = keygen_output(Some("fresh"));
+    let existing = parse_identities(&known);
+
+    let pasted = format!(
+        "{}\n{}\n{}\nAGE-SECRET-KEY-1NOTAVALIDKEY\n",
+        known, fresh, fresh
+    );
+    let keys = new_identities(parse_identities(&pasted), &existing);
+    assert_eq!(
+        keys.len(),
+        1,
+        "Known, duplicated and invalid keys are dropped"
+    );
+    assert_eq!(keys[0].key, parse_identities(&fresh)[0].key);
+}
+
+#[test]
+fn test_set_comment_inserts_replaces_and_removes() {
+    let (identity, content) = keygen_output(None);
+    let secret = identity.to_string().expose_secret().to_string();
+
+    let named = set_comment(&content, &secret, "laptop").unwrap();
+    assert!(
+        named.contains("# public key: "),
+        "age-keygen lines are kept"
+    );
+    assert_eq!(
+        parse_identities(&named)[0].comment.as_deref(),
+        Some("laptop")
+    );
+
+    let renamed = set_comment(&named, &secret, "desktop").unwrap();
+    assert_eq!(renamed.lines().count(), named.lines().count());
+    assert_eq!(
+        parse_identities(&renamed)[0].comment.as_deref(),
+        Some("desktop")
+    );
+
+    let removed = set_comment(&renamed, &secret, "").unwrap();
+    assert_eq!(removed, content);
+    assert!(set_comment(&content, "AGE-SECRET-KEY-1OTHER", "x").is_err());
+}
+
+#[test]
+fn test_append_identities_writes_private_file() {
+    let dir = temp_dir("keystore-append");
+    let path = dir.join("keys.txt");
+    let (_, first) = keygen_output(Some("first"));
+    let (_, second) = keygen_output(Some("second"));
+    fs::write(&path, &first).unwrap();
+
+    append_identities(&path, &parse_identities(&second)).unwrap();
+    let keys = parse_identities(&fs::read_to_string(&path).unwrap());
+    let comments: Vec<_> = keys.iter().map(|k| k.comment.as_deref()).collect();
+    assert!(comments.contains(&Some("second")));
+    assert_eq!(keys.len(), 2);
+
+    let exported = dir.join("exported.txt");
+    write_private(&exported, b"secret").unwrap();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = fs::metadata(&exported).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+
+    fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn test_export_with_passphrase_can_be_reimported() {
+    let (_, content) = keygen_output(Some("exported"));
+    let key = parse_identities(&content).remove(0);
+
+    let encrypted = encrypt_with_passphrase(identity_block(&key).as_bytes(), "hunter2").unwrap();
+    assert!(encrypted.starts_with(b"-----BEGIN AGE ENCRYPTED FILE-----"));
+
+    let keys = decrypt_identity_file(&encrypted, "hunter2", &KeySource::Env).unwrap();
+    assert_eq!(keys[0].key, key.key);
+    assert!(decrypt_identity_file(&encrypted, "wrong", &KeySource::Env).is_err());
+}
+
+#[test]
+fn test_backup_to_age_and_ssh_recipients() {
+    let age_identity = age::x25519::Identity::generate();
+    let mut recipients = vec![age_identity.to_public().to_string()];
+
+    // La parte SSH solo se comprueba si ssh-keygen está instalado
+    let dir = temp_dir("keystore-backup");
+    let ssh_path = dir.join("id_ed25519");
+    let has_ssh = Command::new("ssh-keygen")
+        .args(["-t", "ed25519", "-N", "", "-q", "-f"])
+        .arg(&ssh_path)
+        .status()
+        .map(|s| s.success())
+        .unwrap_or(false);
+    if has_ssh {
+        let public = fs::read_to_string(ssh_path.with_extension("pub")).unwrap();
+        recipients.push(public.trim().to_string());
+    }
+
+    let encrypted = encrypt_to_recipients(b"keys.txt", &recipients).unwrap();
+    assert_eq!(decrypt_with(&age_identity, &encrypted), b"keys.txt");
+    if has_ssh {
+        let private = fs::read(&ssh_path).unwrap();
+        let ssh_identity = age::ssh::Identity::from_buffer(&private[..], None).unwrap();
+        assert_eq!(decrypt_with(&ssh_identity, &encrypted), b"keys.txt");
+    }
+
+    assert!(encrypt_to_recipients(b"x", &[]).is_err());
+    assert!(encrypt_to_recipients(b"x", &["bob".to_string()]).is_err());
+
+    fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn test_backup_bundles_every_plaintext_identity_file() {
+    let dir = temp_dir("keystore-bundle");
+    let (first, first_content) = keygen_output(Some("main"));
+    let (second, second_content) = keygen_output(None);
+    let keys = dir.join("keys.txt");
+    let extra = dir.join("extra.txt");
+    let locked = dir.join("locked.age");
+    fs::write(&keys, &first_content).unwrap();
+    fs::write(&extra, &second_content).unwrap();
+    fs::write(
+        &locked,
+        encrypt_with_passphrase(first_content.as_bytes(), "hunter2").unwrap(),
+    )
+    .unwrap();
+
+    let paths = vec![keys, dir.join("missing.txt"), locked, extra.clone()];
+    let bundle = bundle_identity_files(&paths).unwrap();
+    assert!(bundle.contains(&format!("# {}", extra.display())));
+
+    let restored: Vec<String> = parse_age_keys(&bundle, &KeySource::Env)
+        .into_iter()
+        .map(|k| k.key)
+        .collect();
+    assert_eq!(
+        restored,
+        vec![
+            first.to_string().expose_secret().to_string(),
+            second.to_string().expose_secret().to_string(),
+        ]
+    );
+
+    assert!(bundle_identity_files(&[dir.join("missing.txt")]).is_err());
+    fs::remove_dir_all(&dir).ok();
+}
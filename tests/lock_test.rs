@@ -0,0 +1,118 @@
+/// Tests para la autenticación de la pantalla de bloqueo
+#[allow(dead_code)]
+#[path = "../src/lock.rs"]
+mod lock;
+#[allow(dead_code)]
+#[path = "../src/perms.rs"]
+mod perms;
+#[allow(dead_code)]
+#[path = "../src/sops.rs"]
+mod sops;
+
+use age::secrecy::ExposeSecret;
+use base64::{engine::general_purpose, Engine as _};
+use lock::{hash_passphrase, verify_passphrase, Challenge};
+use sops::{AgeKey, KeySource};
+use std::io::Write;
+
+fn identity() -> (String, AgeKey) {
+    let identity = age::x25519::Identity::generate();
+    let secret = identity.to_string().expose_secret().to_string();
+    let key = AgeKey {
+        key: secret.clone(),
+        comment: None,
+        public_key: Some(identity.to_public().to_string()),
+        source: KeySource::Env,
+    };
+    (secret, key)
+}
+
+#[test]
+fn test_passphrase_hash_roundtrip() {
+    let hash = hash_passphrase("correct horse battery staple").unwrap();
+    assert!(hash.starts_with("age-scrypt$"));
+    assert!(
+        !hash.contains("correct"),
+        "Hash must not contain the passphrase"
+    );
+
+    assert!(verify_passphrase("correct horse battery staple", &hash));
+    assert!(!verify_passphrase("correct horse battery stapler", &hash));
+    assert!(!verify_passphrase("", &hash));
+}
+
+#[test]
+fn test_passphrase_hash_is_salted() {
+    assert_ne!(
+        hash_passphrase("same").unwrap(),
+        hash_passphrase("same").unwrap()
+    );
+}
+
+#[test]
+fn test_malformed_hash_never_verifies() {
+    for stored in [
+        "",
+        "sha256$",
+        "md5$1$00$00",
+        "sha256$x$00$00",
+        "sha256$1$zz$00",
+        "age-scrypt$",
+        "age-scrypt$not base64",
+        "age-scrypt$YWdlc21pdGg=",
+    ] {
+        assert!(!verify_passphrase("", stored), "{}", stored);
+    }
+}
+
+#[test]
+fn test_hash_must_be_passphrase_encrypted() {
+    // Un reto cifrado a un recipient (no con frase) nunca se acepta como frase
+    let recipient = age::x25519::Identity::generate().to_public();
+    let encryptor = age::Encryptor::with_recipients(std::iter::once(&recipient as _)).unwrap();
+    let mut ciphertext = Vec::new();
+    let mut writer = encryptor.wrap_output(&mut ciphertext).unwrap();
+    writer.write_all(b"agesmith").unwrap();
+    writer.finish().unwrap();
+
+    let stored = format!(
+        "age-scrypt${}",
+        general_purpose::STANDARD.encode(ciphertext)
+    );
+    assert!(!verify_passphrase("", &stored));
+}
+
+#[test]
+fn test_challenge_requires_matching_identity() {
+    let (secret, key) = identity();
+    let (other_secret, _) = identity();
+
+    let challenge = Challenge::new(&[key]).unwrap().expect("Challenge");
+    assert!(challenge.verify(&secret));
+    assert!(challenge.verify(&format!("  {}\n", secret)));
+    assert!(!challenge.verify(&other_secret));
+    assert!(!challenge.verify("AGE-SECRET-KEY-1INVALID"));
+}
+
+#[test]
+fn test_challenge_accepts_any_of_several_identities() {
+    let (first_secret, first) = identity();
+    let (second_secret, second) = identity();
+
+    let challenge = Challenge::new(&[first, second]).unwrap().unwrap();
+    assert!(challenge.verify(&first_secret));
+    assert!(challenge.verify(&second_secret));
+}
+
+#[test]
+fn test_no_identities_means_no_challenge() {
+    assert!(Challenge::new(&[]).unwrap().is_none());
+
+    let invalid = AgeKey {
+        key: "not a key".to_string(),
+        comment: None,
+        public_key: None,
+        source: KeySource::Env,
+    };
+    assert!(Challenge::new(&[invalid]).unwrap().is_none());
+}
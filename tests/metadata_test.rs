@@ -0,0 +1,206 @@
+/// Tests para los metadatos de expiración/rotación y `agesmith check-expiry`
+#[allow(dead_code)]
+#[path = "../src/generator.rs"]
+mod generator;
+#[allow(dead_code)]
+#[path = "../src/metadata.rs"]
+mod metadata;
+#[allow(dead_code)]
+#[path = "../src/perms.rs"]
+mod perms;
+
+#[allow(dead_code)]
+mod helpers;
+
+use chrono::{NaiveDate, TimeZone, Utc};
+use generator::{GeneratorPolicy, TokenFormat};
+use helpers::temp_dir;
+use metadata::{
+    load_metadata, save_metadata, scan_expiry, sidecar_path, ExpiryStatus, FileMetadata, SecretMeta,
+};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(y, m, d).unwrap()
+}
+
+#[test]
+fn test_sidecar_path() {
+    assert_eq!(
+        sidecar_path(Path::new("/repo/secrets.yaml")),
+        PathBuf::from("/repo/secrets.yaml.agesmith.toml")
+    );
+    assert_eq!(
+        sidecar_path(Path::new(".env")),
+        PathBuf::from(".env.agesmith.toml")
+    );
+}
+
+#[test]
+fn test_due_date_uses_earliest_of_expiry_and_rotation() {
+    let meta = SecretMeta {
+        rotated: Some(Utc.with_ymd_and_hms(2026, 1, 1, 12, 0, 0).unwrap()),
+        rotation_days: Some(30),
+        expires: Some(date(2026, 6, 1)),
+        ..Default::default()
+    };
+    assert_eq!(meta.due_date(), Some(date(2026, 1, 31)));
+
+    let only_expiry = SecretMeta {
+        expires: Some(date(2026, 6, 1)),
+        ..Default::default()
+    };
+    assert_eq!(only_expiry.due_date(), Some(date(2026, 6, 1)));
+
+    // Sin fecha de creación/rotación el periodo no se puede calcular
+    let only_period = SecretMeta {
+        rotation_days: Some(30),
+        ..Default::default()
+    };
+    assert_eq!(only_period.due_date(), None);
+
+    // Un periodo enorme no cabe en el calendario: sin fecha límite en vez de pánico
+    let overflow = SecretMeta {
+        rotated: Some(Utc.with_ymd_and_hms(2026, 1, 1, 12, 0, 0).unwrap()),
+        rotation_days: Some(u32::MAX),
+        expires: Some(date(2026, 6, 1)),
+        ..Default::default()
+    };
+    assert_eq!(overflow.due_date(), Some(date(2026, 6, 1)));
+    assert_eq!(overflow.status(date(2026, 1, 1), 14), ExpiryStatus::Ok);
+}
+
+#[test]
+fn test_status_thresholds() {
+    let meta = SecretMeta {
+        expires: Some(date(2026, 3, 15)),
+        ..Default::default()
+    };
+    assert_eq!(meta.status(date(2026, 3, 16), 14), ExpiryStatus::Expired);
+    assert_eq!(
+        meta.status(date(2026, 3, 15), 14),
+        ExpiryStatus::ExpiringSoon
+    );
+    assert_eq!(
+        meta.status(date(2026, 3, 1), 14),
+        ExpiryStatus::ExpiringSoon
+    );
+    assert_eq!(meta.status(date(2026, 2, 28), 14), ExpiryStatus::Ok);
+    assert_eq!(
+        SecretMeta::default().status(date(2026, 3, 1), 14),
+        ExpiryStatus::Ok
+    );
+}
+
+#[test]
+fn test_rename_and_remove_keep_metadata_in_sync() {
+    let mut metadata = FileMetadata::default();
+    metadata.mark_created("OLD_NAME");
+    metadata.entry("OLD_NAME").owner = Some("ops".to_string());
+
+    metadata.rename("OLD_NAME", "NEW_NAME");
+    assert!(metadata.get("OLD_NAME").is_none());
+    assert_eq!(
+        metadata.get("NEW_NAME").unwrap().owner.as_deref(),
+        Some("ops")
+    );
+
+    metadata.remove("NEW_NAME");
+    assert!(metadata.secrets.is_empty());
+}
+
+#[test]
+fn test_save_and_load_roundtrip() {
+    let dir = temp_dir("meta-roundtrip");
+    let file = dir.join("secrets.yaml");
+
+    let mut metadata = FileMetadata::default();
+    let meta = metadata.entry("DB_PASSWORD");
+    meta.owner = Some("db-team@example.com".to_string());
+    meta.expires = Some(date(2027, 1, 1));
+    meta.rotation_days = Some(90);
+    save_metadata(&file, &metadata).unwrap();
+
+    let content = fs::read_to_string(sidecar_path(&file)).unwrap();
+    assert!(content.contains("[secrets.DB_PASSWORD]"));
+    assert!(content.contains("expires = \"2027-01-01\""));
+
+    let loaded = load_metadata(&file).unwrap();
+    assert_eq!(loaded.get("DB_PASSWORD"), metadata.get("DB_PASSWORD"));
+
+    // Sin metadatos el sidecar se elimina
+    save_metadata(&file, &FileMetadata::default()).unwrap();
+    assert!(!sidecar_path(&file).exists());
+
+    fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn test_scan_expiry_finds_nested_sidecars() {
+    let dir = temp_dir("meta-scan");
+    fs::create_dir_all(dir.join("prod")).unwrap();
+    fs::create_dir_all(dir.join(".git")).unwrap();
+
+    let mut prod = FileMetadata::default();
+    prod.entry("EXPIRED_KEY").expires = Some(date(2026, 1, 1));
+    prod.entry("SOON_KEY").expires = Some(date(2026, 2, 5));
+    prod.entry("FINE_KEY").expires = Some(date(2027, 1, 1));
+    save_metadata(&dir.join("prod/secrets.env"), &prod).unwrap();
+
+    let mut ignored = FileMetadata::default();
+    ignored.entry("IN_GIT_DIR").expires = Some(date(2020, 1, 1));
+    save_metadata(&dir.join(".git/secrets.env"), &ignored).unwrap();
+
+    // Un enlace a un directorio ancestro no se recorre
+    std::os::unix::fs::symlink(&dir, dir.join("prod/loop")).unwrap();
+
+    let findings = scan_expiry(&dir, date(2026, 2, 1), 14).unwrap();
+    assert_eq!(findings.len(), 2);
+    assert_eq!(findings[0].key, "EXPIRED_KEY");
+    assert_eq!(findings[0].status, ExpiryStatus::Expired);
+    assert_eq!(findings[0].file, dir.join("prod/secrets.env"));
+    assert_eq!(findings[1].key, "SOON_KEY");
+    assert_eq!(findings[1].status, ExpiryStatus::ExpiringSoon);
+
+    fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn test_generator_policy_roundtrip() {
+    let dir = temp_dir("meta-policy");
+    let file = dir.join("secrets.env");
+
+    let mut metadata = FileMetadata::default();
+    metadata.mark_created("API_TOKEN");
+    metadata.entry("API_TOKEN").policy = Some(GeneratorPolicy::Token {
+        format: TokenFormat::Base64,
+        length: 40,
+    });
+    metadata.entry("DB_PASSWORD").policy = Some(GeneratorPolicy::Password {
+        length: 24,
+        use_special: false,
+        use_numbers: true,
+    });
+    save_metadata(&file, &metadata).unwrap();
+
+    let content = fs::read_to_string(sidecar_path(&file)).unwrap();
+    assert!(content.contains("kind = \"token\""));
+    assert!(content.contains("format = \"base64\""));
+
+    let loaded = load_metadata(&file).unwrap();
+    assert_eq!(loaded.get("API_TOKEN"), metadata.get("API_TOKEN"));
+    assert_eq!(loaded.get("DB_PASSWORD"), metadata.get("DB_PASSWORD"));
+
+    let value = loaded
+        .get("DB_PASSWORD")
+        .unwrap()
+        .policy
+        .as_ref()
+        .unwrap()
+        .generate()
+        .unwrap();
+    assert_eq!(value.len(), 24);
+
+    fs::remove_dir_all(&dir).ok();
+}
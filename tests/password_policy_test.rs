@@ -0,0 +1,251 @@
+/// Tests para las políticas de contraseñas, frases de contraseña y la estimación de fortaleza
+#[allow(dead_code)]
+#[path = "../src/generator.rs"]
+mod generator;
+#[allow(dead_code)]
+#[path = "../src/perms.rs"]
+mod perms;
+
+use generator::{
+    builtin_policies, estimate_entropy, generate_password, wordlist, GeneratorPolicy,
+    PassphrasePolicy, PasswordPolicy, Strength, DEFAULT_SYMBOLS,
+};
+
+fn builtin(name: &str) -> PasswordPolicy {
+    builtin_policies()
+        .into_iter()
+        .find(|(n, _)| n == name)
+        .map(|(_, p)| p)
+        .unwrap()
+}
+
+#[test]
+fn test_every_class_is_present() {
+    let policy = PasswordPolicy {
+        length: 8,
+        min_symbols: 2,
+        min_digits: 2,
+        ..Default::default()
+    };
+
+    for _ in 0..500 {
+        let password = policy.generate().unwrap();
+        assert_eq!(password.chars().count(), 8);
+        assert!(password.chars().any(|c| c.is_ascii_lowercase()));
+        assert!(password.chars().any(|c| c.is_ascii_uppercase()));
+        assert!(password.chars().filter(|c| c.is_ascii_digit()).count() >= 2);
+        assert!(
+            password
+                .chars()
+                .filter(|c| DEFAULT_SYMBOLS.contains(*c))
+                .count()
+                >= 2
+        );
+    }
+}
+
+#[test]
+fn test_simple_generator_guarantees_enabled_classes() {
+    for _ in 0..500 {
+        let password = generate_password(8, true, true);
+        assert!(password.chars().any(|c| c.is_ascii_digit()), "{}", password);
+        assert!(
+            password.chars().any(|c| DEFAULT_SYMBOLS.contains(c)),
+            "{}",
+            password
+        );
+
+        let alnum = generate_password(8, false, false);
+        assert!(alnum.chars().all(|c| c.is_ascii_alphabetic()), "{}", alnum);
+    }
+}
+
+#[test]
+fn test_exclude_ambiguous_and_forbidden() {
+    let policy = PasswordPolicy {
+        length: 64,
+        exclude_ambiguous: true,
+        forbidden: "#$".to_string(),
+        ..Default::default()
+    };
+    let alphabet = policy.alphabet();
+    for c in "Il1O0o#$".chars() {
+        assert!(!alphabet.contains(&c), "{} should be excluded", c);
+    }
+
+    for _ in 0..100 {
+        let password = policy.generate().unwrap();
+        assert!(!password.chars().any(|c| "Il1O0o#$".contains(c)));
+    }
+}
+
+#[test]
+fn test_dotenv_and_url_safe_presets() {
+    let dotenv = builtin("dotenv-safe");
+    let url = builtin("url-safe");
+
+    for _ in 0..200 {
+        let value = dotenv.generate().unwrap();
+        assert!(
+            !value.chars().any(|c| "#\"'`$\\ =;".contains(c)),
+            "{}",
+            value
+        );
+
+        let value = url.generate().unwrap();
+        assert!(
+            value
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || "-._~".contains(c)),
+            "{}",
+            value
+        );
+    }
+}
+
+#[test]
+fn test_custom_alphabet() {
+    let policy = PasswordPolicy {
+        length: 32,
+        alphabet: Some("abc".to_string()),
+        ..Default::default()
+    };
+    let password = policy.generate().unwrap();
+    assert!(password.chars().all(|c| "abc".contains(c)));
+    assert!((policy.entropy_bits() - 32.0 * 3f64.log2()).abs() < 1e-9);
+}
+
+#[test]
+fn test_invalid_policies_are_rejected() {
+    let too_short = PasswordPolicy {
+        length: 3,
+        ..Default::default()
+    };
+    assert!(too_short.generate().is_err());
+
+    let empty = PasswordPolicy {
+        alphabet: Some("ab".to_string()),
+        forbidden: "ab".to_string(),
+        ..Default::default()
+    };
+    assert!(empty.generate().is_err());
+
+    // Una clase con mínimo que se queda sin caracteres no se descarta en silencio
+    let no_digits = PasswordPolicy {
+        min_digits: 2,
+        forbidden: "0123456789".to_string(),
+        ..Default::default()
+    };
+    assert!(no_digits.validate().is_err());
+    let no_ambiguous = PasswordPolicy {
+        min_uppercase: 1,
+        forbidden: "ABCDEFGHJKLMNPQRSTUVWXYZ".to_string(),
+        exclude_ambiguous: true,
+        ..Default::default()
+    };
+    assert!(no_ambiguous.generate().is_err());
+    // Sin mínimo, la clase vacía no impide generar
+    let optional = PasswordPolicy {
+        min_digits: 0,
+        forbidden: "0123456789".to_string(),
+        ..Default::default()
+    };
+    assert!(optional.generate().is_ok());
+}
+
+#[test]
+fn test_policy_from_toml() {
+    let policy: PasswordPolicy = toml::from_str(
+        r#"
+        length = 30
+        symbols = false
+        min_digits = 3
+        "#,
+    )
+    .unwrap();
+    assert_eq!(policy.length, 30);
+    assert!(!policy.symbols);
+    assert_eq!(policy.min_digits, 3);
+    assert!(policy.lowercase, "Missing fields use defaults");
+}
+
+#[test]
+fn test_strength_estimation() {
+    assert_eq!(
+        Strength::from_bits(estimate_entropy("aaaa")),
+        Strength::VeryWeak
+    );
+    assert_eq!(
+        Strength::from_bits(estimate_entropy("password")),
+        Strength::Weak
+    );
+    assert!(estimate_entropy("Tr0ub4dor&3xQ!zP9#mK") > 100.0);
+    assert_eq!(estimate_entropy(""), 0.0);
+    assert!(Strength::from_bits(20.0) < Strength::from_bits(200.0));
+}
+
+#[test]
+fn test_wordlist_is_unique() {
+    let mut words = wordlist();
+    assert_eq!(words.len(), 2048);
+    assert!(words
+        .iter()
+        .all(|w| w.chars().all(|c| c.is_ascii_lowercase())));
+    words.sort_unstable();
+    words.dedup();
+    assert_eq!(words.len(), 2048);
+}
+
+#[test]
+fn test_passphrase_generation() {
+    let list = wordlist();
+    let policy = PassphrasePolicy {
+        words: 5,
+        separator: ".".to_string(),
+        ..Default::default()
+    };
+    let phrase = policy.generate().unwrap();
+    let words: Vec<&str> = phrase.split('.').collect();
+    assert_eq!(words.len(), 5);
+    assert!(words.iter().all(|w| list.contains(w)), "{}", phrase);
+    assert!((policy.entropy_bits() - 55.0).abs() < 1e-9);
+
+    let fancy = PassphrasePolicy {
+        words: 4,
+        separator: " ".to_string(),
+        capitalize: true,
+        digit: true,
+    };
+    for _ in 0..50 {
+        let phrase = fancy.generate().unwrap();
+        let words: Vec<&str> = phrase.split(' ').collect();
+        assert_eq!(words.len(), 4);
+        assert!(words
+            .iter()
+            .all(|w| w.starts_with(|c: char| c.is_ascii_uppercase())));
+        assert_eq!(phrase.chars().filter(|c| c.is_ascii_digit()).count(), 1);
+    }
+    assert!(fancy.entropy_bits() > 44.0);
+
+    assert!(PassphrasePolicy {
+        words: 0,
+        ..Default::default()
+    }
+    .generate()
+    .is_err());
+}
+
+#[test]
+fn test_passphrase_policy_serializes_with_kind() {
+    let policy = GeneratorPolicy::Passphrase(PassphrasePolicy {
+        words: 7,
+        separator: "_".to_string(),
+        capitalize: true,
+        digit: false,
+    });
+    let content = toml::to_string(&policy).unwrap();
+    assert!(content.contains("kind = \"passphrase\""), "{}", content);
+
+    let parsed: GeneratorPolicy = toml::from_str(&content).unwrap();
+    assert_eq!(parsed, policy);
+}
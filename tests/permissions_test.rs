@@ -0,0 +1,121 @@
+#![cfg(unix)]
+/// Tests para los permisos de keys.txt, su directorio y los archivos de configuración
+#[allow(dead_code)]
+#[path = "../src/perms.rs"]
+mod perms;
+#[allow(dead_code)]
+#[path = "../src/sops.rs"]
+mod sops;
+
+#[allow(dead_code)]
+mod helpers;
+
+use helpers::temp_dir;
+use perms::{
+    append_private, create_private_dir, insecure_mode, restrict_permissions, write_private,
+};
+use sops::KeyEnv;
+use std::{fs, os::unix::fs::PermissionsExt, path::Path};
+
+fn mode(path: &Path) -> u32 {
+    fs::metadata(path).unwrap().permissions().mode() & 0o777
+}
+
+fn write_with_mode(path: &Path, mode: u32) {
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, "AGE-SECRET-KEY-1EXAMPLE\n").unwrap();
+    fs::set_permissions(path, fs::Permissions::from_mode(mode)).unwrap();
+}
+
+#[test]
+fn test_private_dir_and_file_modes() {
+    let dir = temp_dir("perms-create");
+    let keys_dir = dir.join("sops/age");
+    create_private_dir(&keys_dir).unwrap();
+    assert_eq!(mode(&keys_dir), 0o700);
+    assert_eq!(
+        mode(&dir.join("sops")),
+        0o700,
+        "Missing parents are private too"
+    );
+
+    let keys = keys_dir.join("keys.txt");
+    write_private(&keys, b"new").unwrap();
+    assert_eq!(mode(&keys), 0o600);
+
+    // Un archivo existente con permisos abiertos también se corrige al reescribirlo
+    fs::set_permissions(&keys, fs::Permissions::from_mode(0o644)).unwrap();
+    write_private(&keys, b"again").unwrap();
+    assert_eq!(mode(&keys), 0o600);
+    assert_eq!(fs::read_to_string(&keys).unwrap(), "again");
+
+    fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn test_appended_files_are_private() {
+    let dir = temp_dir("perms-append");
+    let log = dir.join("agesmith/audit.log");
+    append_private(&log, b"uno\n").unwrap();
+    assert_eq!(mode(log.parent().unwrap()), 0o700);
+    assert_eq!(mode(&log), 0o600);
+
+    fs::set_permissions(&log, fs::Permissions::from_mode(0o644)).unwrap();
+    append_private(&log, b"dos\n").unwrap();
+    assert_eq!(mode(&log), 0o600);
+    assert_eq!(fs::read_to_string(&log).unwrap(), "uno\ndos\n");
+
+    fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn test_insecure_mode_detection() {
+    let dir = temp_dir("perms-modes");
+    let file = dir.join("keys.txt");
+    for (bits, insecure) in [(0o600, false), (0o400, false), (0o640, true), (0o604, true)] {
+        write_with_mode(&file, bits);
+        assert_eq!(insecure_mode(&file).is_some(), insecure, "mode {:o}", bits);
+    }
+
+    fs::set_permissions(&dir, fs::Permissions::from_mode(0o755)).unwrap();
+    restrict_permissions(&dir).unwrap();
+    assert_eq!(mode(&dir), 0o700);
+    assert_eq!(insecure_mode(&dir.join("missing")), None);
+
+    fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn test_startup_detects_readable_identity_files() {
+    let home = temp_dir("perms-home");
+    let env = KeyEnv {
+        home: Some(home.clone()),
+        ..KeyEnv::default()
+    };
+
+    let keys = home.join(".config/sops/age/keys.txt");
+    let extra = home.join("work/identities.txt");
+    let secure = home.join("secure.txt");
+    write_with_mode(&keys, 0o644);
+    write_with_mode(&extra, 0o640);
+    write_with_mode(&secure, 0o600);
+
+    let extra_files = vec![
+        "~/work/identities.txt".to_string(),
+        "~/secure.txt".to_string(),
+        "~/missing.txt".to_string(),
+    ];
+    let found = env.insecure_identity_files(&extra_files).unwrap();
+    assert_eq!(found, vec![(keys.clone(), 0o644), (extra.clone(), 0o640)]);
+
+    for (path, _) in &found {
+        restrict_permissions(path).unwrap();
+    }
+    assert!(env
+        .insecure_identity_files(&extra_files)
+        .unwrap()
+        .is_empty());
+    assert_eq!(mode(&keys), 0o600);
+
+    fs::remove_dir_all(&home).ok();
+}
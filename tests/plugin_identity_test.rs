@@ -0,0 +1,168 @@
+/// Tests para identidades y recipients de plugins age (age-plugin-*)
+#[allow(dead_code)]
+#[path = "../src/perms.rs"]
+mod perms;
+#[allow(dead_code)]
+#[path = "../src/sops.rs"]
+mod sops;
+
+#[allow(dead_code)]
+mod helpers;
+
+use helpers::temp_dir;
+use sops::{
+    flatten_json, parse_age_keys, plugin_in_path, plugin_name, set_sops_identity,
+    sops_decrypt_command, KeySource,
+};
+use std::{
+    ffi::OsStr,
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+const YUBIKEY_IDENTITY: &str =
+    "AGE-PLUGIN-YUBIKEY-1QQSZQ5PQYQQSZQGPQYQSZQGPQYQSZQGPQYQSZQGPQYQSZQGPQYQSZQGPQYQS2LVE0V";
+const YUBIKEY_RECIPIENT: &str =
+    "age1yubikey1qwt50d05nh5vutpdzmlg5wn80xq5negm4uj9ghv0snvdd3yysf5yw3rhl3t";
+
+#[test]
+fn test_plugin_names() {
+    assert_eq!(plugin_name(YUBIKEY_IDENTITY).as_deref(), Some("yubikey"));
+    assert_eq!(plugin_name(YUBIKEY_RECIPIENT).as_deref(), Some("yubikey"));
+    assert_eq!(
+        plugin_name("AGE-PLUGIN-SE-1QJPQZD33SGQNYVQ").as_deref(),
+        Some("se")
+    );
+    assert_eq!(
+        plugin_name("age1tpm1qg86fn5esp30u9h6jy6zvu9gcsvnac09vn8jzjxt8s3qtlcv5h2x287wm36")
+            .as_deref(),
+        Some("tpm")
+    );
+
+    // Llaves nativas y SSH no son de plugins
+    assert_eq!(
+        plugin_name("age1ql3z7hjy54pw3hyww5ayyfg7zqgvc7w3j2elw8zmrj2kg5sfn9aqmcac8p"),
+        None
+    );
+    assert_eq!(
+        plugin_name("AGE-SECRET-KEY-1QQQQQQQQQQQQQQQQQQQQQQQQQQQQQQQQQQQQQQQQQQQQQQQQQQQQQQQ"),
+        None
+    );
+    assert_eq!(plugin_name("ssh-ed25519 AAAAC3Nza"), None);
+}
+
+#[test]
+fn test_plugin_identities_are_parsed_with_their_recipient() {
+    let content = format!(
+        "#       Serial: 1234567, Slot: 1\n\
+         #         Name: agesmith\n\
+         #    Recipient: {}\n\
+         {}\n\
+         \n\
+         # no recipient comment\n\
+         AGE-PLUGIN-SE-1QJPQZD33SGQNYVQ\n",
+        YUBIKEY_RECIPIENT, YUBIKEY_IDENTITY
+    );
+
+    let keys = parse_age_keys(&content, &KeySource::Env);
+    assert_eq!(keys.len(), 2);
+
+    assert_eq!(keys[0].key, YUBIKEY_IDENTITY);
+    assert_eq!(keys[0].public_key.as_deref(), Some(YUBIKEY_RECIPIENT));
+    assert_eq!(keys[0].plugin().as_deref(), Some("yubikey"));
+    assert!(keys[0].is_recipient_of(&[YUBIKEY_RECIPIENT.to_string()]));
+
+    assert_eq!(keys[1].plugin().as_deref(), Some("se"));
+    assert_eq!(
+        keys[1].public_key, None,
+        "A recipient hint must not leak to the next identity"
+    );
+    assert_eq!(keys[1].comment.as_deref(), Some("no recipient comment"));
+}
+
+#[test]
+fn test_plugin_identity_is_passed_inline_to_sops() {
+    let mut cmd = Command::new("sops");
+    set_sops_identity(&mut cmd, YUBIKEY_IDENTITY);
+    let envs: Vec<_> = cmd.get_envs().collect();
+    assert_eq!(
+        envs,
+        vec![(
+            OsStr::new("SOPS_AGE_KEY"),
+            Some(OsStr::new(YUBIKEY_IDENTITY))
+        )]
+    );
+
+    let mut cmd = Command::new("sops");
+    set_sops_identity(&mut cmd, "/home/alice/.ssh/id_ed25519");
+    assert_eq!(
+        cmd.get_envs().next().unwrap().0,
+        OsStr::new("SOPS_AGE_SSH_PRIVATE_KEY_FILE")
+    );
+}
+
+/// Escribe un script ejecutable en `dir`
+#[cfg(unix)]
+fn write_stub(dir: &Path, name: &str, script: &str) {
+    use std::os::unix::fs::PermissionsExt;
+
+    let path = dir.join(name);
+    fs::write(&path, script).unwrap();
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn test_detects_installed_plugin_binary() {
+    let dir = temp_dir("plugin-lookup");
+    write_stub(&dir, "age-plugin-agesmithstub", "#!/bin/sh\nexit 0\n");
+    fs::write(dir.join("age-plugin-agesmithdata"), "").unwrap();
+
+    // Se pasa la lista de directorios: cambiar el PATH afectaría a otros tests en paralelo
+    let paths = std::env::join_paths([PathBuf::from("/nonexistent"), dir.clone()]).unwrap();
+    assert!(plugin_in_path("agesmithstub", &paths));
+    assert!(!plugin_in_path("agesmithdata", &paths));
+    assert!(!plugin_in_path("agesmithmissing", &paths));
+
+    fs::remove_dir_all(&dir).ok();
+}
+
+#[cfg(unix)]
+#[test]
+fn test_decryption_passes_plugin_identity_to_sops() {
+    // `sops` falso que responde con la identidad y el archivo que recibió
+    let dir = temp_dir("plugin-sops");
+    write_stub(
+        &dir,
+        "sops",
+        "#!/bin/sh\nprintf '{\"identity\": \"%s\", \"file\": \"%s\"}' \"$SOPS_AGE_KEY\" \"$4\"\n",
+    );
+    let file = dir.join("secrets.yaml");
+
+    let mut cmd = sops_decrypt_command(&file, Some(YUBIKEY_IDENTITY));
+    assert!(
+        cmd.get_envs().all(|(name, _)| name != "SOPS_AGE_KEY_FILE"),
+        "The key file must not override the selected identity"
+    );
+    // Solo el PATH del proceso hijo: el `sops` falso se busca ahí
+    cmd.env("PATH", &dir);
+    let output = cmd.output().unwrap();
+    assert!(output.status.success());
+
+    let mut secrets = Vec::new();
+    flatten_json(
+        "",
+        &serde_json::from_slice(&output.stdout).unwrap(),
+        &mut secrets,
+    );
+    assert_eq!(
+        secrets,
+        vec![
+            ("file".to_string(), file.display().to_string()),
+            ("identity".to_string(), YUBIKEY_IDENTITY.to_string()),
+        ]
+    );
+
+    fs::remove_dir_all(&dir).ok();
+}
@@ -0,0 +1,88 @@
+/// Tests para el manejo de valores descifrados en memoria
+#[allow(dead_code)]
+#[path = "../src/perms.rs"]
+mod perms;
+#[allow(dead_code)]
+#[path = "../src/secmem.rs"]
+mod secmem;
+#[allow(dead_code)]
+#[path = "../src/sops.rs"]
+mod sops;
+
+use secmem::{set_core_dumps, SecretList};
+use serde_json::json;
+use sops::{flatten_json, zeroize_json};
+
+fn sample() -> SecretList {
+    vec![
+        ("API_KEY".to_string(), "abc123".to_string()),
+        ("DB_PASSWORD".to_string(), "hunter2".to_string()),
+    ]
+    .into()
+}
+
+#[test]
+fn test_secret_list_behaves_like_vec() {
+    let mut secrets = sample();
+    assert_eq!(secrets.len(), 2);
+    assert_eq!(secrets[1].0, "DB_PASSWORD");
+    assert!(secrets.iter().any(|(_, v)| v == "abc123"));
+
+    secrets.push(("NEW".to_string(), "value".to_string()));
+    assert_eq!(secrets.last().unwrap().1, "value");
+}
+
+#[test]
+fn test_replace_and_remove() {
+    let mut secrets = sample();
+
+    let previous = secrets.replace_value(0, "rotated".to_string());
+    assert_eq!(previous.as_str(), "abc123");
+    assert_eq!(secrets[0].1, "rotated");
+
+    secrets.set_value(0, "again".to_string());
+    assert_eq!(secrets[0].1, "again");
+
+    secrets.set_key(0, "TOKEN".to_string());
+    assert_eq!(secrets[0], ("TOKEN".to_string(), "again".to_string()));
+
+    assert_eq!(secrets.remove(1), "DB_PASSWORD");
+    assert_eq!(secrets.len(), 1);
+
+    secrets.clear();
+    assert!(secrets.is_empty());
+}
+
+#[test]
+fn test_parsed_json_is_wiped_after_flattening() {
+    let mut json = json!({"db": {"password": "hunter2", "port": 5432}, "tokens": ["abc"]});
+    let mut secrets = Vec::new();
+    flatten_json("", &json, &mut secrets);
+    zeroize_json(&mut json);
+
+    assert!(secrets.contains(&("db.password".to_string(), "hunter2".to_string())));
+    assert_eq!(json["db"]["password"], "");
+    assert_eq!(json["tokens"][0], "");
+    assert_eq!(json["db"]["port"], 5432);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_core_dumps_are_disabled_and_restored() {
+    fn core_limit() -> libc::rlimit {
+        let mut limit = libc::rlimit {
+            rlim_cur: 0,
+            rlim_max: 0,
+        };
+        unsafe { libc::getrlimit(libc::RLIMIT_CORE, &mut limit) };
+        limit
+    }
+
+    let original = core_limit();
+    set_core_dumps(false);
+    assert_eq!(core_limit().rlim_cur, 0);
+    assert_eq!(core_limit().rlim_max, original.rlim_max);
+
+    set_core_dumps(true);
+    assert_eq!(core_limit().rlim_cur, original.rlim_cur);
+}
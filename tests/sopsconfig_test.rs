@@ -0,0 +1,431 @@
+/// Tests para el modelo del `.sops.yaml`: lectura, validación, edición de campos y vista previa
+#[allow(dead_code)]
+#[path = "../src/keystore.rs"]
+mod keystore;
+#[allow(dead_code)]
+#[path = "../src/perms.rs"]
+mod perms;
+#[allow(dead_code)]
+#[path = "../src/sops.rs"]
+mod sops;
+#[allow(dead_code)]
+#[path = "../src/sopsconfig.rs"]
+mod sopsconfig;
+
+#[allow(dead_code)]
+mod helpers;
+
+use helpers::temp_dir;
+use sopsconfig::{load_custom_templates, CreationRule, Resolution, SopsConfig, SopsTemplate};
+use std::fs;
+
+const ALICE: &str = "age1ql3z7hjy54pw3hyww5ayyfg7zqgvc7w3j2elw8zmrj2kg5sfn9aqmcac8p";
+const BOB: &str = "age1lggyhqrw2nlhcxprm67z43rta597azn8gknawjehu9d9dl0jq3yqqvfafg";
+
+fn rule(path_regex: &str, age: &[&str]) -> CreationRule {
+    CreationRule {
+        path_regex: Some(path_regex.to_string()),
+        age: age.iter().map(|r| r.to_string()).collect(),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_parse_and_round_trip_keeps_unknown_settings() {
+    let yaml = format!(
+        r#"
+stores:
+  yaml:
+    indent: 4
+creation_rules:
+  - path_regex: ^prod/.*\.yaml$
+    encrypted_regex: ^(data|stringData)$
+    age: >-
+      {ALICE},
+      {BOB}
+  - path_regex: \.env$
+    pgp: 85D77543B3D624B63CEA9E6DBC17301B491B3F21
+    key_groups:
+      - age:
+          - {ALICE}
+        pgp:
+          - 85D77543B3D624B63CEA9E6DBC17301B491B3F21
+"#
+    );
+    let config = SopsConfig::parse(&yaml).unwrap();
+    assert_eq!(config.creation_rules.len(), 2);
+    assert_eq!(config.creation_rules[0].age, vec![ALICE, BOB]);
+    assert_eq!(config.creation_rules[1].recipients(), vec![ALICE]);
+    assert!(config.creation_rules[1].other.contains_key("pgp"));
+    assert!(config.other.contains_key("stores"));
+    assert!(config.validate().is_empty(), "{:?}", config.validate());
+
+    let again = SopsConfig::parse(&config.to_yaml().unwrap()).unwrap();
+    assert_eq!(again, config);
+    assert!(SopsConfig::parse("").unwrap().creation_rules.is_empty());
+    assert!(SopsConfig::parse("creation_rules: [").is_err());
+}
+
+#[test]
+fn test_validation_reports_each_problem() {
+    let mut selectors = rule(r"\.yaml$", &[ALICE]);
+    selectors.encrypted_regex = Some("^data$".to_string());
+    selectors.other.insert(
+        "unencrypted_regex".to_string(),
+        serde_yaml::Value::String("^id$".to_string()),
+    );
+
+    let config = SopsConfig {
+        creation_rules: vec![
+            rule(r"\.yaml$", &[ALICE]),
+            rule("([unclosed", &[ALICE]),
+            rule(r"\.env$", &["age1notarecipient"]),
+            rule(r"\.json$", &[]),
+            selectors,
+        ],
+        ..Default::default()
+    };
+    let errors = config.validate();
+    let rules: Vec<usize> = errors.iter().map(|(i, _)| *i).collect();
+    assert_eq!(rules, vec![1, 2, 3, 4]);
+    assert!(errors[0].1.starts_with("path_regex"));
+    assert!(errors[1].1.contains("age1notarecipient"));
+    assert!(errors[3].1.contains("unencrypted_regex"));
+
+    let dir = temp_dir("sopscfg-save");
+    let path = dir.join(".sops.yaml");
+    assert!(config.save(&path).is_err());
+    assert!(!path.exists(), "Invalid configs are never written");
+
+    let valid = SopsConfig {
+        creation_rules: vec![rule(r"\.yaml$", &[ALICE])],
+        ..Default::default()
+    };
+    valid.save(&path).unwrap();
+    assert_eq!(SopsConfig::load(&path).unwrap(), valid);
+    fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn test_field_editing() {
+    let mut rule = rule(r"\.yaml$", &[ALICE]);
+    rule.key_groups = vec![sopsconfig::KeyGroup {
+        age: vec![ALICE.to_string()],
+        other: [(
+            "pgp".to_string(),
+            serde_yaml::Value::String("FINGERPRINT".to_string()),
+        )]
+        .into_iter()
+        .collect(),
+    }];
+
+    rule.set_field(2, &format!(" {} , {} ", ALICE, BOB));
+    assert_eq!(rule.age, vec![ALICE, BOB]);
+    assert_eq!(rule.field_text(2), format!("{},{}", ALICE, BOB));
+
+    rule.set_field(3, &format!("{} | {}", BOB, ALICE));
+    assert_eq!(rule.key_groups.len(), 2);
+    assert_eq!(rule.key_groups[0].age, vec![BOB]);
+    assert!(
+        rule.key_groups[0].other.contains_key("pgp"),
+        "Existing groups keep their other keys"
+    );
+    assert_eq!(rule.field_text(3), format!("{} | {}", BOB, ALICE));
+
+    rule.set_field(1, "  ");
+    assert_eq!(rule.encrypted_regex, None);
+    rule.set_field(0, r"^secrets/");
+    assert_eq!(rule.path_regex.as_deref(), Some("^secrets/"));
+}
+
+#[test]
+fn test_preview_uses_first_matching_rule() {
+    let dir = temp_dir("sopscfg-tree");
+    fs::create_dir_all(dir.join("prod")).unwrap();
+    fs::create_dir_all(dir.join(".git")).unwrap();
+    for file in [
+        "prod/db.yaml",
+        "app.yaml",
+        "notes.txt",
+        ".sops.yaml",
+        ".git/config.yaml",
+    ] {
+        fs::write(dir.join(file), "a: b\n").unwrap();
+    }
+
+    let config = SopsConfig {
+        creation_rules: vec![
+            rule(r"^prod/.*\.yaml$", &[ALICE]),
+            rule(r"\.yaml$", &[BOB]),
+            rule(r"^prod/", &[BOB]),
+        ],
+        ..Default::default()
+    };
+    assert_eq!(config.rule_for("prod/db.yaml"), Some(0));
+    assert_eq!(config.rule_for("notes.txt"), None);
+
+    let preview = config.preview(&dir);
+    assert_eq!(preview[0], vec![dir.join("prod/db.yaml")]);
+    assert_eq!(preview[1], vec![dir.join("app.yaml")]);
+    assert!(preview[2].is_empty(), "Shadowed by the earlier rules");
+
+    fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn test_builtin_templates_generate_distinct_valid_configs() {
+    let recipients = vec![ALICE.to_string(), BOB.to_string()];
+    let configs: Vec<SopsConfig> = SopsTemplate::builtin()
+        .iter()
+        .map(|t| t.config("secrets.yaml", &recipients).unwrap())
+        .collect();
+    for (i, config) in configs.iter().enumerate() {
+        assert!(config.validate().is_empty(), "template {}", i);
+        assert!(config.rule_for("secrets.yaml").is_some(), "template {}", i);
+        assert!(config.creation_rules.iter().all(|r| r.age == recipients));
+        for other in &configs[i + 1..] {
+            assert_ne!(config, other);
+        }
+    }
+
+    let by_format = &configs[0];
+    assert_eq!(by_format.rule_for("app/.env"), Some(0));
+    assert_eq!(by_format.rule_for("config.json"), Some(1));
+    assert_eq!(by_format.rule_for("values.yml"), Some(2));
+
+    let k8s = &configs[3];
+    assert_eq!(
+        k8s.creation_rules[0].encrypted_regex.as_deref(),
+        Some("^(data|stringData)$")
+    );
+
+    // Un archivo fuera de los entornos recibe su propia regla al final
+    let by_env = SopsTemplate::ByEnvironment
+        .config("secrets.env", &recipients)
+        .unwrap();
+    assert_eq!(by_env.rule_for("prod/app.yaml"), Some(2));
+    assert_eq!(
+        by_env.creation_rules.last().unwrap().path_regex.as_deref(),
+        Some(r"(^|/)secrets\.env$")
+    );
+    let k8s_env = SopsTemplate::Kubernetes
+        .config("secrets.env", &recipients)
+        .unwrap();
+    assert_eq!(k8s_env.rule_for("secrets.env"), Some(1));
+    assert_eq!(k8s_env.creation_rules[1].encrypted_regex, None);
+}
+
+#[test]
+fn test_custom_templates_from_directory() {
+    let dir = temp_dir("sopscfg-tpl");
+    fs::write(
+        dir.join("team.yaml"),
+        "# Team secrets only\ncreation_rules:\n  - path_regex: {{path_regex}}\n    encrypted_regex: ^secret_\n    age: {{recipients}}\n",
+    )
+    .unwrap();
+    fs::write(dir.join("notes.txt"), "ignored").unwrap();
+
+    let templates = load_custom_templates(&dir).unwrap();
+    assert_eq!(templates.len(), 1);
+    let SopsTemplate::Custom {
+        name, description, ..
+    } = &templates[0]
+    else {
+        panic!("expected a custom template");
+    };
+    assert_eq!(name, "team");
+    assert_eq!(description, "Team secrets only");
+
+    let config = templates[0]
+        .config("app.env", &[ALICE.to_string()])
+        .unwrap();
+    assert_eq!(config.creation_rules.len(), 1);
+    assert_eq!(config.rule_for("app.env"), Some(0));
+    assert_eq!(config.creation_rules[0].age, vec![ALICE]);
+    assert_eq!(
+        config.creation_rules[0].encrypted_regex.as_deref(),
+        Some("^secret_")
+    );
+
+    fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn test_add_file_rule_respects_rule_order() {
+    let alice = vec![ALICE.to_string()];
+    let both = vec![ALICE.to_string(), BOB.to_string()];
+    let mut config = SopsConfig {
+        creation_rules: vec![rule(r"^prod/", &[ALICE]), rule(r"\.yaml$", &[BOB])],
+        ..Default::default()
+    };
+
+    // Sin regla que lo cubra: se añade al final
+    assert_eq!(config.add_file_rule("app.env", &alice), (2, true));
+    assert_eq!(config.rule_for("app.env"), Some(2));
+
+    // Con los mismos recipients no hace falta otra regla
+    assert_eq!(config.add_file_rule("prod/db.env", &alice), (0, false));
+    assert_eq!(config.creation_rules.len(), 3);
+
+    // Con otros recipients va delante de la regla que lo resolvía
+    assert_eq!(config.add_file_rule("prod/db.yaml", &both), (0, true));
+    assert_eq!(config.rule_for("prod/db.yaml"), Some(0));
+    assert_eq!(config.rule_for("prod/other.yaml"), Some(1));
+    assert_eq!(config.creation_rules.len(), 4);
+
+    // La regla propia del archivo se actualiza en vez de duplicarse
+    assert_eq!(config.add_file_rule("app.env", &both), (3, true));
+    assert_eq!(config.creation_rules.len(), 4);
+    assert_eq!(config.creation_rules[3].age, both);
+    assert!(config.validate().is_empty());
+}
+
+#[test]
+fn test_adding_a_rule_keeps_comments() {
+    let dir = temp_dir("sopscfg-comments");
+    let path = dir.join(".sops.yaml");
+    let original = format!(
+        "# Configuración del equipo\n\
+         creation_rules:\n  \
+         # Producción: solo Alice\n  \
+         - path_regex: ^prod/\n    \
+           age: {alice} # llave de Alice\n  \
+         - path_regex: \\.yaml$\n    \
+           age: {bob}\n\
+         # stores al final\n\
+         stores:\n  \
+           yaml:\n    \
+             indent: 4\n",
+        alice = ALICE,
+        bob = BOB
+    );
+    fs::write(&path, &original).unwrap();
+    let both = vec![ALICE.to_string(), BOB.to_string()];
+
+    // Delante de la regla que lo resolvía, antes de su comentario
+    let mut config = SopsConfig::load(&path).unwrap();
+    let (idx, _) = config.add_file_rule("prod/db.env", &both);
+    config.save_rule(&path, idx, false).unwrap();
+    let saved = fs::read_to_string(&path).unwrap();
+    assert_eq!(SopsConfig::load(&path).unwrap(), config);
+    for line in original.lines() {
+        assert!(saved.contains(line), "falta {:?}", line);
+    }
+    let new_rule = saved.find("prod/db").unwrap();
+    assert!(new_rule < saved.find("# Producción").unwrap());
+    assert!(saved.find("creation_rules:").unwrap() < new_rule);
+
+    // Al final de la lista, antes del resto de claves
+    let (idx, _) = config.add_file_rule("app.env", &both);
+    config.save_rule(&path, idx, false).unwrap();
+    let saved = fs::read_to_string(&path).unwrap();
+    assert_eq!(SopsConfig::load(&path).unwrap(), config);
+    assert!(saved.find("app").unwrap() < saved.find("# stores al final").unwrap());
+
+    // La regla propia del archivo se reemplaza sin tocar las demás
+    let alice = vec![ALICE.to_string()];
+    let (idx, _) = config.add_file_rule("app.env", &alice);
+    config.save_rule(&path, idx, true).unwrap();
+    let saved = fs::read_to_string(&path).unwrap();
+    assert_eq!(SopsConfig::load(&path).unwrap(), config);
+    assert!(saved.contains("# llave de Alice"));
+    assert!(saved.contains("    indent: 4"));
+    assert!(!dir.join("..sops.yaml.tmp").exists());
+
+    // Una lista en línea con comentarios no se reescribe
+    let inline = format!("# equipo\ncreation_rules: [{{age: {}}}]\n", BOB);
+    fs::write(&path, &inline).unwrap();
+    let mut config = SopsConfig::load(&path).unwrap();
+    let (idx, _) = config.add_file_rule("app.env", &alice);
+    assert!(config.save_rule(&path, idx, false).is_err());
+    assert_eq!(fs::read_to_string(&path).unwrap(), inline);
+
+    fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn test_shadowed_rules_and_resolution() {
+    let dir = temp_dir("sopscfg-shadow");
+    fs::create_dir_all(dir.join("prod/api")).unwrap();
+    for file in ["prod/db.yaml", "prod/api/app.env", "dev.yaml"] {
+        fs::write(dir.join(file), "a: b\n").unwrap();
+    }
+
+    let catch_all = CreationRule {
+        age: vec![ALICE.to_string()],
+        ..Default::default()
+    };
+    let config = SopsConfig {
+        creation_rules: vec![
+            rule(r"^prod/", &[ALICE]),
+            rule(r"^prod/.*\.yaml$", &[BOB]),
+            rule(r"\.yaml$", &[BOB]),
+            rule(r"^prod/", &[BOB]),
+            catch_all,
+            rule(r"\.json$", &[BOB]),
+        ],
+        ..Default::default()
+    };
+    // 1: todos sus archivos los resuelve la 0; 3: misma regex que la 0; 5: tras una regla sin path_regex
+    assert_eq!(config.shadowed_rules(&dir), vec![(1, 0), (3, 0), (5, 4)]);
+
+    fs::write(
+        dir.join(".sops.yaml"),
+        format!(
+            "creation_rules:\n  - path_regex: ^prod/api/\n    age: {}\n",
+            ALICE
+        ),
+    )
+    .unwrap();
+    let resolution = Resolution::resolve(&dir.join("prod/api/app.env"))
+        .unwrap()
+        .unwrap();
+    assert_eq!(resolution.config_path, dir.join(".sops.yaml"));
+    assert_eq!(resolution.relative, "prod/api/app.env");
+    assert_eq!(resolution.rule, Some(0));
+
+    let unmatched = Resolution::resolve(&dir.join("dev.yaml")).unwrap().unwrap();
+    assert_eq!(unmatched.rule, None);
+
+    fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn test_recipient_drift_against_rule() {
+    let carol = "ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAICarol";
+    let config = SopsConfig {
+        creation_rules: vec![
+            rule(r"^prod/", &[ALICE, carol]),
+            CreationRule {
+                path_regex: Some(r"^legacy/".to_string()),
+                other: [(
+                    "pgp".to_string(),
+                    serde_yaml::Value::String("FINGERPRINT".to_string()),
+                )]
+                .into_iter()
+                .collect(),
+                ..Default::default()
+            },
+        ],
+        ..Default::default()
+    };
+    let resolution = |relative: &str| Resolution {
+        config_path: ".sops.yaml".into(),
+        rule: config.rule_for(relative),
+        config: config.clone(),
+        relative: relative.to_string(),
+    };
+
+    // Las llaves SSH coinciden aunque el comentario sea distinto
+    let in_sync = vec![format!("{} carol@laptop", carol), ALICE.to_string()];
+    assert_eq!(resolution("prod/db.yaml").drift(&in_sync), None);
+
+    let drift = resolution("prod/db.yaml")
+        .drift(&[ALICE.to_string(), BOB.to_string()])
+        .unwrap();
+    assert_eq!(drift.missing, vec![carol]);
+    assert_eq!(drift.extra, vec![BOB]);
+
+    assert_eq!(resolution("legacy/app.env").drift(&in_sync), None);
+    assert_eq!(resolution("other.env").drift(&in_sync), None);
+}
@@ -0,0 +1,123 @@
+/// Tests para el informe de uso de llaves sobre los archivos SOPS de un árbol
+#[allow(dead_code)]
+#[path = "../src/addressbook.rs"]
+mod addressbook;
+#[allow(dead_code)]
+#[path = "../src/keystore.rs"]
+mod keystore;
+#[allow(dead_code)]
+#[path = "../src/perms.rs"]
+mod perms;
+#[allow(dead_code)]
+#[path = "../src/sops.rs"]
+mod sops;
+#[allow(dead_code)]
+#[path = "../src/usage.rs"]
+mod usage;
+
+#[allow(dead_code)]
+mod helpers;
+
+use addressbook::AddressBook;
+use helpers::temp_dir;
+use keystore::display_relative;
+use sops::{AgeKey, KeySource};
+use std::{fs, path::PathBuf};
+use usage::UsageReport;
+
+const ALICE: &str = "age1ql3z7hjy54pw3hyww5ayyfg7zqgvc7w3j2elw8zmrj2kg5sfn9aqmcac8p";
+const BOB: &str = "age1lggyhqrw2nlhcxprm67z43rta597azn8gknawjehu9d9dl0jq3yqqvfafg";
+const CAROL: &str = "ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAICarol";
+
+fn key(secret: &str, comment: &str, public_key: &str) -> AgeKey {
+    AgeKey {
+        key: secret.to_string(),
+        comment: Some(comment.to_string()),
+        public_key: Some(public_key.to_string()),
+        source: KeySource::Env,
+    }
+}
+
+fn files(entries: &[(&str, &[&str])]) -> Vec<(PathBuf, Vec<String>)> {
+    entries
+        .iter()
+        .map(|(path, recipients)| {
+            (
+                PathBuf::from("/repo").join(path),
+                recipients.iter().map(|r| r.to_string()).collect(),
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn test_report_groups_files_by_identity_and_recipient() {
+    let files = files(&[
+        ("a.yaml", &[ALICE]),
+        ("shared.yaml", &[ALICE, BOB]),
+        ("bob.yaml", &[BOB]),
+        ("carol.env", &[&format!("{} carol@laptop", CAROL)]),
+    ]);
+    let keys = vec![
+        key("AGE-SECRET-KEY-1ALICE", "alice", ALICE),
+        key("AGE-SECRET-KEY-1OLD", "old", "age1unused"),
+    ];
+    let book = AddressBook::parse(&format!("[people.bob]\nkeys = [\"{}\"]\n", BOB)).unwrap();
+
+    let report = UsageReport::build(&PathBuf::from("/repo"), &files, &keys, &book);
+    assert_eq!(report.total_files, 4);
+
+    assert_eq!(report.identities.len(), 2);
+    assert_eq!(report.identities[0].files.len(), 2);
+    assert!(
+        report.identities[1].files.is_empty(),
+        "Unused keys are listed"
+    );
+
+    // Los recipients con nombre en la libreta aparecen primero
+    let names: Vec<_> = report.recipients.iter().map(|r| r.name.clone()).collect();
+    assert_eq!(names, vec![Some("bob".to_string()), None]);
+    assert_eq!(report.recipients[0].files.len(), 2);
+    assert_eq!(
+        report.recipients[1].recipient,
+        format!("{} carol@laptop", CAROL)
+    );
+
+    let orphaned: Vec<String> = report
+        .orphaned
+        .iter()
+        .map(|p| display_relative(&report.root, p).to_string())
+        .collect();
+    assert_eq!(orphaned, vec!["bob.yaml", "carol.env"]);
+}
+
+#[test]
+fn test_scan_reads_sops_files_from_tree() {
+    let dir = temp_dir("usage");
+    fs::create_dir_all(dir.join("nested")).unwrap();
+    fs::write(
+        dir.join("app.json"),
+        format!(
+            r#"{{"a": "ENC[x]", "sops": {{"age": [{{"recipient": "{}"}}]}}}}"#,
+            ALICE
+        ),
+    )
+    .unwrap();
+    fs::write(
+        dir.join("nested/db.yaml"),
+        format!(
+            "password: ENC[x]\nsops:\n    age:\n        - recipient: {}\n",
+            BOB
+        ),
+    )
+    .unwrap();
+    fs::write(dir.join("README.md"), "# not encrypted\n").unwrap();
+
+    let keys = vec![key("AGE-SECRET-KEY-1ALICE", "alice", ALICE)];
+    let report = UsageReport::scan(&dir, &keys, &AddressBook::default());
+    assert_eq!(report.total_files, 2);
+    assert_eq!(report.identities[0].files, vec![dir.join("app.json")]);
+    assert_eq!(report.orphaned, vec![dir.join("nested/db.yaml")]);
+
+    fs::remove_dir_all(&dir).ok();
+}